@@ -304,6 +304,45 @@ fn clone_from_large(b: &mut Bencher) {
     })
 }
 
+// Collecting from an iterator with an exact size hint allocates the table
+// once, while hiding the size hint makes the table grow (and rehash) as it is
+// filled.
+#[bench]
+fn collect_sized(b: &mut Bencher) {
+    let pairs: Vec<(usize, usize)> = (0..SIZE * 1000).map(|i| (i, i)).collect();
+
+    b.iter(|| black_box(pairs.iter().copied().collect::<AHashMap<_, _>>()))
+}
+
+#[bench]
+fn collect_unsized(b: &mut Bencher) {
+    let pairs: Vec<(usize, usize)> = (0..SIZE * 1000).map(|i| (i, i)).collect();
+
+    b.iter(|| {
+        black_box(
+            pairs
+                .iter()
+                .copied()
+                .filter(|_| true)
+                .collect::<AHashMap<_, _>>(),
+        )
+    })
+}
+
+// Reserving space for a single element before each insertion must not
+// reallocate on every call, since each growth at least doubles the table.
+#[bench]
+fn reserve_one_insert(b: &mut Bencher) {
+    b.iter(|| {
+        let mut m = AHashMap::default();
+        for i in 0..SIZE * 1000 {
+            m.reserve(1);
+            m.insert(i, i);
+        }
+        black_box(&mut m);
+    })
+}
+
 #[bench]
 fn rehash_in_place(b: &mut Bencher) {
     b.iter(|| {
@@ -329,3 +368,250 @@ fn rehash_in_place(b: &mut Bencher) {
         }
     });
 }
+
+// Batched random lookups in a table much larger than the CPU caches, with and
+// without prefetching the probe position of upcoming lookups. Each iteration
+// looks up the next batch of keys so that the table is never warm in cache.
+#[cfg(feature = "raw")]
+mod raw_prefetch {
+    use super::RandomKeys;
+    use hashbrown::raw::RawTable;
+    use test::{black_box, Bencher};
+
+    const TABLE_SIZE: usize = 1 << 22;
+    const BATCH: usize = 1024;
+    const LOOKAHEAD: usize = 8;
+
+    fn hash(x: &usize) -> u64 {
+        (*x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn setup() -> (RawTable<usize>, Vec<u64>) {
+        let mut table = RawTable::with_capacity(TABLE_SIZE);
+        for i in RandomKeys::new().take(TABLE_SIZE) {
+            table.insert(hash(&i), i, hash);
+        }
+        let hashes = RandomKeys::new()
+            .take(TABLE_SIZE)
+            .map(|i| hash(&i))
+            .collect();
+        (table, hashes)
+    }
+
+    #[bench]
+    fn lookup_batch(b: &mut Bencher) {
+        let (table, hashes) = setup();
+        let mut batches = hashes.chunks_exact(BATCH).cycle();
+        b.iter(|| {
+            for &h in batches.next().unwrap() {
+                black_box(table.find(h, |x| hash(x) == h));
+            }
+        })
+    }
+
+    #[bench]
+    fn lookup_batch_prefetch(b: &mut Bencher) {
+        let (table, hashes) = setup();
+        let mut batches = hashes.chunks_exact(BATCH).cycle();
+        b.iter(|| {
+            let batch = batches.next().unwrap();
+            for (i, &h) in batch.iter().enumerate() {
+                if let Some(&next) = batch.get(i + LOOKAHEAD) {
+                    table.prefetch(next);
+                }
+                black_box(table.find(h, |x| hash(x) == h));
+            }
+        })
+    }
+}
+
+// Compares the thresholded parallel value transform against unconditionally
+// using rayon and a plain serial loop, across map sizes around the threshold.
+#[cfg(feature = "rayon")]
+macro_rules! bench_par_for_each_value_mut {
+    ($($size:literal => $par:ident, $rayon:ident, $serial:ident;)*) => {$(
+        #[bench]
+        fn $par(b: &mut Bencher) {
+            let mut m: AHashMap<usize, usize> = (0..$size).map(|i| (i, i)).collect();
+            b.iter(|| {
+                m.par_for_each_value_mut(|v| *v = v.wrapping_mul(3));
+                black_box(&mut m);
+            })
+        }
+
+        #[bench]
+        fn $rayon(b: &mut Bencher) {
+            use rayon::iter::ParallelIterator;
+            let mut m: AHashMap<usize, usize> = (0..$size).map(|i| (i, i)).collect();
+            b.iter(|| {
+                m.par_values_mut().for_each(|v| *v = v.wrapping_mul(3));
+                black_box(&mut m);
+            })
+        }
+
+        #[bench]
+        fn $serial(b: &mut Bencher) {
+            let mut m: AHashMap<usize, usize> = (0..$size).map(|i| (i, i)).collect();
+            b.iter(|| {
+                m.values_mut().for_each(|v| *v = v.wrapping_mul(3));
+                black_box(&mut m);
+            })
+        }
+    )*};
+}
+
+#[cfg(feature = "rayon")]
+bench_par_for_each_value_mut! {
+    256 => par_for_each_value_mut_256, par_values_mut_256, values_mut_256;
+    1024 => par_for_each_value_mut_1024, par_values_mut_1024, values_mut_1024;
+    4096 => par_for_each_value_mut_4096, par_values_mut_4096, values_mut_4096;
+    16384 => par_for_each_value_mut_16384, par_values_mut_16384, values_mut_16384;
+    65536 => par_for_each_value_mut_65536, par_values_mut_65536, values_mut_65536;
+}
+
+// Growing a `HashMap<u64, u64>` from empty spends most of its time moving
+// elements into each newly allocated table.
+#[bench]
+fn grow_insert_u64(b: &mut Bencher) {
+    b.iter(|| {
+        let mut m: AHashMap<u64, u64> = AHashMap::default();
+        for i in 0..(SIZE * 100) as u64 {
+            m.insert(i, i);
+        }
+        black_box(&mut m);
+    })
+}
+
+// Resizes a table in which most buckets are empty, as left behind by removals.
+#[bench]
+fn resize_sparse_u64(b: &mut Bencher) {
+    let mut base: AHashMap<u64, u64> = AHashMap::with_capacity(SIZE * 100);
+    for i in 0..(SIZE * 10) as u64 {
+        base.insert(i, i);
+    }
+    b.iter(|| {
+        let mut m = base.clone();
+        m.reserve(m.capacity() + 1);
+        black_box(&mut m);
+    })
+}
+
+// Clears a full table of 1M `u64`s. libtest has no way to exclude per-iteration
+// setup from the timing, so `refill` measures the setup alone and the cost of
+// `clear()` is the difference between the two benches.
+#[cfg(feature = "raw")]
+mod raw_clear {
+    use hashbrown::raw::RawTable;
+    use test::{black_box, Bencher};
+
+    const TABLE_SIZE: usize = 1_000_000;
+
+    fn setup() -> (RawTable<u64>, RawTable<u64>) {
+        let hasher = |x: &u64| x.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut full = RawTable::with_capacity(TABLE_SIZE);
+        for i in 0..TABLE_SIZE as u64 {
+            full.insert(hasher(&i), i, hasher);
+        }
+        let table = full.clone();
+        (full, table)
+    }
+
+    #[bench]
+    fn refill(b: &mut Bencher) {
+        let (full, mut table) = setup();
+        b.iter(|| {
+            table.clone_from(&full);
+            black_box(&mut table);
+        })
+    }
+
+    #[bench]
+    fn refill_and_clear(b: &mut Bencher) {
+        let (full, mut table) = setup();
+        b.iter(|| {
+            table.clone_from(&full);
+            table.clear();
+            black_box(&mut table);
+        })
+    }
+
+    // Clearing a table which is already empty needs no setup, so this times
+    // `clear()` on its own.
+    #[bench]
+    fn clear_empty(b: &mut Bencher) {
+        let (_, mut table) = setup();
+        table.clear();
+        b.iter(|| {
+            table.clear();
+            black_box(&mut table);
+        })
+    }
+}
+
+// Sums every value of a large table through the group-at-a-time fold and
+// through the generic iterator.
+#[cfg(feature = "raw")]
+mod raw_fold {
+    use hashbrown::raw::RawTable;
+    use test::{black_box, Bencher};
+
+    const TABLE_SIZE: usize = 1_000_000;
+
+    fn setup() -> RawTable<u64> {
+        let hasher = |x: &u64| x.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table = RawTable::with_capacity(TABLE_SIZE);
+        for i in 0..TABLE_SIZE as u64 {
+            table.insert(hasher(&i), i, hasher);
+        }
+        table
+    }
+
+    #[bench]
+    fn sum_fold(b: &mut Bencher) {
+        let table = setup();
+        b.iter(|| black_box(table.fold(0u64, |acc, &x| acc + x)))
+    }
+
+    #[bench]
+    fn sum_iter(b: &mut Bencher) {
+        let table = setup();
+        b.iter(|| black_box(unsafe { table.iter().map(|x| *x.as_ref()).sum::<u64>() }))
+    }
+}
+
+// Builds a map from unique keys in parallel, trusting the keys to be unique
+// versus the regular parallel collect.
+#[cfg(feature = "rayon")]
+#[bench]
+fn par_from_unique_iter_u64(b: &mut Bencher) {
+    use rayon::prelude::*;
+    let entries: Vec<(u64, u64)> = (0..(SIZE * 1000) as u64).map(|i| (i, i)).collect();
+    b.iter(|| {
+        let m: AHashMap<u64, u64> = AHashMap::par_from_unique_iter(entries.par_iter().copied());
+        black_box(m)
+    })
+}
+
+#[cfg(feature = "rayon")]
+#[bench]
+fn par_collect_u64(b: &mut Bencher) {
+    use rayon::prelude::*;
+    let entries: Vec<(u64, u64)> = (0..(SIZE * 1000) as u64).map(|i| (i, i)).collect();
+    b.iter(|| {
+        let m: AHashMap<u64, u64> = entries.par_iter().copied().collect();
+        black_box(m)
+    })
+}
+
+// Drives the indexed producer, which splits the table at exact element counts.
+#[cfg(feature = "rayon")]
+#[bench]
+fn par_iter_collect_into_vec_u64(b: &mut Bencher) {
+    use rayon::prelude::*;
+    let m: AHashMap<u64, u64> = (0..(SIZE * 100) as u64).map(|i| (i, i)).collect();
+    let mut values = Vec::new();
+    b.iter(|| {
+        m.par_iter().map(|(_, &v)| v).collect_into_vec(&mut values);
+        black_box(&values);
+    })
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+
+    let minor = rustc_minor_version().unwrap_or(0);
+
+    // The AArch64 NEON intrinsics were stabilized in Rust 1.59. Older
+    // compilers fall back to the generic `Group` implementation so that the
+    // crate keeps building on its minimum supported Rust version.
+    if minor >= 59 {
+        println!("cargo:rustc-cfg=hashbrown_neon");
+    }
+    if minor >= 80 {
+        println!("cargo:rustc-check-cfg=cfg(hashbrown_neon)");
+    }
+}
+
+/// Returns the minor version of the compiler, e.g. `59` for `rustc 1.59.0`.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let mut pieces = version.split('.');
+    if pieces.next() != Some("rustc 1") {
+        return None;
+    }
+    pieces.next()?.parse().ok()
+}
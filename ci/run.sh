@@ -36,6 +36,10 @@ fi
 "${CARGO}" -vv ${OP} --target="${TARGET}" --release
 "${CARGO}" -vv ${OP} --target="${TARGET}" --release --features "${FEATURES}"
 
+# Run the test suite again with word-sized groups so the probing logic is also
+# exercised at a smaller group width on targets which normally use SIMD.
+"${CARGO}" -vv ${OP} --target="${TARGET}" --features "${FEATURES},small-groups"
+
 if [ "${CHANNEL}" = "nightly" ] && [ "${NO_STD}" != 1 ]; then
     # Run benchmark on native targets, build them on non-native ones:
     NO_RUN=""
@@ -1,13 +1,29 @@
 //! Rayon extensions for `HashMap`.
 
-use super::raw::{RawIntoParIter, RawParDrain, RawParIter};
+use super::raw::{RawIntoParIter, RawParDrain, RawParIter, RawParIterWithIndex};
 use crate::hash_map::HashMap;
+use crate::map::make_insert_hash;
 use crate::raw::{Allocator, Global};
+use alloc::vec::Vec;
 use core::fmt;
 use core::hash::{BuildHasher, Hash};
 use core::marker::PhantomData;
-use rayon::iter::plumbing::UnindexedConsumer;
-use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use rayon::iter::plumbing::{self, Consumer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, ParallelExtend,
+    ParallelIterator,
+};
+
+/// Maps with fewer elements than this are processed serially by
+/// [`HashMap::par_for_each_value_mut`]. This value is a guess which has not
+/// been measured on a multi-core machine yet; the `par_for_each_value_mut`
+/// benches compare both sides of it.
+const PAR_FOR_EACH_SERIAL_THRESHOLD: usize = 8192;
+
+/// The fewest values that [`HashMap::par_for_each_value_mut`] hands to a
+/// single rayon job, so that a large map isn't split into many tiny jobs.
+/// Like the threshold above, this is an untuned guess.
+const PAR_FOR_EACH_MIN_LEN: usize = 1024;
 
 /// Parallel iterator over shared references to entries in a map.
 ///
@@ -38,6 +54,39 @@ impl<'a, K: Sync, V: Sync> ParallelIterator for ParIter<'a, K, V> {
             })
             .drive_unindexed(consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<K: Sync, V: Sync> IndexedParallelIterator for ParIter<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|x| unsafe {
+                let r = x.as_ref();
+                (&r.0, &r.1)
+            })
+            .with_producer(callback)
+    }
 }
 
 impl<K, V> Clone for ParIter<'_, K, V> {
@@ -60,6 +109,56 @@ impl<K: fmt::Debug + Eq + Hash, V: fmt::Debug> fmt::Debug for ParIter<'_, K, V>
     }
 }
 
+/// Parallel iterator over shared references to entries in a map, along with
+/// the index of the bucket holding each entry.
+///
+/// This iterator is created by the [`par_iter_with_index`] method on
+/// [`HashMap`]. See its documentation for more.
+///
+/// [`par_iter_with_index`]: /hashbrown/struct.HashMap.html#method.par_iter_with_index
+/// [`HashMap`]: /hashbrown/struct.HashMap.html
+pub struct ParIterWithIndex<'a, K, V> {
+    inner: RawParIterWithIndex<(K, V)>,
+    marker: PhantomData<(&'a K, &'a V)>,
+}
+
+impl<'a, K: Sync, V: Sync> ParallelIterator for ParIterWithIndex<'a, K, V> {
+    type Item = (usize, (&'a K, &'a V));
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.inner
+            .map(|(index, x)| unsafe {
+                let r = x.as_ref();
+                (index, (&r.0, &r.1))
+            })
+            .drive_unindexed(consumer)
+    }
+}
+
+impl<K, V> Clone for ParIterWithIndex<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K: fmt::Debug + Eq + Hash, V: fmt::Debug> fmt::Debug for ParIterWithIndex<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = unsafe { self.inner.iter() }.map(|(index, x)| unsafe {
+            let r = x.as_ref();
+            (index, (&r.0, &r.1))
+        });
+        f.debug_list().entries(iter).finish()
+    }
+}
+
 /// Parallel iterator over shared references to keys in a map.
 ///
 /// This iterator is created by the [`par_keys`] method on [`HashMap`].
@@ -84,6 +183,36 @@ impl<'a, K: Sync, V: Sync> ParallelIterator for ParKeys<'a, K, V> {
             .map(|x| unsafe { &x.as_ref().0 })
             .drive_unindexed(consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<K: Sync, V: Sync> IndexedParallelIterator for ParKeys<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|x| unsafe { &x.as_ref().0 })
+            .with_producer(callback)
+    }
 }
 
 impl<K, V> Clone for ParKeys<'_, K, V> {
@@ -127,6 +256,36 @@ impl<'a, K: Sync, V: Sync> ParallelIterator for ParValues<'a, K, V> {
             .map(|x| unsafe { &x.as_ref().1 })
             .drive_unindexed(consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<K: Sync, V: Sync> IndexedParallelIterator for ParValues<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|x| unsafe { &x.as_ref().1 })
+            .with_producer(callback)
+    }
 }
 
 impl<K, V> Clone for ParValues<'_, K, V> {
@@ -175,6 +334,39 @@ impl<'a, K: Sync, V: Send> ParallelIterator for ParIterMut<'a, K, V> {
             })
             .drive_unindexed(consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<K: Sync, V: Send> IndexedParallelIterator for ParIterMut<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|x| unsafe {
+                let r = x.as_mut();
+                (&r.0, &mut r.1)
+            })
+            .with_producer(callback)
+    }
 }
 
 impl<K: fmt::Debug + Eq + Hash, V: fmt::Debug> fmt::Debug for ParIterMut<'_, K, V> {
@@ -211,6 +403,36 @@ impl<'a, K: Sync, V: Send> ParallelIterator for ParValuesMut<'a, K, V> {
             .map(|x| unsafe { &mut x.as_mut().1 })
             .drive_unindexed(consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<K: Sync, V: Send> IndexedParallelIterator for ParValuesMut<'_, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner
+            .map(|x| unsafe { &mut x.as_mut().1 })
+            .with_producer(callback)
+    }
 }
 
 impl<K: Eq + Hash, V: fmt::Debug> fmt::Debug for ParValuesMut<'_, K, V> {
@@ -233,7 +455,7 @@ impl<K: Eq + Hash, V: fmt::Debug> fmt::Debug for ParValuesMut<'_, K, V> {
 /// [`HashMap`]: /hashbrown/struct.HashMap.html
 /// [`IntoParallelIterator`]: https://docs.rs/rayon/1.0/rayon/iter/trait.IntoParallelIterator.html
 pub struct IntoParIter<K, V, A: Allocator + Clone = Global> {
-    inner: RawIntoParIter<(K, V), A>,
+    pub(super) inner: RawIntoParIter<(K, V), A>,
 }
 
 impl<K: Send, V: Send, A: Allocator + Clone + Send> ParallelIterator for IntoParIter<K, V, A> {
@@ -268,7 +490,7 @@ impl<K: fmt::Debug + Eq + Hash, V: fmt::Debug, A: Allocator + Clone> fmt::Debug
 /// [`par_drain`]: /hashbrown/struct.HashMap.html#method.par_drain
 /// [`HashMap`]: /hashbrown/struct.HashMap.html
 pub struct ParDrain<'a, K, V, A: Allocator + Clone = Global> {
-    inner: RawParDrain<'a, (K, V), A>,
+    pub(super) inner: RawParDrain<'a, (K, V), A>,
 }
 
 impl<K: Send, V: Send, A: Allocator + Clone + Sync> ParallelIterator for ParDrain<'_, K, V, A> {
@@ -296,6 +518,22 @@ impl<K: fmt::Debug + Eq + Hash, V: fmt::Debug, A: Allocator + Clone> fmt::Debug
 }
 
 impl<K: Sync, V: Sync, S, A: Allocator + Clone> HashMap<K, V, S, A> {
+    /// Visits (potentially in parallel) immutably borrowed entries in an
+    /// arbitrary order, along with the index of the bucket holding each entry.
+    ///
+    /// Every entry has a different index, which is smaller than the number of
+    /// buckets in the table and is computed from the position of the entry
+    /// rather than the order in which it is visited. The indices can therefore
+    /// be used to write the results of a parallel computation into a buffer
+    /// sized for the table.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn par_iter_with_index(&self) -> ParIterWithIndex<'_, K, V> {
+        ParIterWithIndex {
+            inner: unsafe { self.table.par_iter_with_index() },
+            marker: PhantomData,
+        }
+    }
+
     /// Visits (potentially in parallel) immutably borrowed keys in an arbitrary order.
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn par_keys(&self) -> ParKeys<'_, K, V> {
@@ -335,6 +573,65 @@ impl<K: Send, V: Send, S, A: Allocator + Clone> HashMap<K, V, S, A> {
     }
 }
 
+impl<K: Sync, V: Send, S, A: Allocator + Clone> HashMap<K, V, S, A> {
+    /// Retains only the elements specified by the predicate, evaluating it
+    /// (potentially) in parallel.
+    ///
+    /// In other words, remove all pairs `(k, v)` such that `f(&k, &mut v)` returns `false`.
+    /// The elements are visited in an arbitrary order.
+    pub fn par_retain<F>(&mut self, f: F)
+    where
+        F: Fn(&K, &mut V) -> bool + Sync,
+    {
+        // Classify in parallel: every bucket is handed to exactly one task, so
+        // the predicate can be given a mutable reference to its value. Erasing
+        // writes to control bytes which are shared by neighbouring buckets (and
+        // mirrored at the end of the table), so it is done serially afterwards.
+        let erase: Vec<_> = unsafe { self.table.par_iter() }
+            .filter(|item| unsafe {
+                let &mut (ref key, ref mut value) = item.as_mut();
+                !f(key, value)
+            })
+            .collect();
+        for item in erase {
+            unsafe { self.table.erase(item) };
+        }
+    }
+
+    /// Calls `f` on every value in the map, (potentially) in parallel.
+    ///
+    /// This is equivalent to `par_values_mut().for_each(f)`, except that small
+    /// maps are processed serially so that rayon's overhead is only paid when
+    /// there is enough work to split, and larger maps are never split into
+    /// jobs of fewer than 1024 values. If `f` is expensive, using
+    /// [`par_values_mut`](Self::par_values_mut) directly may be faster for
+    /// small maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map: HashMap<u32, u32> = (0..10_000).map(|i| (i, i)).collect();
+    /// map.par_for_each_value_mut(|v| *v *= 2);
+    /// assert!(map.iter().all(|(k, v)| *v == k * 2));
+    /// ```
+    pub fn par_for_each_value_mut<F>(&mut self, f: F)
+    where
+        F: Fn(&mut V) + Sync,
+    {
+        if self.len() < PAR_FOR_EACH_SERIAL_THRESHOLD {
+            self.values_mut().for_each(f);
+        } else {
+            let values = ParValuesMut {
+                inner: unsafe { self.table.par_iter() },
+                marker: PhantomData,
+            };
+            values.with_min_len(PAR_FOR_EACH_MIN_LEN).for_each(&f);
+        }
+    }
+}
+
 impl<K, V, S, A> HashMap<K, V, S, A>
 where
     K: Eq + Hash + Sync,
@@ -345,8 +642,13 @@ where
     /// Returns `true` if the map is equal to another,
     /// i.e. both maps contain the same keys mapped to the same values.
     ///
+    /// As with `==`, the maps may use different hashers.
+    ///
     /// This method runs in a potentially parallel fashion.
-    pub fn par_eq(&self, other: &Self) -> bool {
+    pub fn par_eq<S2>(&self, other: &HashMap<K, V, S2, A>) -> bool
+    where
+        S2: BuildHasher + Sync,
+    {
         self.len() == other.len()
             && self
                 .into_par_iter()
@@ -354,6 +656,70 @@ where
     }
 }
 
+impl<K, V, S> HashMap<K, V, S, Global>
+where
+    K: Eq + Hash + Send,
+    V: Send,
+    S: BuildHasher + Default + Sync,
+{
+    /// Creates a map from a parallel iterator whose keys are known to be
+    /// unique, such as pre-deduplicated input.
+    ///
+    /// The keys are hashed in parallel. The entries are then inserted
+    /// serially into a table sized once for all of them, with
+    /// [`insert_unique_unchecked`] semantics, skipping the lookup for an
+    /// existing equal key.
+    ///
+    /// As with [`insert_unique_unchecked`], if two keys are equal the
+    /// behavior is unspecified (but memory safe): any following operation
+    /// on the map may panic, loop forever or return an arbitrary result.
+    /// Duplicates are detected with a panic in debug builds.
+    ///
+    /// [`insert_unique_unchecked`]: HashMap::insert_unique_unchecked
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    /// use rayon::prelude::*;
+    ///
+    /// let map: HashMap<u32, u32> =
+    ///     HashMap::par_from_unique_iter((0..1000).into_par_iter().map(|i| (i, i * 2)));
+    /// assert_eq!(map.len(), 1000);
+    /// assert_eq!(map[&500], 1000);
+    /// ```
+    pub fn par_from_unique_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = (K, V)>,
+    {
+        let hash_builder = S::default();
+
+        // Each rayon job hashes its entries into its own shard.
+        let hash_builder_ref = &hash_builder;
+        let (shards, len) = super::helpers::collect(
+            par_iter
+                .into_par_iter()
+                .map(|(k, v)| (make_insert_hash::<K, S>(hash_builder_ref, &k), k, v)),
+        );
+
+        // The whole capacity is reserved up front, so the shards are merged
+        // without checking the load factor or calling the hasher again.
+        let mut map = HashMap::with_capacity_and_hasher(len, hash_builder);
+        for shard in shards {
+            for (hash, k, v) in shard {
+                debug_assert!(
+                    map.table.find(hash, |x| k == x.0).is_none(),
+                    "duplicate key in `par_from_unique_iter`"
+                );
+                unsafe {
+                    map.table.insert_no_grow(hash, (k, v));
+                }
+            }
+        }
+        map
+    }
+}
+
 impl<K: Send, V: Send, S, A: Allocator + Clone + Send> IntoParallelIterator
     for HashMap<K, V, S, A>
 {
@@ -419,6 +785,10 @@ where
 }
 
 /// Extend a hash map with items from a parallel iterator.
+///
+/// As with the sequential `Extend`, if multiple pairs correspond to the same
+/// key then the value produced last in the parallel iterator is kept, while
+/// the key already in the map (or produced first) is left in place.
 impl<K, V, S, A> ParallelExtend<(K, V)> for HashMap<K, V, S, A>
 where
     K: Eq + Hash + Send,
@@ -622,6 +992,43 @@ mod test_par_map {
         assert_eq!(value.load(Ordering::Relaxed), 0);
     }
 
+    #[test]
+    fn test_par_drain_early_exit_drops() {
+        let key = AtomicUsize::new(0);
+        let value = AtomicUsize::new(0);
+
+        let mut hm = HashMap::new();
+        for i in 0..100 {
+            let d1 = Dropable::new(i, &key);
+            let d2 = Dropable::new(i + 100, &value);
+            hm.insert(d1, d2);
+        }
+        let capacity = hm.capacity();
+
+        // Consume only part of the drain: `find_any` stops as soon as any
+        // element matches, leaving the rest to the drain's destructor.
+        let found = hm.par_drain().find_any(|&(ref key, _)| key.k == 42);
+        assert_eq!(found.as_ref().map(|(key, _)| key.k), Some(42));
+        assert!(hm.is_empty());
+        assert_eq!(hm.iter().count(), 0);
+        assert_eq!(hm.capacity(), capacity);
+        assert_eq!(key.load(Ordering::Relaxed), 1);
+        assert_eq!(value.load(Ordering::Relaxed), 1);
+
+        drop(found);
+        assert_eq!(key.load(Ordering::Relaxed), 0);
+        assert_eq!(value.load(Ordering::Relaxed), 0);
+
+        // The map is still usable afterwards.
+        for i in 0..10 {
+            hm.insert(Dropable::new(i, &key), Dropable::new(i, &value));
+        }
+        assert_eq!(hm.par_drain().filter(|&(ref key, _)| key.k < 5).count(), 5);
+        assert!(hm.is_empty());
+        assert_eq!(key.load(Ordering::Relaxed), 0);
+        assert_eq!(value.load(Ordering::Relaxed), 0);
+    }
+
     #[test]
     fn test_empty_iter() {
         let mut m: HashMap<isize, bool> = HashMap::new();
@@ -687,6 +1094,46 @@ mod test_par_map {
         assert!(values.contains(&6));
     }
 
+    #[test]
+    fn test_par_for_each_value_mut() {
+        // Sizes on both sides of the serial threshold.
+        for &n in &[0, 10, super::PAR_FOR_EACH_SERIAL_THRESHOLD, 50_000] {
+            let mut par: HashMap<usize, usize> = (0..n).map(|i| (i, i)).collect();
+            let mut seq = par.clone();
+            par.par_for_each_value_mut(|v| *v = *v * 3 + 1);
+            seq.values_mut().for_each(|v| *v = *v * 3 + 1);
+            assert_eq!(par, seq);
+        }
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let mut m = HashMap::new();
+        for i in 0..1000 {
+            m.insert(i, i * 2);
+        }
+
+        // Indexed iteration visits entries in the same order as the serial
+        // iterator, so the indices must line up.
+        let seq: Vec<_> = m.iter().enumerate().collect();
+        let par: Vec<_> = m.par_iter().with_min_len(1).enumerate().collect();
+        assert_eq!(seq, par);
+
+        let mut seq: Vec<_> = m.keys().copied().collect();
+        seq.reverse();
+        let mut par = Vec::new();
+        m.par_keys()
+            .copied()
+            .with_max_len(7)
+            .rev()
+            .collect_into_vec(&mut par);
+        assert_eq!(seq, par);
+
+        let keys: Vec<_> = m.keys().copied().collect();
+        m.par_values_mut().zip(keys).for_each(|(v, k)| *v += k);
+        assert!(m.iter().all(|(&k, &v)| v == k * 3));
+    }
+
     #[test]
     fn test_eq() {
         let mut m1 = HashMap::new();
@@ -731,4 +1178,47 @@ mod test_par_map {
         assert_eq!(a[&2], "two");
         assert_eq!(a[&3], "three");
     }
+
+    #[test]
+    fn test_par_iter_with_index() {
+        let empty: HashMap<u32, u32> = HashMap::new();
+        assert_eq!(empty.par_iter_with_index().count(), 0);
+
+        let map: HashMap<u32, u32> = (0..10_000).map(|i| (i, i * 2)).collect();
+        let buckets = map.table.buckets();
+        let mut pairs: Vec<_> = map
+            .par_iter_with_index()
+            .map(|(index, (&k, &v))| (index, k, v))
+            .collect();
+        assert_eq!(pairs.len(), map.len());
+
+        pairs.sort_unstable();
+        for window in pairs.windows(2) {
+            assert!(window[0].0 < window[1].0);
+        }
+        assert!(pairs.iter().all(|&(index, _, _)| index < buckets));
+
+        // The indices match those of a sequential iteration over the table.
+        let mut expected: Vec<_> = unsafe {
+            map.table
+                .iter()
+                .map(|bucket| {
+                    let &(k, v) = bucket.as_ref();
+                    (map.table.bucket_index(&bucket), k, v)
+                })
+                .collect()
+        };
+        expected.sort_unstable();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_par_iter_with_index_debug() {
+        let map: HashMap<u32, u32> = [(7, 70)].iter().copied().collect();
+        let index = unsafe { map.table.bucket_index(&map.table.iter().next().unwrap()) };
+        assert_eq!(
+            format!("{:?}", map.par_iter_with_index()),
+            format!("[({}, (7, 70))]", index)
+        );
+    }
 }
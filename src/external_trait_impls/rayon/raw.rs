@@ -1,18 +1,20 @@
 use crate::raw::Bucket;
-use crate::raw::{Allocator, Global, RawIter, RawIterRange, RawTable};
+use crate::raw::{Allocator, Global, RawIter, RawIterRange, RawIterRangeIndexed, RawTable};
 use crate::scopeguard::guard;
-use alloc::alloc::dealloc;
 use core::marker::PhantomData;
 use core::mem;
 use core::ptr::NonNull;
 use rayon::iter::{
-    plumbing::{self, Folder, UnindexedConsumer, UnindexedProducer},
-    ParallelIterator,
+    plumbing::{
+        self, Consumer, Folder, Producer, ProducerCallback, UnindexedConsumer, UnindexedProducer,
+    },
+    IndexedParallelIterator, ParallelIterator,
 };
 
 /// Parallel iterator which returns a raw pointer to every full bucket in the table.
 pub struct RawParIter<T> {
     iter: RawIterRange<T>,
+    items: usize,
 }
 
 impl<T> RawParIter<T> {
@@ -27,13 +29,17 @@ impl<T> Clone for RawParIter<T> {
     fn clone(&self) -> Self {
         Self {
             iter: self.iter.clone(),
+            items: self.items,
         }
     }
 }
 
 impl<T> From<RawIter<T>> for RawParIter<T> {
     fn from(it: RawIter<T>) -> Self {
-        RawParIter { iter: it.iter }
+        let items = it.len();
+        let mut iter = it.iter;
+        iter.settle_back_group();
+        RawParIter { iter, items }
     }
 }
 
@@ -48,6 +54,37 @@ impl<T> ParallelIterator for RawParIter<T> {
         let producer = ParIterProducer { iter: self.iter };
         plumbing::bridge_unindexed(producer, consumer)
     }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.items)
+    }
+}
+
+impl<T> IndexedParallelIterator for RawParIter<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.items
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        let producer = ParIterIndexedProducer {
+            iter: unsafe { self.iter.into_indexed(self.items) },
+        };
+        callback.callback(producer)
+    }
 }
 
 /// Producer which returns a `Bucket<T>` for every element.
@@ -75,6 +112,211 @@ impl<T> UnindexedProducer for ParIterProducer<T> {
     }
 }
 
+/// Parallel iterator which returns a raw pointer to every full bucket in the
+/// table, along with the index of that bucket.
+pub struct RawParIterWithIndex<T> {
+    iter: RawIterRange<T>,
+    data_end: NonNull<T>,
+}
+
+// `data_end` is only used to compute bucket indices and never dereferenced, so
+// this is as safe to send between threads as `RawIterRange`.
+unsafe impl<T> Send for RawParIterWithIndex<T> {}
+
+impl<T> RawParIterWithIndex<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub(super) unsafe fn iter(&self) -> impl Iterator<Item = (usize, Bucket<T>)> {
+        let data_end = self.data_end;
+        self.iter
+            .clone()
+            .map(move |bucket| (bucket.to_base_index(data_end), bucket))
+    }
+}
+
+impl<T> Clone for RawParIterWithIndex<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            data_end: self.data_end,
+        }
+    }
+}
+
+impl<T> ParallelIterator for RawParIterWithIndex<T> {
+    type Item = (usize, Bucket<T>);
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = ParIterWithIndexProducer {
+            iter: self.iter,
+            data_end: self.data_end,
+        };
+        plumbing::bridge_unindexed(producer, consumer)
+    }
+}
+
+/// Producer which returns a `Bucket<T>` and its index for every element.
+struct ParIterWithIndexProducer<T> {
+    iter: RawIterRange<T>,
+    data_end: NonNull<T>,
+}
+
+unsafe impl<T> Send for ParIterWithIndexProducer<T> {}
+
+impl<T> UnindexedProducer for ParIterWithIndexProducer<T> {
+    type Item = (usize, Bucket<T>);
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn split(self) -> (Self, Option<Self>) {
+        let data_end = self.data_end;
+        let (left, right) = self.iter.split();
+        let left = ParIterWithIndexProducer {
+            iter: left,
+            data_end,
+        };
+        let right = right.map(|right| ParIterWithIndexProducer {
+            iter: right,
+            data_end,
+        });
+        (left, right)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        let data_end = self.data_end;
+        folder.consume_iter(
+            self.iter
+                .map(|bucket| (unsafe { bucket.to_base_index(data_end) }, bucket)),
+        )
+    }
+}
+
+/// Indexed producer which returns a `Bucket<T>` for every element.
+struct ParIterIndexedProducer<T> {
+    iter: RawIterRangeIndexed<T>,
+}
+
+impl<T> Producer for ParIterIndexedProducer<T> {
+    type Item = Bucket<T>;
+    type IntoIter = RawIterRangeIndexed<T>;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.iter.split_at(index);
+        let left = ParIterIndexedProducer { iter: left };
+        let right = ParIterIndexedProducer { iter: right };
+        (left, right)
+    }
+}
+
+#[cfg(feature = "raw")]
+/// Parallel iterator which returns a mutable reference to every full bucket in
+/// the table.
+pub struct RawParIterMut<'a, T> {
+    iter: RawIterRange<T>,
+    items: usize,
+    marker: PhantomData<&'a mut T>,
+}
+
+#[cfg(feature = "raw")]
+impl<'a, T: Send> ParallelIterator for RawParIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let producer = ParIterMutProducer {
+            iter: self.iter,
+            marker: PhantomData,
+        };
+        plumbing::bridge_unindexed(producer, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.items)
+    }
+}
+
+#[cfg(feature = "raw")]
+impl<'a, T: Send> IndexedParallelIterator for RawParIterMut<'a, T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.items
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        RawParIter {
+            iter: self.iter,
+            items: self.items,
+        }
+        .map(|bucket| unsafe { bucket.as_mut() })
+        .with_producer(callback)
+    }
+}
+
+#[cfg(feature = "raw")]
+/// Producer which returns a `&mut T` for every element.
+struct ParIterMutProducer<'a, T> {
+    iter: RawIterRange<T>,
+    marker: PhantomData<&'a mut T>,
+}
+
+#[cfg(feature = "raw")]
+impl<'a, T: Send> UnindexedProducer for ParIterMutProducer<'a, T> {
+    type Item = &'a mut T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn split(self) -> (Self, Option<Self>) {
+        // The two halves cover disjoint sets of buckets, so the mutable
+        // references they yield can never alias.
+        let (left, right) = self.iter.split();
+        let left = ParIterMutProducer {
+            iter: left,
+            marker: PhantomData,
+        };
+        let right = right.map(|right| ParIterMutProducer {
+            iter: right,
+            marker: PhantomData,
+        });
+        (left, right)
+    }
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn fold_with<F>(self, folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        folder.consume_iter(self.iter.map(|bucket| unsafe { bucket.as_mut() }))
+    }
+}
+
 /// Parallel iterator which consumes a table and returns elements.
 pub struct RawIntoParIter<T, A: Allocator + Clone = Global> {
     table: RawTable<T, A>,
@@ -96,10 +338,11 @@ impl<T: Send, A: Allocator + Clone + Send> ParallelIterator for RawIntoParIter<T
         C: UnindexedConsumer<Self::Item>,
     {
         let iter = unsafe { self.table.iter().iter };
-        let _guard = guard(self.table.into_allocation(), |alloc| {
-            if let Some((ptr, layout)) = *alloc {
+        let alloc = self.table.allocator().clone();
+        let _guard = guard(self.table.into_allocation(), move |allocation| {
+            if let Some((ptr, layout)) = *allocation {
                 unsafe {
-                    dealloc(ptr.as_ptr(), layout);
+                    alloc.deallocate(ptr, layout);
                 }
             }
         });
@@ -210,6 +453,34 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
     pub unsafe fn par_iter(&self) -> RawParIter<T> {
         RawParIter {
             iter: self.iter().iter,
+            items: self.len(),
+        }
+    }
+
+    /// Returns a parallel iterator over the elements in a `RawTable`, along
+    /// with the index of the bucket holding each of them.
+    ///
+    /// The indices are the same as those returned by [`bucket_index`], so
+    /// they do not depend on how the iteration is split between threads.
+    ///
+    /// [`bucket_index`]: RawTable::bucket_index
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub unsafe fn par_iter_with_index(&self) -> RawParIterWithIndex<T> {
+        RawParIterWithIndex {
+            iter: self.iter().iter,
+            data_end: self.data_end(),
+        }
+    }
+
+    #[cfg(feature = "raw")]
+    /// Returns a parallel iterator over mutable references to the elements in
+    /// a `RawTable`.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn par_iter_mut(&mut self) -> RawParIterMut<'_, T> {
+        RawParIterMut {
+            iter: unsafe { self.iter().iter },
+            items: self.len(),
+            marker: PhantomData,
         }
     }
 
@@ -229,3 +500,177 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 }
+
+#[cfg(test)]
+mod test_par_raw {
+    use alloc::vec::Vec;
+
+    use rayon::prelude::*;
+
+    use super::RawParIter;
+    use crate::raw::{RawIterRangeIndexed, RawTable};
+
+    fn hasher(x: &u64) -> u64 {
+        *x
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn test_par_iter_mut() {
+        let mut table = RawTable::new();
+        for i in 0..1000 {
+            table.insert(i, i, hasher);
+        }
+
+        table.par_iter_mut().for_each(|x| *x *= 2);
+
+        let sum: u64 = unsafe { table.iter().map(|b| *b.as_ref()).sum() };
+        assert_eq!(sum, 2 * (0..1000).sum::<u64>());
+        assert_eq!(table.par_iter_mut().count(), 1000);
+    }
+
+    #[test]
+    fn test_par_iter_indexed() {
+        for &n in &[0, 1, 3, 7, 30, 1000] {
+            let mut table = RawTable::new();
+            for i in 0..n {
+                table.insert(i, i, hasher);
+            }
+            for i in (0..n).step_by(3) {
+                table.remove_entry(i, |x| *x == i);
+            }
+
+            let seq: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+            let par: Vec<u64> = unsafe { table.par_iter() }
+                .with_min_len(1)
+                .map(|b| unsafe { *b.as_ref() })
+                .collect();
+            assert_eq!(seq, par);
+
+            let mut rev = Vec::new();
+            unsafe { table.par_iter() }
+                .with_max_len(5)
+                .rev()
+                .map(|b| unsafe { *b.as_ref() })
+                .collect_into_vec(&mut rev);
+            rev.reverse();
+            assert_eq!(seq, rev);
+
+            let mut iter = unsafe { table.iter() };
+            let skip = usize::min(seq.len(), 2);
+            for _ in 0..skip {
+                iter.next();
+            }
+            let rest: Vec<u64> = RawParIter::from(iter)
+                .with_min_len(1)
+                .map(|b| unsafe { *b.as_ref() })
+                .collect();
+            assert_eq!(seq[skip..], rest[..]);
+
+            #[cfg(feature = "raw")]
+            {
+                table
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(i, x)| *x = i as u64);
+                let indices: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+                assert_eq!(indices, (0..seq.len() as u64).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_indexed_split_at() {
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(i, i, hasher);
+        }
+        for i in (0..100).step_by(3) {
+            table.remove_entry(i, |x| *x == i);
+        }
+        let seq: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+        let indexed = || unsafe { table.iter().iter.into_indexed(seq.len()) };
+        let values = |iter: RawIterRangeIndexed<u64>| {
+            iter.map(|b| unsafe { *b.as_ref() }).collect::<Vec<_>>()
+        };
+
+        for index in 0..=seq.len() {
+            let (left, right) = indexed().split_at(index);
+            assert_eq!(values(left), seq[..index]);
+            assert_eq!(values(right), seq[index..]);
+
+            // Split again after consuming the halves from the inside.
+            let (mut left, mut right) = indexed().split_at(index);
+            let left_end = index.saturating_sub(usize::from(left.next_back().is_some()));
+            let right_start = index + usize::from(right.next().is_some());
+            let (left_mid, right_mid) = (left.len() / 2, right.len() / 2);
+            let (left, _) = left.split_at(left_mid);
+            let (_, right) = right.split_at(right_mid);
+            let mut back: Vec<u64> = left.rev().map(|b| unsafe { *b.as_ref() }).collect();
+            back.reverse();
+            assert_eq!(back, seq[..left_end / 2]);
+            assert_eq!(values(right), seq[right_start + right_mid..]);
+        }
+    }
+
+    #[test]
+    fn test_par_iter_from_double_ended() {
+        for &n in &[1, 5, 20, 40] {
+            let mut table = RawTable::new();
+            for i in 0..n {
+                table.insert(i, i, hasher);
+            }
+            let seq: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+
+            // Consume from the back first so that the front runs into the
+            // group that the back is processing.
+            for back in 0..=seq.len() {
+                for front in 0..=seq.len() - back {
+                    let mut iter = unsafe { table.iter() };
+                    for _ in 0..back {
+                        iter.next_back();
+                    }
+                    for _ in 0..front {
+                        iter.next();
+                    }
+                    let rest = &seq[front..seq.len() - back];
+
+                    let indexed: Vec<u64> = RawParIter::from(iter.clone())
+                        .with_max_len(3)
+                        .map(|b| unsafe { *b.as_ref() })
+                        .collect();
+                    assert_eq!(indexed, rest);
+                    let unindexed: Vec<u64> = RawParIter::from(iter)
+                        .filter(|_| true)
+                        .map(|b| unsafe { *b.as_ref() })
+                        .collect();
+                    assert_eq!(unindexed, rest);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn test_par_iter_mut_empty() {
+        let mut table: RawTable<u64> = RawTable::new();
+        assert_eq!(table.par_iter_mut().count(), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_into_par_iter_frees_through_allocator() {
+        use crate::raw::TestAlloc;
+
+        let alloc = TestAlloc::default();
+        let mut table = RawTable::new_in(alloc.clone());
+        for i in 0..1000 {
+            table.insert(i, i, hasher);
+        }
+        assert_eq!(alloc.live(), 1);
+
+        let sum: u64 = table.into_par_iter().sum();
+        assert_eq!(sum, (0..1000).sum::<u64>());
+        assert_eq!(alloc.live(), 0);
+    }
+}
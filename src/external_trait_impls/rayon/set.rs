@@ -3,9 +3,14 @@
 use super::map;
 use crate::hash_set::HashSet;
 use crate::raw::{Allocator, Global};
+use alloc::vec::Vec;
+use core::fmt;
 use core::hash::{BuildHasher, Hash};
-use rayon::iter::plumbing::UnindexedConsumer;
-use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use rayon::iter::plumbing::{self, Consumer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, ParallelExtend,
+    ParallelIterator,
+};
 
 /// Parallel iterator over elements of a consumed set.
 ///
@@ -31,6 +36,15 @@ impl<T: Send, A: Allocator + Clone + Send> ParallelIterator for IntoParIter<T, A
     }
 }
 
+impl<T: fmt::Debug + Eq + Hash, A: Allocator + Clone> fmt::Debug for IntoParIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = unsafe { self.inner.inner.par_iter().iter() };
+        f.debug_list()
+            .entries(iter.map(|x| unsafe { &x.as_ref().0 }))
+            .finish()
+    }
+}
+
 /// Parallel draining iterator over entries of a set.
 ///
 /// This iterator is created by the [`par_drain`] method on [`HashSet`].
@@ -53,6 +67,47 @@ impl<T: Send, A: Allocator + Clone + Send + Sync> ParallelIterator for ParDrain<
     }
 }
 
+impl<T: fmt::Debug + Eq + Hash, A: Allocator + Clone> fmt::Debug for ParDrain<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = unsafe { self.inner.inner.par_iter().iter() };
+        f.debug_list()
+            .entries(iter.map(|x| unsafe { &x.as_ref().0 }))
+            .finish()
+    }
+}
+
+/// Parallel iterator over the elements removed from a set by
+/// [`par_extract_if`].
+///
+/// The elements have already been removed from the set when this iterator is
+/// created, so dropping it only drops the elements that were not yielded.
+///
+/// [`par_extract_if`]: /hashbrown/struct.HashSet.html#method.par_extract_if
+pub struct ParExtractIf<T> {
+    removed: Vec<T>,
+}
+
+impl<T: Send> ParallelIterator for ParExtractIf<T> {
+    type Item = T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.removed.into_par_iter().drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.removed.len())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ParExtractIf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.removed).finish()
+    }
+}
+
 /// Parallel iterator over shared references to elements in a set.
 ///
 /// This iterator is created by the [`par_iter`] method on [`HashSet`]
@@ -75,6 +130,36 @@ impl<'a, T: Sync> ParallelIterator for ParIter<'a, T> {
     {
         self.inner.drive_unindexed(consumer)
     }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.inner.len())
+    }
+}
+
+impl<T: Sync> IndexedParallelIterator for ParIter<'_, T> {
+    fn drive<C>(self, consumer: C) -> C::Result
+    where
+        C: Consumer<Self::Item>,
+    {
+        plumbing::bridge(self, consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn with_producer<CB>(self, callback: CB) -> CB::Output
+    where
+        CB: ProducerCallback<Self::Item>,
+    {
+        self.inner.with_producer(callback)
+    }
+}
+
+impl<T: fmt::Debug + Eq + Hash> fmt::Debug for ParIter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
 }
 
 /// Parallel iterator over shared references to elements in the difference of
@@ -167,9 +252,16 @@ where
     where
         C: UnindexedConsumer<Self::Item>,
     {
-        self.a
+        // Iterate over the smaller set in order to reduce hash lookups, as
+        // `HashSet::intersection` does.
+        let (smaller, larger) = if self.a.len() <= self.b.len() {
+            (self.a, self.b)
+        } else {
+            (self.b, self.a)
+        };
+        smaller
             .into_par_iter()
-            .filter(|&x| self.b.contains(x))
+            .filter(|&x| larger.contains(x))
             .drive_unindexed(consumer)
     }
 }
@@ -254,7 +346,13 @@ where
     ///
     /// This method runs in a potentially parallel fashion.
     pub fn par_is_disjoint(&self, other: &Self) -> bool {
-        self.into_par_iter().all(|x| !other.contains(x))
+        // Only the smaller set needs to be scanned.
+        let (a, b) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        a.into_par_iter().all(|x| !b.contains(x))
     }
 
     /// Returns `true` if the set is a subset of another,
@@ -301,6 +399,61 @@ where
     }
 }
 
+impl<T: Sync, S, A: Allocator + Clone> HashSet<T, S, A> {
+    /// Retains only the elements specified by the predicate, evaluating it
+    /// (potentially) in parallel.
+    ///
+    /// In other words, remove all elements `e` such that `f(&e)` returns `false`.
+    /// The elements are visited in an arbitrary order.
+    pub fn par_retain<F>(&mut self, f: F)
+    where
+        F: Fn(&T) -> bool + Sync,
+    {
+        self.map.par_retain(|k, _| f(k));
+    }
+
+    /// Removes all elements `e` such that `f(&e)` returns `true`, evaluating
+    /// the predicate (potentially) in parallel, and returns a parallel
+    /// iterator over the removed elements.
+    ///
+    /// Unlike [`extract_if`], the matching elements are removed eagerly when
+    /// this method is called, not as the iterator is consumed. The elements
+    /// are visited in an arbitrary order.
+    ///
+    /// [`extract_if`]: HashSet::extract_if
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashSet;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut set: HashSet<i32> = (0..8).collect();
+    /// let mut evens: Vec<i32> = set.par_extract_if(|v| v % 2 == 0).collect();
+    /// evens.sort_unstable();
+    ///
+    /// assert_eq!(evens, [0, 2, 4, 6]);
+    /// assert_eq!(set.len(), 4);
+    /// ```
+    pub fn par_extract_if<F>(&mut self, f: F) -> ParExtractIf<T>
+    where
+        F: Fn(&T) -> bool + Sync,
+    {
+        // Same two phases as `par_retain`: classify in parallel, then move the
+        // matching elements out serially since erasing writes to shared
+        // control bytes.
+        let table = &mut self.map.table;
+        let extract: Vec<_> = unsafe { table.par_iter() }
+            .filter(|item| unsafe { f(&item.as_ref().0) })
+            .collect();
+        let removed = extract
+            .into_iter()
+            .map(|item| unsafe { table.remove(item).0 })
+            .collect();
+        ParExtractIf { removed }
+    }
+}
+
 impl<T: Send, S, A: Allocator + Clone + Send> IntoParallelIterator for HashSet<T, S, A> {
     type Item = T;
     type Iter = IntoParIter<T, A>;
@@ -325,7 +478,9 @@ impl<'a, T: Sync, S, A: Allocator + Clone> IntoParallelIterator for &'a HashSet<
     }
 }
 
-/// Collect values from a parallel iterator into a hashset.
+/// Collect values from a parallel iterator into a hashset. If multiple values
+/// are equal, then the one produced earliest in the parallel iterator is kept,
+/// just as with a sequential iterator.
 impl<T, S> FromParallelIterator<T> for HashSet<T, S, Global>
 where
     T: Eq + Hash + Send,
@@ -342,6 +497,9 @@ where
 }
 
 /// Extend a hash set with items from a parallel iterator.
+///
+/// As with the sequential `Extend`, values that are already present in the set
+/// are not replaced.
 impl<T, S> ParallelExtend<T> for HashSet<T, S, Global>
 where
     T: Eq + Hash + Send,
@@ -607,6 +765,28 @@ mod test_par_set {
         assert!(v == ['a', 'b'] || v == ['b', 'a']);
     }
 
+    #[test]
+    fn test_par_drain_early_exit() {
+        use alloc::sync::Arc;
+
+        let token = Arc::new(());
+        let mut hs = HashSet::new();
+        for i in 0..100 {
+            hs.insert((i, token.clone()));
+        }
+        assert_eq!(Arc::strong_count(&token), 101);
+
+        // Elements not reached before `find_any` returns are dropped by the
+        // drain itself, exactly once.
+        let found = hs.par_drain().find_any(|&(i, _)| i == 7);
+        assert_eq!(found.as_ref().map(|&(i, _)| i), Some(7));
+        assert!(hs.is_empty());
+        assert_eq!(Arc::strong_count(&token), 2);
+
+        drop(found);
+        assert_eq!(Arc::strong_count(&token), 1);
+    }
+
     #[test]
     fn test_eq() {
         // These constants once happened to expose a bug in insert().
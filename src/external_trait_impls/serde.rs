@@ -10,11 +10,11 @@ mod size_hint {
     }
 }
 
-mod map {
+pub(crate) mod map {
     use core::fmt;
     use core::hash::{BuildHasher, Hash};
     use core::marker::PhantomData;
-    use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+    use serde::de::{Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
     use serde::ser::{Serialize, Serializer};
 
     use crate::hash_map::HashMap;
@@ -85,6 +85,157 @@ mod map {
             };
             deserializer.deserialize_map(visitor)
         }
+
+        #[allow(clippy::missing_errors_doc)]
+        fn deserialize_in_place<D>(deserializer: D, place: &mut Self) -> Result<(), D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct MapInPlaceVisitor<'a, K, V, S>(&'a mut HashMap<K, V, S>);
+
+            impl<'a, 'de, K, V, S> Visitor<'de> for MapInPlaceVisitor<'a, K, V, S>
+            where
+                K: Deserialize<'de> + Eq + Hash,
+                V: Deserialize<'de>,
+                S: BuildHasher + Default,
+            {
+                type Value = ();
+
+                fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    formatter.write_str("a map")
+                }
+
+                #[cfg_attr(feature = "inline-more", inline)]
+                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: MapAccess<'de>,
+                {
+                    self.0.clear();
+                    self.0.reserve(size_hint::cautious(map.size_hint()));
+
+                    while let Some((key, value)) = map.next_entry()? {
+                        self.0.insert(key, value);
+                    }
+
+                    Ok(())
+                }
+            }
+
+            deserializer.deserialize_map(MapInPlaceVisitor(place))
+        }
+    }
+
+    /// Serializes a `HashMap` as a sequence of `(key, value)` tuples instead
+    /// of as a map.
+    ///
+    /// Some formats have no map type, or encode a sequence of tuples more
+    /// compactly than a map. Use [`AsSeqSeed`] to deserialize the result.
+    pub struct AsSeq<'a, K, V, S>(pub &'a HashMap<K, V, S>);
+
+    impl<K, V, H> Serialize for AsSeq<'_, K, V, H>
+    where
+        K: Serialize + Eq + Hash,
+        V: Serialize,
+        H: BuildHasher,
+    {
+        #[cfg_attr(feature = "inline-more", inline)]
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(self.0)
+        }
+    }
+
+    /// Deserializes a `HashMap` from a sequence of `(key, value)` tuples, as
+    /// written by [`AsSeq`].
+    ///
+    /// As with `FromIterator`, a later value for a duplicate key replaces an
+    /// earlier one.
+    pub struct AsSeqSeed<K, V, S> {
+        hash_builder: S,
+        marker: PhantomData<HashMap<K, V, S>>,
+    }
+
+    impl<K, V, S: Default> AsSeqSeed<K, V, S> {
+        /// Creates a seed which builds the map with the default hasher.
+        #[cfg_attr(feature = "inline-more", inline)]
+        pub fn new() -> Self {
+            Self::with_hasher(S::default())
+        }
+    }
+
+    impl<K, V, S: Default> Default for AsSeqSeed<K, V, S> {
+        #[cfg_attr(feature = "inline-more", inline)]
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<K, V, S> AsSeqSeed<K, V, S> {
+        /// Creates a seed which builds the map with the given hasher.
+        #[cfg_attr(feature = "inline-more", inline)]
+        pub fn with_hasher(hash_builder: S) -> Self {
+            Self {
+                hash_builder,
+                marker: PhantomData,
+            }
+        }
+    }
+
+    impl<'de, K, V, S> DeserializeSeed<'de> for AsSeqSeed<K, V, S>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        S: BuildHasher,
+    {
+        type Value = HashMap<K, V, S>;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct SeqVisitor<K, V, S> {
+                hash_builder: S,
+                marker: PhantomData<HashMap<K, V, S>>,
+            }
+
+            impl<'de, K, V, S> Visitor<'de> for SeqVisitor<K, V, S>
+            where
+                K: Deserialize<'de> + Eq + Hash,
+                V: Deserialize<'de>,
+                S: BuildHasher,
+            {
+                type Value = HashMap<K, V, S>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    formatter.write_str("a sequence of key-value pairs")
+                }
+
+                #[cfg_attr(feature = "inline-more", inline)]
+                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: SeqAccess<'de>,
+                {
+                    let mut values = HashMap::with_capacity_and_hasher(
+                        size_hint::cautious(seq.size_hint()),
+                        self.hash_builder,
+                    );
+
+                    while let Some((key, value)) = seq.next_element()? {
+                        values.insert(key, value);
+                    }
+
+                    Ok(values)
+                }
+            }
+
+            let visitor = SeqVisitor {
+                hash_builder: self.hash_builder,
+                marker: PhantomData,
+            };
+            deserializer.deserialize_seq(visitor)
+        }
     }
 }
 
@@ -199,3 +350,108 @@ mod set {
         }
     }
 }
+
+#[cfg(feature = "raw")]
+mod raw {
+    use core::fmt;
+    use core::marker::PhantomData;
+    use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+    use serde::ser::{Serialize, Serializer};
+
+    use crate::raw::{Allocator, Global, RawTable};
+
+    use super::size_hint;
+
+    impl<T, A> Serialize for RawTable<T, A>
+    where
+        T: Serialize,
+        A: Allocator + Clone,
+    {
+        #[cfg_attr(feature = "inline-more", inline)]
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(unsafe { self.iter().map(|bucket| bucket.as_ref()) })
+        }
+    }
+
+    impl<T> RawTable<T, Global> {
+        /// Deserializes a `RawTable` from a sequence of `T`, using `hasher` to
+        /// compute the hash of each element as it is inserted.
+        ///
+        /// This does not check for duplicate elements.
+        #[cfg_attr(feature = "inline-more", inline)]
+        pub fn deserialize_seq_with_hasher<'de, D>(
+            deserializer: D,
+            hasher: impl Fn(&T) -> u64,
+        ) -> Result<Self, D::Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            Self::deserialize_seq_with_hasher_in(deserializer, hasher, Global)
+        }
+    }
+
+    impl<T, A: Allocator + Clone> RawTable<T, A> {
+        /// Deserializes a `RawTable` from a sequence of `T` into the given
+        /// allocator, using `hasher` to compute the hash of each element as
+        /// it is inserted.
+        ///
+        /// This does not check for duplicate elements.
+        pub fn deserialize_seq_with_hasher_in<'de, D>(
+            deserializer: D,
+            hasher: impl Fn(&T) -> u64,
+            alloc: A,
+        ) -> Result<Self, D::Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            struct SeqVisitor<T, A: Allocator + Clone, H> {
+                hasher: H,
+                alloc: A,
+                marker: PhantomData<RawTable<T, A>>,
+            }
+
+            impl<'de, T, A, H> Visitor<'de> for SeqVisitor<T, A, H>
+            where
+                T: Deserialize<'de>,
+                A: Allocator + Clone,
+                H: Fn(&T) -> u64,
+            {
+                type Value = RawTable<T, A>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    formatter.write_str("a sequence")
+                }
+
+                #[cfg_attr(feature = "inline-more", inline)]
+                fn visit_seq<S>(self, mut seq: S) -> Result<Self::Value, S::Error>
+                where
+                    S: SeqAccess<'de>,
+                {
+                    let mut table = RawTable::with_capacity_in(
+                        size_hint::cautious(seq.size_hint()),
+                        self.alloc,
+                    );
+
+                    while let Some(value) = seq.next_element()? {
+                        let hash = (self.hasher)(&value);
+                        table.insert(hash, value, &self.hasher);
+                    }
+
+                    Ok(table)
+                }
+            }
+
+            let visitor = SeqVisitor {
+                hasher,
+                alloc,
+                marker: PhantomData,
+            };
+            deserializer.deserialize_seq(visitor)
+        }
+    }
+}
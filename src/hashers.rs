@@ -0,0 +1,177 @@
+//! Hashers for keys which are already well-distributed hash values.
+
+use core::hash::{BuildHasher, Hasher};
+
+/// Multiplier used to spread the key bits, taken from the golden ratio.
+const MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// A `BuildHasher` which produces [`IdentityHasher`]s.
+///
+/// This is intended for maps whose keys are integers that are already
+/// uniformly distributed, such as hashes computed elsewhere or randomly
+/// generated IDs, where running them through a general-purpose hasher again
+/// would be wasted work.
+///
+/// # Security
+///
+/// The hash of a key is a fixed function of its value, so this offers no
+/// protection against HashDoS attacks. Only use it if the keys are random or
+/// cannot be chosen by an attacker.
+///
+/// # Examples
+///
+/// ```
+/// use hashbrown::hashers::BuildIdentityHasher;
+/// use hashbrown::HashMap;
+///
+/// let mut map: HashMap<u64, &str, BuildIdentityHasher> = HashMap::default();
+/// map.insert(0x3c8f_6a14_0b2d_97e5, "a");
+/// assert_eq!(map[&0x3c8f_6a14_0b2d_97e5], "a");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildIdentityHasher;
+
+impl BuildHasher for BuildIdentityHasher {
+    type Hasher = IdentityHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> IdentityHasher {
+        IdentityHasher::default()
+    }
+}
+
+/// A `Hasher` which uses the bits of an integer key as its hash.
+///
+/// The table takes the bucket index from the low bits of a hash and a tag
+/// stored in the control bytes from its top 7 bits, so a key is not returned
+/// completely unchanged: [`finish`] multiplies it by a constant and folds the
+/// high half of the 128-bit product into the low half. This spreads keys
+/// which only differ in their low bits (such as sequential integers) or only
+/// in their high bits (such as shifted values) over the whole table.
+///
+/// Integer keys are written with a single call to one of the `write_*`
+/// methods. Other types are supported by folding their bytes together, but
+/// this performs no mixing beyond that of `finish` and so is rarely a good
+/// choice.
+///
+/// See [`BuildIdentityHasher`] for the caveats of using this hasher.
+///
+/// [`finish`]: Hasher::finish
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdentityHasher {
+    hash: u64,
+}
+
+impl IdentityHasher {
+    #[inline]
+    fn combine(&mut self, value: u64) {
+        // A single write leaves the value unchanged, which is the common case.
+        self.hash = self.hash.rotate_left(5) ^ value;
+    }
+}
+
+impl Hasher for IdentityHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        let full = u128::from(self.hash) * u128::from(MULTIPLIER);
+        (full as u64) ^ ((full >> 64) as u64)
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut buf = [0; 8];
+            buf[..chunk.len()].copy_from_slice(chunk);
+            self.combine(u64::from_le_bytes(buf));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.combine(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.combine(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.combine(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.combine(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.combine(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod test_hashers {
+    use super::BuildIdentityHasher;
+    use crate::HashMap;
+    use core::hash::BuildHasher;
+    use std::vec::Vec;
+
+    fn hash(key: u64) -> u64 {
+        let mut hasher = BuildIdentityHasher.build_hasher();
+        core::hash::Hash::hash(&key, &mut hasher);
+        core::hash::Hasher::finish(&hasher)
+    }
+
+    /// Checks that `keys` are spread over the buckets of a table with 1024
+    /// buckets and over the 128 possible control byte tags.
+    fn check_spread(keys: impl Iterator<Item = u64>) {
+        let mut buckets = [0u32; 1024];
+        let mut tags = [0u32; 128];
+        for key in keys.take(1024) {
+            let hash = hash(key);
+            buckets[hash as usize % 1024] += 1;
+            tags[(hash >> 57) as usize] += 1;
+        }
+
+        // Uniformly random hashes would leave about a third of the buckets
+        // empty, with at most a handful of keys in any of them.
+        assert!(buckets.iter().filter(|&&n| n == 0).count() < 450);
+        assert!(buckets.iter().all(|&n| n <= 8));
+        // Each tag is expected to be used 8 times.
+        assert!(tags.iter().all(|&n| n <= 24));
+    }
+
+    #[test]
+    fn test_sequential_keys_spread() {
+        check_spread(0..);
+        check_spread((0..).map(|i| i << 32));
+        check_spread((0..).map(|i| i << 54));
+        check_spread((0..).map(|i| i * 1024));
+    }
+
+    #[test]
+    fn test_identity_map() {
+        let mut map: HashMap<u64, u64, BuildIdentityHasher> = HashMap::default();
+        for i in 0..100_000 {
+            assert!(map.insert(i, i * 2).is_none());
+        }
+        for i in 0..100_000 {
+            assert_eq!(map[&i], i * 2);
+        }
+
+        let mut map: HashMap<(u32, u32), usize, BuildIdentityHasher> = HashMap::default();
+        let keys: Vec<_> = (0..100)
+            .flat_map(|i| (0..100).map(move |j| (i, j)))
+            .collect();
+        for (n, &key) in keys.iter().enumerate() {
+            map.insert(key, n);
+        }
+        assert_eq!(map.len(), keys.len());
+        for (n, key) in keys.iter().enumerate() {
+            assert_eq!(map[key], n);
+        }
+    }
+}
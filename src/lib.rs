@@ -76,11 +76,13 @@ pub mod raw {
 mod raw;
 
 mod external_trait_impls;
+pub mod hashers;
 mod map;
 #[cfg(feature = "rustc-internal-api")]
 mod rustc_entry;
 mod scopeguard;
 mod set;
+mod table;
 
 pub mod hash_map {
     //! A hash map implemented with quadratic probing and SIMD lookup.
@@ -89,6 +91,9 @@ pub mod hash_map {
     #[cfg(feature = "rustc-internal-api")]
     pub use crate::rustc_entry::*;
 
+    #[cfg(feature = "serde")]
+    pub use crate::external_trait_impls::serde::map::{AsSeq, AsSeqSeed};
+
     #[cfg(feature = "rayon")]
     /// [rayon]-based parallel iterator types for hash maps.
     /// You will rarely need to interact with it directly unless you have need
@@ -113,9 +118,48 @@ pub mod hash_set {
         pub use crate::external_trait_impls::rayon::set::*;
     }
 }
+pub mod hash_table {
+    //! A hash table with explicit hashing, for elements that cannot hash or
+    //! compare themselves.
+    pub use crate::table::*;
+}
 
 pub use crate::map::HashMap;
 pub use crate::set::HashSet;
+pub use crate::table::HashTable;
+
+/// Key equivalence trait.
+///
+/// This trait defines the function used to compare the input value with the
+/// map keys (or set values) during a lookup operation such as `HashMap::get`
+/// or `HashSet::contains`. It is provided with a blanket implementation based
+/// on the `Borrow` trait, but it can also be implemented directly to look up
+/// keys by a type that the key cannot be borrowed as.
+///
+/// # Correctness
+///
+/// Equivalent values must hash to the same value.
+pub trait Equivalent<K: ?Sized> {
+    /// Checks if this value is equivalent to the given key.
+    ///
+    /// Returns `true` if both values are equivalent, and `false` otherwise.
+    ///
+    /// # Correctness
+    ///
+    /// When this function returns `true`, both `self` and `key` must hash to
+    /// the same value.
+    fn equivalent(&self, key: &K) -> bool;
+}
+
+impl<Q: ?Sized, K: ?Sized> Equivalent<K> for Q
+where
+    Q: Eq,
+    K: core::borrow::Borrow<Q>,
+{
+    fn equivalent(&self, key: &K) -> bool {
+        self == key.borrow()
+    }
+}
 
 /// The error type for `try_reserve` methods.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -131,6 +175,17 @@ pub enum TryReserveError {
     },
 }
 
+/// The error type for `try_get_many_mut` methods.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GetManyMutError {
+    /// The key at this index in the query was not found.
+    MissingKey(usize),
+
+    /// The keys at these two indices in the query resolved to the same entry.
+    /// The first index is always less than the second.
+    DuplicateKey(usize, usize),
+}
+
 /// Wrapper around `Bump` which allows it to be used as an allocator for
 /// `HashMap`, `HashSet` and `RawTable`.
 ///
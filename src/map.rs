@@ -1,12 +1,14 @@
 use crate::raw::{Allocator, Bucket, Global, RawDrain, RawIntoIter, RawIter, RawTable};
-use crate::TryReserveError;
+use crate::{Equivalent, GetManyMutError, TryReserveError};
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::fmt::{self, Debug};
 use core::hash::{BuildHasher, Hash};
 use core::iter::{FromIterator, FusedIterator};
 use core::marker::PhantomData;
 use core::mem;
-use core::ops::Index;
+use core::ops::{ControlFlow, Index};
 
 /// Default hasher for `HashMap`.
 #[cfg(feature = "ahash")]
@@ -209,13 +211,12 @@ impl<K: Clone, V: Clone, S: Clone, A: Allocator + Clone> Clone for HashMap<K, V,
 /// Ensures that a single closure type across uses of this which, in turn prevents multiple
 /// instances of any functions like RawTable::reserve from being generated
 #[cfg_attr(feature = "inline-more", inline)]
-pub(crate) fn make_hasher<K, Q, V, S>(hash_builder: &S) -> impl Fn(&(Q, V)) -> u64 + '_
+pub(crate) fn make_hasher<Q, V, S>(hash_builder: &S) -> impl Fn(&(Q, V)) -> u64 + '_
 where
-    K: Borrow<Q>,
     Q: Hash,
     S: BuildHasher,
 {
-    move |val| make_hash::<K, Q, S>(hash_builder, &val.0)
+    move |val| make_hash::<Q, S>(hash_builder, &val.0)
 }
 
 /// Ensures that a single closure type across uses of this which, in turn prevents multiple
@@ -223,10 +224,9 @@ where
 #[cfg_attr(feature = "inline-more", inline)]
 fn equivalent_key<Q, K, V>(k: &Q) -> impl Fn(&(K, V)) -> bool + '_
 where
-    K: Borrow<Q>,
-    Q: ?Sized + Eq,
+    Q: ?Sized + Equivalent<K>,
 {
-    move |x| k.eq(x.0.borrow())
+    move |x| k.equivalent(&x.0)
 }
 
 /// Ensures that a single closure type across uses of this which, in turn prevents multiple
@@ -234,17 +234,56 @@ where
 #[cfg_attr(feature = "inline-more", inline)]
 fn equivalent<Q, K>(k: &Q) -> impl Fn(&K) -> bool + '_
 where
-    K: Borrow<Q>,
-    Q: ?Sized + Eq,
+    Q: ?Sized + Equivalent<K>,
 {
-    move |x| k.eq(x.borrow())
+    move |x| k.equivalent(x)
+}
+
+/// Checks that `key` still hashes to `hash`, the hash it was looked up or
+/// inserted with. Only used in debug builds to catch `Hash` implementations
+/// which are not deterministic.
+#[cfg(debug_assertions)]
+fn debug_assert_hash_consistent<K, S>(hash_builder: &S, key: &K, hash: u64)
+where
+    K: Hash,
+    S: BuildHasher,
+{
+    assert_eq!(
+        make_hash::<K, S>(hash_builder, key),
+        hash,
+        "the hash of a key changed between two calls to `Hash::hash`"
+    );
+}
+
+/// Checks that the key which was just inserted into `bucket` with `hash` is
+/// found in that same bucket by a lookup. Only used in debug builds to catch
+/// keys whose `Hash` and `Eq` implementations are inconsistent.
+#[cfg(debug_assertions)]
+fn debug_assert_key_findable<K, V, S, A>(
+    table: &RawTable<(K, V), A>,
+    hash_builder: &S,
+    hash: u64,
+    bucket: &Bucket<(K, V)>,
+) where
+    K: Eq + Hash,
+    S: BuildHasher,
+    A: Allocator + Clone,
+{
+    let key = unsafe { &bucket.as_ref().0 };
+    debug_assert_hash_consistent(hash_builder, key, hash);
+    let found = table.find(hash, equivalent_key(key));
+    assert!(
+        found.map_or(false, |found| unsafe {
+            table.bucket_index(&found) == table.bucket_index(bucket)
+        }),
+        "an inserted key cannot be found again, its `Eq` and `Hash` implementations are inconsistent"
+    );
 }
 
 #[cfg(not(feature = "nightly"))]
 #[cfg_attr(feature = "inline-more", inline)]
-pub(crate) fn make_hash<K, Q, S>(hash_builder: &S, val: &Q) -> u64
+pub(crate) fn make_hash<Q, S>(hash_builder: &S, val: &Q) -> u64
 where
-    K: Borrow<Q>,
     Q: Hash + ?Sized,
     S: BuildHasher,
 {
@@ -256,9 +295,8 @@ where
 
 #[cfg(feature = "nightly")]
 #[cfg_attr(feature = "inline-more", inline)]
-pub(crate) fn make_hash<K, Q, S>(hash_builder: &S, val: &Q) -> u64
+pub(crate) fn make_hash<Q, S>(hash_builder: &S, val: &Q) -> u64
 where
-    K: Borrow<Q>,
     Q: Hash + ?Sized,
     S: BuildHasher,
 {
@@ -313,6 +351,12 @@ impl<K, V> HashMap<K, V, DefaultHashBuilder> {
     /// The hash map will be able to hold at least `capacity` elements without
     /// reallocating. If `capacity` is 0, the hash map will not allocate.
     ///
+    /// The table is sized for the maximum load factor, so it may have room for
+    /// quite a few more elements than `capacity`. When `capacity` is just past
+    /// a power-of-two boundary,
+    /// [`with_exact_capacity`](Self::with_exact_capacity) can trade lookup
+    /// speed for a table with half as many buckets.
+    ///
     /// # Examples
     ///
     /// ```
@@ -325,6 +369,24 @@ impl<K, V> HashMap<K, V, DefaultHashBuilder> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
     }
+
+    /// Creates an empty `HashMap` with the smallest table that can hold
+    /// `capacity` elements.
+    ///
+    /// See [`with_exact_capacity_and_hasher`](Self::with_exact_capacity_and_hasher).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    /// let map: HashMap<&str, i32> = HashMap::with_exact_capacity(15);
+    /// assert!(map.capacity() >= 15);
+    /// assert!(map.capacity() < HashMap::<&str, i32>::with_capacity(15).capacity());
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        Self::with_exact_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
 }
 
 #[cfg(feature = "ahash")]
@@ -422,6 +484,45 @@ impl<K, V, S> HashMap<K, V, S> {
             table: RawTable::with_capacity(capacity),
         }
     }
+
+    /// Creates an empty `HashMap` with the smallest table that can hold
+    /// `capacity` elements, using `hash_builder` to hash the keys.
+    ///
+    /// [`with_capacity_and_hasher`](Self::with_capacity_and_hasher) keeps at
+    /// least 1/8 of the buckets empty so that probe sequences stay short. This
+    /// instead only keeps a single bucket empty, at the cost of slower lookups
+    /// and insertions once the map is nearly full.
+    ///
+    /// Since the number of buckets is always a power of two, this only helps
+    /// when `capacity` is just past a size at which the load factor forces
+    /// the table to double, e.g. 15 elements fit in 16 buckets instead of 32.
+    /// The allocation is then half the size. For most other capacities,
+    /// including almost all small ones (3 elements need 4 buckets either
+    /// way), the table is the same as the one `with_capacity_and_hasher`
+    /// creates.
+    ///
+    /// The map goes back to the normal load factor once it is cleared or needs
+    /// to grow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    /// use hashbrown::hash_map::DefaultHashBuilder;
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut map = HashMap::with_exact_capacity_and_hasher(7, s);
+    /// assert!(map.capacity() >= 7);
+    ///
+    /// map.insert(1, 2);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn with_exact_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self {
+            hash_builder,
+            table: RawTable::with_exact_capacity(capacity),
+        }
+    }
 }
 
 impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
@@ -525,6 +626,61 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
         self.table.capacity()
     }
 
+    /// Returns a lower bound on the number of elements that can be inserted
+    /// before the map reallocates or rehashes.
+    ///
+    /// At least this many new keys can always be inserted without
+    /// reallocating. Removing elements may leave tombstones behind which count
+    /// against it until the next rehash, and an insertion which reuses a
+    /// tombstone does not decrease it, so more insertions than this may fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = HashMap::with_capacity(100);
+    /// let batch = map.growth_left();
+    /// assert!(batch >= 100);
+    ///
+    /// // None of these insertions reallocate the map.
+    /// for i in 0..batch as i32 {
+    ///     map.insert(i, i);
+    /// }
+    /// assert_eq!(map.len(), batch);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn growth_left(&self) -> usize {
+        self.table.growth_left()
+    }
+
+    /// Returns the capacity that a map created with `with_capacity(len)` would
+    /// have, without allocating one.
+    ///
+    /// This takes the rounding of the number of buckets to a power of two and
+    /// the maximum load factor into account, so it can be used to estimate the
+    /// memory used by a map ahead of time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let capacity = HashMap::<i32, i32>::capacity_for(100);
+    /// assert!(capacity >= 100);
+    ///
+    /// let map: HashMap<i32, i32> = HashMap::with_capacity(100);
+    /// assert_eq!(map.capacity(), capacity);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn capacity_for(len: usize) -> usize {
+        RawTable::<(K, V), A>::capacity_for(len)
+    }
+
     /// An iterator visiting all keys in arbitrary order.
     /// The iterator element type is `&'a K`.
     ///
@@ -712,6 +868,39 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
         }
     }
 
+    /// An iterator visiting all key-value pairs in ascending key order.
+    /// The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// Unlike [`iter`], the order does not depend on the hasher or on the
+    /// history of the map, which makes it suitable for reproducible output
+    /// such as snapshots. The entries are collected and sorted up front, so
+    /// this allocates and takes `O(n log n)` time.
+    ///
+    /// [`iter`]: #method.iter
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert("c", 3);
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    ///
+    /// let vec: Vec<(&&str, &i32)> = map.iter_sorted().collect();
+    /// assert_eq!(vec, [(&"a", &1), (&"b", &2), (&"c", &3)]);
+    /// ```
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        // Keys are unique, so an unstable sort is deterministic.
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter()
+    }
+
     #[cfg(test)]
     #[cfg_attr(feature = "inline-more", inline)]
     fn raw_capacity(&self) -> usize {
@@ -803,6 +992,9 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
     /// In other words, remove all pairs `(k, v)` such that `f(&k, &mut v)` returns `false`.
     /// The elements are visited in unsorted (and unspecified) order.
     ///
+    /// The predicate already receives a mutable reference to each value, so
+    /// unlike `Vec` there is no separate `retain_mut` method.
+    ///
     /// # Examples
     ///
     /// ```
@@ -838,21 +1030,74 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
         }
     }
 
+    /// Retains only the elements specified by the predicate, with the option
+    /// of stopping early.
+    ///
+    /// For each visited pair `(k, v)`, `f(&k, &mut v)` returns
+    /// `ControlFlow::Continue(keep)` to keep or remove it as with
+    /// [`retain`](Self::retain), or `ControlFlow::Break(())` to stop. Once the
+    /// predicate breaks, the current pair and all pairs not yet visited are
+    /// left in the map. The elements are visited in unsorted (and unspecified)
+    /// order.
+    ///
+    /// This is useful for bounded cleanup passes, for example under a time
+    /// budget.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+    ///
+    /// // Remove at most three odd keys.
+    /// let mut budget = 3;
+    /// map.retain_while(|&k, _| {
+    ///     if budget == 0 {
+    ///         return ControlFlow::Break(());
+    ///     }
+    ///     let keep = k % 2 == 0;
+    ///     if !keep {
+    ///         budget -= 1;
+    ///     }
+    ///     ControlFlow::Continue(keep)
+    /// });
+    /// assert_eq!(map.len(), 5);
+    /// assert_eq!(map.keys().filter(|&&k| k % 2 == 0).count(), 4);
+    /// ```
+    pub fn retain_while<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> ControlFlow<(), bool>,
+    {
+        // Here we only use `iter` as a temporary, preventing use-after-free
+        unsafe {
+            for item in self.table.iter() {
+                let &mut (ref key, ref mut value) = item.as_mut();
+                match f(key, value) {
+                    ControlFlow::Continue(true) => {}
+                    ControlFlow::Continue(false) => self.table.erase(item),
+                    ControlFlow::Break(()) => break,
+                }
+            }
+        }
+    }
+
     /// Drains elements which are true under the given predicate,
     /// and returns an iterator over the removed items.
     ///
     /// In other words, move all pairs `(k, v)` such that `f(&k, &mut v)` returns `true` out
     /// into another iterator.
     ///
-    /// Note that `drain_filter` lets you mutate every value in the filter closure, regardless of
+    /// Note that `extract_if` lets you mutate every value in the filter closure, regardless of
     /// whether you choose to keep or remove it.
     ///
-    /// When the returned DrainedFilter is dropped, any remaining elements that satisfy
+    /// When the returned ExtractIf is dropped, any remaining elements that satisfy
     /// the predicate are dropped from the table.
     ///
     /// It is unspecified how many more elements will be subjected to the closure
     /// if a panic occurs in the closure, or a panic occurs while dropping an element,
-    /// or if the `DrainFilter` value is leaked.
+    /// or if the `ExtractIf` value is leaked.
     ///
     /// Keeps the allocated memory for reuse.
     ///
@@ -862,8 +1107,8 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
     /// use hashbrown::HashMap;
     ///
     /// let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x)).collect();
-    /// let capacity_before_drain_filter = map.capacity();
-    /// let drained: HashMap<i32, i32> = map.drain_filter(|k, _v| k % 2 == 0).collect();
+    /// let capacity_before_extract_if = map.capacity();
+    /// let drained: HashMap<i32, i32> = map.extract_if(|k, _v| k % 2 == 0).collect();
     ///
     /// let mut evens = drained.keys().cloned().collect::<Vec<_>>();
     /// let mut odds = map.keys().cloned().collect::<Vec<_>>();
@@ -873,32 +1118,45 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
     /// assert_eq!(evens, vec![0, 2, 4, 6]);
     /// assert_eq!(odds, vec![1, 3, 5, 7]);
     /// // Map capacity is equal to old one.
-    /// assert_eq!(map.capacity(), capacity_before_drain_filter);
+    /// assert_eq!(map.capacity(), capacity_before_extract_if);
     ///
     /// let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x)).collect();
     ///
     /// {   // Iterator is dropped without being consumed.
-    ///     let d = map.drain_filter(|k, _v| k % 2 != 0);
+    ///     let d = map.extract_if(|k, _v| k % 2 != 0);
     /// }
     ///
     /// // But the map lens have been reduced by half
-    /// // even if we do not use DrainFilter iterator.
+    /// // even if we do not use ExtractIf iterator.
     /// assert_eq!(map.len(), 4);
     /// ```
     #[cfg_attr(feature = "inline-more", inline)]
-    pub fn drain_filter<F>(&mut self, f: F) -> DrainFilter<'_, K, V, F, A>
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, K, V, F, A>
     where
         F: FnMut(&K, &mut V) -> bool,
     {
-        DrainFilter {
+        ExtractIf {
             f,
-            inner: DrainFilterInner {
+            inner: ExtractIfInner {
                 iter: unsafe { self.table.iter() },
                 table: &mut self.table,
             },
         }
     }
 
+    /// Drains elements which are true under the given predicate,
+    /// and returns an iterator over the removed items.
+    ///
+    /// This method has been renamed to [`extract_if`](HashMap::extract_if).
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[deprecated(since = "0.12.4", note = "renamed to `extract_if`")]
+    pub fn drain_filter<F>(&mut self, f: F) -> ExtractIf<'_, K, V, F, A>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.extract_if(f)
+    }
+
     /// Clears the map, removing all key-value pairs. Keeps the allocated memory
     /// for reuse.
     ///
@@ -923,6 +1181,37 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
         self.table.clear();
     }
 
+    /// Clears the map, removing all key-value pairs, and shrinks its
+    /// allocation so that it holds at most enough memory for `max_capacity`
+    /// elements.
+    ///
+    /// If the map had grown larger than `max_capacity`, it is reallocated
+    /// directly to the smaller size. No elements have to be moved since the
+    /// map is empty. Otherwise the allocated memory is kept for reuse, as
+    /// with [`clear`].
+    ///
+    /// This bounds the memory held by a map which is cleared and reused after
+    /// occasionally growing very large.
+    ///
+    /// [`clear`]: #method.clear
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut a: HashMap<i32, i32> = (0..1000).map(|i| (i, i)).collect();
+    /// assert!(a.capacity() >= 1000);
+    ///
+    /// a.clear_and_shrink_to(16);
+    /// assert!(a.is_empty());
+    /// assert!(a.capacity() >= 16 && a.capacity() < 1000);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn clear_and_shrink_to(&mut self, max_capacity: usize) {
+        self.table.clear_and_shrink_to(max_capacity);
+    }
+
     /// Creates a consuming iterator visiting all the keys in arbitrary order.
     /// The map cannot be used after calling this.
     /// The iterator element type is `K`.
@@ -990,6 +1279,10 @@ where
     /// in the `HashMap`. The collection may reserve more space to avoid
     /// frequent reallocations.
     ///
+    /// Whenever the map has to grow, it at least doubles its number of
+    /// buckets, so calling `reserve(1)` before each insertion only reallocates
+    /// a logarithmic number of times.
+    ///
     /// # Panics
     ///
     /// Panics if the new allocation size overflows [`usize`].
@@ -1012,7 +1305,38 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn reserve(&mut self, additional: usize) {
         self.table
-            .reserve(additional, make_hasher::<K, _, V, S>(&self.hash_builder));
+            .reserve(additional, make_hasher::<_, V, S>(&self.hash_builder));
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the `HashMap`, without deliberately over-allocating.
+    ///
+    /// Unlike [`reserve`](HashMap::reserve), growing the map does not at least
+    /// double its number of buckets, so prefer `reserve` when more insertions
+    /// are likely to follow. The capacity may still end up larger than
+    /// requested because of the load factor and the power of two number of
+    /// buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// [`usize`]: https://doc.rust-lang.org/std/primitive.usize.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    /// let mut map: HashMap<i32, i32> = (0..100).map(|i| (i, i)).collect();
+    ///
+    /// map.reserve_exact(100);
+    /// assert!(map.capacity() >= 200);
+    /// assert_eq!(map.capacity(), HashMap::<i32, i32>::with_capacity(200).capacity());
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.table
+            .reserve_exact(additional, make_hasher::<_, V, S>(&self.hash_builder));
     }
 
     /// Tries to reserve capacity for at least `additional` more elements to be inserted
@@ -1062,13 +1386,41 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
         self.table
-            .try_reserve(additional, make_hasher::<K, _, V, S>(&self.hash_builder))
+            .try_reserve(additional, make_hasher::<_, V, S>(&self.hash_builder))
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the `HashMap`, without deliberately over-allocating.
+    ///
+    /// See [`reserve_exact`](HashMap::reserve_exact).
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error
+    /// is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map: HashMap<&str, isize> = HashMap::new();
+    /// map.try_reserve_exact(10).expect("why is the test harness OOMing on 10 bytes?");
+    /// assert!(map.capacity() >= 10);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.table
+            .try_reserve_exact(additional, make_hasher::<_, V, S>(&self.hash_builder))
     }
 
     /// Shrinks the capacity of the map as much as possible. It will drop
     /// down as much as possible while maintaining the internal rules
     /// and possibly leaving some space in accordance with the resize policy.
     ///
+    /// If the map is empty, its allocation is freed entirely and it returns to
+    /// the same state as a map created with [`new`](HashMap::new).
+    ///
     /// # Examples
     ///
     /// ```
@@ -1080,11 +1432,15 @@ where
     /// assert!(map.capacity() >= 100);
     /// map.shrink_to_fit();
     /// assert!(map.capacity() >= 2);
+    ///
+    /// map.clear();
+    /// map.shrink_to_fit();
+    /// assert_eq!(map.capacity(), 0);
     /// ```
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn shrink_to_fit(&mut self) {
         self.table
-            .shrink_to(0, make_hasher::<K, _, V, S>(&self.hash_builder));
+            .shrink_to(0, make_hasher::<_, V, S>(&self.hash_builder));
     }
 
     /// Shrinks the capacity of the map with a lower limit. It will drop
@@ -1113,7 +1469,32 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn shrink_to(&mut self, min_capacity: usize) {
         self.table
-            .shrink_to(min_capacity, make_hasher::<K, _, V, S>(&self.hash_builder));
+            .shrink_to(min_capacity, make_hasher::<_, V, S>(&self.hash_builder));
+    }
+
+    /// Rehashes the entries of the map in place, without reallocating.
+    ///
+    /// Removing entries can leave tombstones behind, which take up capacity
+    /// and make lookups probe further than they would otherwise need to. The
+    /// map clears them on its own once an insertion runs out of capacity;
+    /// this method does so immediately, for example after removing a large
+    /// part of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..1000).map(|i| (i, i)).collect();
+    /// map.retain(|&k, _| k % 10 == 0);
+    /// map.rehash();
+    /// assert_eq!(map.len(), 100);
+    /// assert_eq!(map[&500], 500);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn rehash(&mut self) {
+        self.table
+            .rehash_in_place(make_hasher::<_, V, S>(&self.hash_builder));
     }
 
     /// Gets the given key's corresponding entry in the map for in-place manipulation.
@@ -1138,6 +1519,44 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S, A> {
         let hash = make_insert_hash::<K, S>(&self.hash_builder, &key);
+        self.entry_inner(hash, key)
+    }
+
+    /// Gets the given key's corresponding entry in the map for in-place manipulation,
+    /// using a hash which was computed ahead of time by the caller.
+    ///
+    /// The `hash` must be the value the map's [`hasher`](HashMap::hasher)
+    /// produces for `key`. This is checked in debug builds; in release builds
+    /// a mismatched hash causes the entry to look in the wrong place, which may
+    /// lead to duplicate keys or missed lookups later on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::HashMap;
+    ///
+    /// fn hash_key<S: BuildHasher>(hash_builder: &S, key: &str) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     key.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let mut map: HashMap<&str, u32> = HashMap::new();
+    /// let hash = hash_key(map.hasher(), "poneyland");
+    ///
+    /// *map.entry_with_hash(hash, "poneyland").or_insert(0) += 1;
+    /// *map.entry_with_hash(hash, "poneyland").or_insert(0) += 1;
+    /// assert_eq!(map["poneyland"], 2);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn entry_with_hash(&mut self, hash: u64, key: K) -> Entry<'_, K, V, S, A> {
+        debug_assert_eq!(hash, make_insert_hash::<K, S>(&self.hash_builder, &key));
+        self.entry_inner(hash, key)
+    }
+
+    #[inline]
+    fn entry_inner(&mut self, hash: u64, key: K) -> Entry<'_, K, V, S, A> {
         if let Some(elem) = self.table.find(hash, equivalent_key(&key)) {
             Entry::Occupied(OccupiedEntry {
                 hash,
@@ -1156,6 +1575,12 @@ where
 
     /// Gets the given key's corresponding entry by reference in the map for in-place manipulation.
     ///
+    /// The borrowed key is only converted into an owned `K`, through its
+    /// `From<&Q>` implementation, when a value is inserted into a vacant
+    /// entry. Working with an occupied entry never creates an owned key, so
+    /// for example `map.entry_ref(cow.as_ref()).or_insert(v)` does not
+    /// allocate a new key when `cow` is already in the map.
+    ///
     /// # Examples
     ///
     /// ```
@@ -1174,10 +1599,9 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn entry_ref<'a, 'b, Q: ?Sized>(&'a mut self, key: &'b Q) -> EntryRef<'a, 'b, K, Q, V, S, A>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
-        let hash = make_hash::<K, Q, S>(&self.hash_builder, key);
+        let hash = make_hash::<Q, S>(&self.hash_builder, key);
         if let Some(elem) = self.table.find(hash, equivalent_key(key)) {
             EntryRef::Occupied(OccupiedEntryRef {
                 hash,
@@ -1194,6 +1618,90 @@ where
         }
     }
 
+    /// Returns a mutable reference to the value corresponding to the key,
+    /// inserting the value returned by `make` if the key is not present.
+    ///
+    /// Unlike [`entry`], this takes the key by reference and only converts it
+    /// into an owned key, and only calls `make`, if the key is missing.
+    ///
+    /// [`entry`]: HashMap::entry
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut cache: HashMap<String, usize> = HashMap::new();
+    /// for &word in &["poneyland", "horseyland", "poneyland"] {
+    ///     *cache.get_or_insert_with(word, || 0) += 1;
+    /// }
+    ///
+    /// assert_eq!(cache.len(), 2);
+    /// assert_eq!(cache["poneyland"], 2);
+    /// assert_eq!(cache["horseyland"], 1);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get_or_insert_with<Q: ?Sized, F>(&mut self, key: &Q, make: F) -> &mut V
+    where
+        Q: Hash + Equivalent<K> + ToOwned<Owned = K>,
+        F: FnOnce() -> V,
+    {
+        let hash = make_hash::<Q, S>(&self.hash_builder, key);
+        let bucket = match self.table.find(hash, equivalent_key(key)) {
+            Some(bucket) => bucket,
+            None => self.table.insert(
+                hash,
+                (key.to_owned(), make()),
+                make_hasher::<_, V, S>(&self.hash_builder),
+            ),
+        };
+        unsafe { &mut bucket.as_mut().1 }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key,
+    /// inserting the value returned by `make` if the key is not present and
+    /// `make` succeeds.
+    ///
+    /// If the key is missing and `make` returns an error, the error is
+    /// returned, `key` is dropped and the map is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut cache: HashMap<&str, u32> = HashMap::new();
+    ///
+    /// let parsed = cache.get_or_try_insert_with("42", || "42".parse());
+    /// assert_eq!(parsed, Ok(&mut 42));
+    ///
+    /// let failed = cache.get_or_try_insert_with("pony", || "pony".parse());
+    /// assert!(failed.is_err());
+    /// assert!(!cache.contains_key("pony"));
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get_or_try_insert_with<F, E>(&mut self, key: K, make: F) -> Result<&mut V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        let hash = make_hash::<K, S>(&self.hash_builder, &key);
+        let bucket = match self.table.find(hash, equivalent_key(&key)) {
+            Some(bucket) => bucket,
+            None => {
+                // No slot is claimed until `make` has succeeded, so there is
+                // nothing to roll back on error.
+                let value = make()?;
+                self.table.insert(
+                    hash,
+                    (key, value),
+                    make_hasher::<_, V, S>(&self.hash_builder),
+                )
+            }
+        };
+        Ok(unsafe { &mut bucket.as_mut().1 })
+    }
+
     /// Returns a reference to the value corresponding to the key.
     ///
     /// The key may be any borrowed form of the map's key type, but
@@ -1216,8 +1724,7 @@ where
     #[inline]
     pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.get_inner(k) {
@@ -1226,6 +1733,44 @@ where
         }
     }
 
+    /// Returns a reference to the value corresponding to the key, using a hash
+    /// which was computed ahead of time by the caller.
+    ///
+    /// The `hash` must be the value the map's [`hasher`](HashMap::hasher)
+    /// produces for `k`. This is checked in debug builds; in release builds a
+    /// mismatched hash may cause the lookup to miss.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::HashMap;
+    ///
+    /// fn hash_key<S: BuildHasher>(hash_builder: &S, key: &i32) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     key.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(1, "a");
+    /// let (hash1, hash2) = (hash_key(map.hasher(), &1), hash_key(map.hasher(), &2));
+    /// assert_eq!(map.get_with_hash(hash1, &1), Some(&"a"));
+    /// assert_eq!(map.get_with_hash(hash2, &2), None);
+    /// ```
+    #[inline]
+    pub fn get_with_hash<Q: ?Sized>(&self, hash: u64, k: &Q) -> Option<&V>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        debug_assert_eq!(hash, make_hash::<Q, S>(&self.hash_builder, k));
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.table.get(hash, equivalent_key(k)) {
+            Some(&(_, ref v)) => Some(v),
+            None => None,
+        }
+    }
+
     /// Returns the key-value pair corresponding to the supplied key.
     ///
     /// The supplied key may be any borrowed form of the map's key type, but
@@ -1248,8 +1793,7 @@ where
     #[inline]
     pub fn get_key_value<Q: ?Sized>(&self, k: &Q) -> Option<(&K, &V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.get_inner(k) {
@@ -1261,13 +1805,12 @@ where
     #[inline]
     fn get_inner<Q: ?Sized>(&self, k: &Q) -> Option<&(K, V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         if self.table.is_empty() {
             None
         } else {
-            let hash = make_hash::<K, Q, S>(&self.hash_builder, k);
+            let hash = make_hash::<Q, S>(&self.hash_builder, k);
             self.table.get(hash, equivalent_key(k))
         }
     }
@@ -1298,8 +1841,7 @@ where
     #[inline]
     pub fn get_key_value_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<(&K, &mut V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.get_inner_mut(k) {
@@ -1330,8 +1872,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn contains_key<Q: ?Sized>(&self, k: &Q) -> bool
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.get_inner(k).is_some()
     }
@@ -1362,8 +1903,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut V>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.get_inner_mut(k) {
@@ -1375,13 +1915,12 @@ where
     #[inline]
     fn get_inner_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut (K, V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         if self.table.is_empty() {
             None
         } else {
-            let hash = make_hash::<K, Q, S>(&self.hash_builder, k);
+            let hash = make_hash::<Q, S>(&self.hash_builder, k);
             self.table.get_mut(hash, equivalent_key(k))
         }
     }
@@ -1431,8 +1970,53 @@ where
     /// ```
     pub fn get_many_mut<Q: ?Sized, const N: usize>(&mut self, ks: [&Q; N]) -> Option<[&'_ mut V; N]>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
+    {
+        self.get_many_mut_inner(ks)
+            .ok()
+            .map(|res| res.map(|(_, v)| v))
+    }
+
+    /// Attempts to get mutable references to `N` values in the map at once,
+    /// reporting which keys were the problem if that is not possible.
+    ///
+    /// This behaves like [`get_many_mut`](HashMap::get_many_mut), but instead
+    /// of `None` it returns [`GetManyMutError::MissingKey`] with the index of
+    /// the first key that was not found, or [`GetManyMutError::DuplicateKey`]
+    /// with the indices of the first two keys that refer to the same value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::{GetManyMutError, HashMap};
+    ///
+    /// let mut libraries = HashMap::new();
+    /// libraries.insert("Bodleian Library".to_string(), 1602);
+    /// libraries.insert("Athenæum".to_string(), 1807);
+    /// libraries.insert("Library of Congress".to_string(), 1800);
+    ///
+    /// let got = libraries.try_get_many_mut(["Athenæum", "Library of Congress"]);
+    /// assert_eq!(got, Ok([&mut 1807, &mut 1800]));
+    ///
+    /// let got = libraries.try_get_many_mut([
+    ///     "Athenæum",
+    ///     "New York Public Library",
+    /// ]);
+    /// assert_eq!(got, Err(GetManyMutError::MissingKey(1)));
+    ///
+    /// let got = libraries.try_get_many_mut([
+    ///     "Athenæum",
+    ///     "Bodleian Library",
+    ///     "Athenæum",
+    /// ]);
+    /// assert_eq!(got, Err(GetManyMutError::DuplicateKey(0, 2)));
+    /// ```
+    pub fn try_get_many_mut<Q: ?Sized, const N: usize>(
+        &mut self,
+        ks: [&Q; N],
+    ) -> Result<[&'_ mut V; N], GetManyMutError>
+    where
+        Q: Hash + Equivalent<K>,
     {
         self.get_many_mut_inner(ks).map(|res| res.map(|(_, v)| v))
     }
@@ -1463,10 +2047,12 @@ where
     /// libraries.insert("Herzogin-Anna-Amalia-Bibliothek".to_string(), 1691);
     /// libraries.insert("Library of Congress".to_string(), 1800);
     ///
-    /// let got = libraries.get_many_mut([
-    ///     "Athenæum",
-    ///     "Library of Congress",
-    /// ]);
+    /// let got = unsafe {
+    ///     libraries.get_many_unchecked_mut([
+    ///         "Athenæum",
+    ///         "Library of Congress",
+    ///     ])
+    /// };
     /// assert_eq!(
     ///     got,
     ///     Some([
@@ -1476,10 +2062,12 @@ where
     /// );
     ///
     /// // Missing keys result in None
-    /// let got = libraries.get_many_mut([
-    ///     "Athenæum",
-    ///     "New York Public Library",
-    /// ]);
+    /// let got = unsafe {
+    ///     libraries.get_many_unchecked_mut([
+    ///         "Athenæum",
+    ///         "New York Public Library",
+    ///     ])
+    /// };
     /// assert_eq!(got, None);
     /// ```
     pub unsafe fn get_many_unchecked_mut<Q: ?Sized, const N: usize>(
@@ -1487,8 +2075,7 @@ where
         ks: [&Q; N],
     ) -> Option<[&'_ mut V; N]>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.get_many_unchecked_mut_inner(ks)
             .map(|res| res.map(|(_, v)| v))
@@ -1543,10 +2130,10 @@ where
         ks: [&Q; N],
     ) -> Option<[(&'_ K, &'_ mut V); N]>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.get_many_mut_inner(ks)
+            .ok()
             .map(|res| res.map(|(k, v)| (&*k, v)))
     }
 
@@ -1599,24 +2186,65 @@ where
         ks: [&Q; N],
     ) -> Option<[(&'_ K, &'_ mut V); N]>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.get_many_unchecked_mut_inner(ks)
             .map(|res| res.map(|(k, v)| (&*k, v)))
     }
 
+    /// Attempts to get mutable references to the values of a runtime number
+    /// of keys at once.
+    ///
+    /// This behaves like [`get_many_mut`](`HashMap::get_many_mut`), but takes
+    /// a slice of keys and returns the references in a `Vec`, in the same
+    /// order as the keys. `None` will be returned if any of the keys are
+    /// duplicates or missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut scores = HashMap::new();
+    /// scores.insert("a".to_string(), 1);
+    /// scores.insert("b".to_string(), 2);
+    /// scores.insert("c".to_string(), 3);
+    ///
+    /// let keys: Vec<&str> = vec!["c", "a"];
+    /// for score in scores.get_disjoint_mut(&keys).unwrap() {
+    ///     *score *= 10;
+    /// }
+    /// assert_eq!(scores["a"], 10);
+    /// assert_eq!(scores["c"], 30);
+    ///
+    /// // Duplicate or missing keys result in None
+    /// assert_eq!(scores.get_disjoint_mut(&["a", "b", "a"]), None);
+    /// assert_eq!(scores.get_disjoint_mut(&["a", "d"]), None);
+    /// ```
+    pub fn get_disjoint_mut<Q: ?Sized>(&mut self, ks: &[&Q]) -> Option<Vec<&'_ mut V>>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        let hashes: Vec<u64> = ks
+            .iter()
+            .map(|k| make_hash::<Q, S>(&self.hash_builder, k))
+            .collect();
+        let entries = self
+            .table
+            .get_disjoint_mut(&hashes, |i, (k, _)| ks[i].equivalent(k))?;
+        Some(entries.into_iter().map(|(_, v)| v).collect())
+    }
+
     fn get_many_mut_inner<Q: ?Sized, const N: usize>(
         &mut self,
         ks: [&Q; N],
-    ) -> Option<[&'_ mut (K, V); N]>
+    ) -> Result<[&'_ mut (K, V); N], GetManyMutError>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         let hashes = self.build_hashes_inner(ks);
         self.table
-            .get_many_mut(hashes, |i, (k, _)| ks[i].eq(k.borrow()))
+            .try_get_many_mut(hashes, |i, (k, _)| ks[i].equivalent(k))
     }
 
     unsafe fn get_many_unchecked_mut_inner<Q: ?Sized, const N: usize>(
@@ -1624,22 +2252,20 @@ where
         ks: [&Q; N],
     ) -> Option<[&'_ mut (K, V); N]>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         let hashes = self.build_hashes_inner(ks);
         self.table
-            .get_many_unchecked_mut(hashes, |i, (k, _)| ks[i].eq(k.borrow()))
+            .get_many_unchecked_mut(hashes, |i, (k, _)| ks[i].equivalent(k))
     }
 
     fn build_hashes_inner<Q: ?Sized, const N: usize>(&self, ks: [&Q; N]) -> [u64; N]
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         let mut hashes = [0_u64; N];
         for i in 0..N {
-            hashes[i] = make_hash::<K, Q, S>(&self.hash_builder, ks[i]);
+            hashes[i] = make_hash::<Q, S>(&self.hash_builder, ks[i]);
         }
         hashes
     }
@@ -1673,12 +2299,18 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn insert(&mut self, k: K, v: V) -> Option<V> {
         let hash = make_insert_hash::<K, S>(&self.hash_builder, &k);
-        if let Some((_, item)) = self.table.get_mut(hash, equivalent_key(&k)) {
-            Some(mem::replace(item, v))
-        } else {
-            self.table
-                .insert(hash, (k, v), make_hasher::<K, _, V, S>(&self.hash_builder));
-            None
+        let hasher = make_hasher::<_, V, S>(&self.hash_builder);
+        match self
+            .table
+            .find_or_find_insert_slot(hash, equivalent_key(&k), hasher)
+        {
+            Ok(bucket) => Some(mem::replace(unsafe { &mut bucket.as_mut().1 }, v)),
+            Err(slot) => {
+                let _bucket = unsafe { self.table.insert_in_slot(hash, slot, (k, v)) };
+                #[cfg(debug_assertions)]
+                debug_assert_key_findable(&self.table, &self.hash_builder, hash, &_bucket);
+                None
+            }
         }
     }
 
@@ -1736,7 +2368,7 @@ where
         let hash = make_insert_hash::<K, S>(&self.hash_builder, &k);
         let bucket = self
             .table
-            .insert(hash, (k, v), make_hasher::<K, _, V, S>(&self.hash_builder));
+            .insert(hash, (k, v), make_hasher::<_, V, S>(&self.hash_builder));
         let (k_ref, v_ref) = unsafe { bucket.as_mut() };
         (k_ref, v_ref)
     }
@@ -1812,8 +2444,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn remove<Q: ?Sized>(&mut self, k: &Q) -> Option<V>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.remove_entry(k) {
@@ -1853,12 +2484,299 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn remove_entry<Q: ?Sized>(&mut self, k: &Q) -> Option<(K, V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
-        let hash = make_hash::<K, Q, S>(&self.hash_builder, k);
+        let hash = make_hash::<Q, S>(&self.hash_builder, k);
         self.table.remove_entry(hash, equivalent_key(k))
     }
+
+    /// Removes a key from the map, returning the value at the key if the key
+    /// was previously in the map, using a hash which was computed ahead of time
+    /// by the caller. Keeps the allocated memory for reuse.
+    ///
+    /// The `hash` must be the value the map's [`hasher`](HashMap::hasher)
+    /// produces for `k`. This is checked in debug builds; in release builds a
+    /// mismatched hash may cause the key to not be found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::HashMap;
+    ///
+    /// fn hash_key<S: BuildHasher>(hash_builder: &S, key: &i32) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     key.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(1, "a");
+    /// let hash = hash_key(map.hasher(), &1);
+    /// assert_eq!(map.remove_with_hash(hash, &1), Some("a"));
+    /// assert_eq!(map.remove_with_hash(hash, &1), None);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn remove_with_hash<Q: ?Sized>(&mut self, hash: u64, k: &Q) -> Option<V>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        debug_assert_eq!(hash, make_hash::<Q, S>(&self.hash_builder, k));
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.table.remove_entry(hash, equivalent_key(k)) {
+            Some((_, v)) => Some(v),
+            None => None,
+        }
+    }
+
+    /// Visits every entry, keeping or removing it according to `f`, and
+    /// optionally replacing removed entries with new ones.
+    ///
+    /// For each pair `(k, v)`, `f(&k, &mut v)` returns a [`TransformAction`]:
+    /// [`Keep`] leaves the entry in place, [`Remove`] drops it and
+    /// [`RemoveAndInsert`] drops it and queues the returned pair for insertion.
+    ///
+    /// The queued pairs are only inserted once every entry has been visited, so
+    /// `f` never sees them. They are inserted in the order they were queued, as
+    /// with [`insert`]: a queued key which is already present in the map (or was
+    /// queued earlier) replaces the value stored for it.
+    ///
+    /// [`Keep`]: TransformAction::Keep
+    /// [`Remove`]: TransformAction::Remove
+    /// [`RemoveAndInsert`]: TransformAction::RemoveAndInsert
+    /// [`insert`]: HashMap::insert
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{HashMap, TransformAction};
+    ///
+    /// let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+    /// map.transform_retain(|&k, v| match k % 3 {
+    ///     0 => TransformAction::Keep,
+    ///     1 => TransformAction::Remove,
+    ///     _ => TransformAction::RemoveAndInsert(k + 100, *v),
+    /// });
+    ///
+    /// let mut vec: Vec<(i32, i32)> = map.into_iter().collect();
+    /// vec.sort_unstable();
+    /// assert_eq!(vec, [(0, 0), (3, 30), (6, 60), (102, 20), (105, 50)]);
+    /// ```
+    pub fn transform_retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> TransformAction<K, V>,
+    {
+        let mut pending = Vec::new();
+        // Here we only use `iter` as a temporary, preventing use-after-free
+        unsafe {
+            for item in self.table.iter() {
+                let &mut (ref key, ref mut value) = item.as_mut();
+                match f(key, value) {
+                    TransformAction::Keep => {}
+                    TransformAction::Remove => self.table.erase(item),
+                    TransformAction::RemoveAndInsert(k, v) => {
+                        self.table.erase(item);
+                        pending.push((k, v));
+                    }
+                }
+            }
+        }
+        self.reserve(pending.len());
+        for (k, v) in pending {
+            self.insert(k, v);
+        }
+    }
+
+    /// Moves all entries of `other` into the map. When a key is present in
+    /// both maps, the value from `other` replaces the existing one, but the
+    /// existing key is kept.
+    ///
+    /// This is equivalent to calling [`merge_with`] with a resolver that
+    /// overwrites the value.
+    ///
+    /// [`merge_with`]: #method.merge_with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut a: HashMap<&str, i32> = [("x", 1), ("y", 2)].into();
+    /// let b: HashMap<&str, i32> = [("y", 20), ("z", 30)].into();
+    ///
+    /// a.merge(b);
+    /// assert_eq!(a, [("x", 1), ("y", 20), ("z", 30)].into());
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn merge(&mut self, other: HashMap<K, V, S, A>) {
+        self.merge_with(other, |_, v, new| *v = new);
+    }
+
+    /// Moves all entries of `other` into the map, calling `resolve` to
+    /// combine the values of keys present in both maps.
+    ///
+    /// For such keys, `resolve(key, value, other_value)` is called with the
+    /// existing key and a mutable reference to the existing value, and is
+    /// expected to merge `other_value` into it in place. Keys only present in
+    /// `other` are moved into the map unchanged. Since `other` is consumed, no
+    /// keys or values are cloned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let mut a: HashMap<&str, i32> = [("x", 1), ("y", 2)].into();
+    /// let b: HashMap<&str, i32> = [("y", 20), ("z", 30)].into();
+    ///
+    /// a.merge_with(b, |_, v, other| *v += other);
+    /// assert_eq!(a, [("x", 1), ("y", 22), ("z", 30)].into());
+    /// ```
+    pub fn merge_with<F>(&mut self, other: HashMap<K, V, S, A>, mut resolve: F)
+    where
+        F: FnMut(&K, &mut V, V),
+    {
+        self.reserve(other.len());
+
+        for (k, v) in other {
+            let hash = make_insert_hash::<K, S>(&self.hash_builder, &k);
+            let hasher = make_hasher::<_, V, S>(&self.hash_builder);
+            match self
+                .table
+                .find_or_find_insert_slot(hash, equivalent_key(&k), hasher)
+            {
+                Ok(bucket) => {
+                    let &mut (ref key, ref mut value) = unsafe { bucket.as_mut() };
+                    resolve(key, value, v);
+                }
+                Err(slot) => unsafe {
+                    self.table.insert_in_slot(hash, slot, (k, v));
+                },
+            }
+        }
+    }
+
+    /// Replaces the contents of the map with clones of the entries in
+    /// `source`, switching to `hash_builder` for all future hashing.
+    ///
+    /// Unlike [`clone_from`], `source` may use a different hasher type and
+    /// every element is rehashed with the new hasher, so the layout of the
+    /// resulting table is independent of `source`. The existing allocation
+    /// is reused if it is large enough to hold all of the entries.
+    ///
+    /// If cloning an entry panics, the map is left holding the entries
+    /// cloned so far.
+    ///
+    /// [`clone_from`]: Clone::clone_from
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{DefaultHashBuilder, HashMap};
+    ///
+    /// let source: HashMap<i32, &str> = [(1, "a"), (2, "b")].into_iter().collect();
+    ///
+    /// let mut map = HashMap::with_capacity(10);
+    /// map.insert(3, "c");
+    /// map.clone_from_with_hasher(&source, DefaultHashBuilder::default());
+    ///
+    /// assert_eq!(map, source);
+    /// assert!(map.capacity() >= 10);
+    /// ```
+    pub fn clone_from_with_hasher<S2>(&mut self, source: &HashMap<K, V, S2, A>, hash_builder: S)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.table.clear();
+        self.hash_builder = hash_builder;
+        self.reserve(source.len());
+
+        // The keys in `source` are distinct and we reserved enough space for
+        // all of them above, so this never needs to probe for equal keys or
+        // grow the table.
+        for (k, v) in source.iter() {
+            let hash = make_insert_hash::<K, S>(&self.hash_builder, k);
+            self.table.insert(
+                hash,
+                (k.clone(), v.clone()),
+                make_hasher::<_, V, S>(&self.hash_builder),
+            );
+        }
+    }
+
+    /// Returns `true` if both maps hold the same entries, even if they use
+    /// different hasher types.
+    ///
+    /// This is the comparison done by `==`, which is only implemented between
+    /// maps with the same hasher type. Each key of `self` is looked up in
+    /// `other` with `other`'s hasher.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{DefaultHashBuilder, HashMap};
+    /// use std::collections::hash_map::RandomState;
+    ///
+    /// let mut a = HashMap::with_hasher(RandomState::new());
+    /// let mut b = HashMap::with_hasher(DefaultHashBuilder::default());
+    /// a.insert(1, "a");
+    /// b.insert(1, "a");
+    /// assert!(a.eq_contents(&b));
+    ///
+    /// b.insert(2, "b");
+    /// assert!(!a.eq_contents(&b));
+    /// ```
+    pub fn eq_contents<S2>(&self, other: &HashMap<K, V, S2, A>) -> bool
+    where
+        V: PartialEq,
+        S2: BuildHasher,
+    {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        self.iter()
+            .all(|(key, value)| other.get(key).map_or(false, |v| *value == *v))
+    }
+
+    /// An iterator visiting all key-value pairs ordered by the hash of their
+    /// key. The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// The hash of every key is recomputed with the map's hasher, so the order
+    /// does not depend on the bucket layout or on insertion history, and does
+    /// not require `K: Ord`. It is only reproducible across runs if the hasher
+    /// itself is deterministic (i.e. not randomly seeded), and keys whose
+    /// hashes collide are yielded in an unspecified relative order. Use
+    /// [`iter_sorted`] when `K: Ord` is available.
+    ///
+    /// The entries are collected and sorted up front, so this allocates and
+    /// takes `O(n log n)` time.
+    ///
+    /// [`iter_sorted`]: #method.iter_sorted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::BuildHasherDefault;
+    /// use hashbrown::HashMap;
+    /// use std::collections::hash_map::DefaultHasher;
+    ///
+    /// type Map<K, V> = HashMap<K, V, BuildHasherDefault<DefaultHasher>>;
+    ///
+    /// let a: Map<u32, u32> = (0..16).map(|i| (i, i)).collect();
+    /// let b: Map<u32, u32> = (0..16).rev().map(|i| (i, i)).collect();
+    ///
+    /// assert!(a.iter_sorted_by_hash().eq(b.iter_sorted_by_hash()));
+    /// ```
+    pub fn iter_sorted_by_hash(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut entries: Vec<(u64, &K, &V)> = self
+            .iter()
+            .map(|(k, v)| (make_hash::<K, S>(&self.hash_builder, k), k, v))
+            .collect();
+        entries.sort_unstable_by_key(|&(hash, _, _)| hash);
+        entries.into_iter().map(|(_, k, v)| (k, v))
+    }
 }
 
 impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
@@ -2013,6 +2931,37 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
         RawEntryBuilder { map: self }
     }
 
+    /// Returns a shared reference to the [`RawTable`] used underneath [`HashMap`].
+    /// This function is only available if the `raw` feature of the crate is enabled.
+    ///
+    /// See [`raw_table`] for the invariants of the table.
+    ///
+    /// # Safety
+    ///
+    /// The caller must uphold the invariants described in [`raw_table`] for
+    /// as long as the returned reference is used. In particular, elements
+    /// reached through [`Bucket::as_mut`] must not have their keys changed in
+    /// a way that changes their hash or their equality with other keys.
+    ///
+    /// [`raw_table`]: Self::raw_table
+    /// [`Bucket::as_mut`]: crate::raw::Bucket::as_mut
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let map: HashMap<_, _> = [("a", 10), ("b", 20)].into();
+    /// let raw_table = unsafe { map.raw_table_ref() };
+    /// assert_eq!(raw_table.len(), 2);
+    /// assert!(raw_table.buckets() >= 2);
+    /// ```
+    #[cfg(feature = "raw")]
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub unsafe fn raw_table_ref(&self) -> &RawTable<(K, V), A> {
+        &self.table
+    }
+
     /// Returns a mutable reference to the [`RawTable`] used underneath [`HashMap`].
     /// This function is only available if the `raw` feature of the crate is enabled.
     ///
@@ -2024,6 +2973,14 @@ impl<K, V, S, A: Allocator + Clone> HashMap<K, V, S, A> {
     /// `RawTable` API gives the lowest level of control under the map that can be useful
     /// for extending the HashMap's API, but may lead to *[undefined behavior]*.
     ///
+    /// Every element of the table must be stored in the bucket probed for the
+    /// hash which the map's hasher computes for its key, and no two elements
+    /// may have equal keys. The `RawTable` methods which take a hash or a
+    /// hasher trust the caller on this: breaking it does not cause undefined
+    /// behavior by itself, but the map's methods may then fail to find or
+    /// remove elements, or return and iterate over duplicate keys, just as
+    /// with a key type whose `Hash` or `Eq` implementation is inconsistent.
+    ///
     /// [`HashMap`]: struct.HashMap.html
     /// [`RawTable`]: raw/struct.RawTable.html
     /// [undefined behavior]: https://doc.rust-lang.org/reference/behavior-considered-undefined.html
@@ -2083,12 +3040,7 @@ where
     A: Allocator + Clone,
 {
     fn eq(&self, other: &Self) -> bool {
-        if self.len() != other.len() {
-            return false;
-        }
-
-        self.iter()
-            .all(|(key, value)| other.get(key).map_or(false, |v| *value == *v))
+        self.eq_contents(other)
     }
 }
 
@@ -2140,8 +3092,8 @@ where
 
 impl<K, Q: ?Sized, V, S, A> Index<&Q> for HashMap<K, V, S, A>
 where
-    K: Eq + Hash + Borrow<Q>,
-    Q: Eq + Hash,
+    K: Eq + Hash,
+    Q: Hash + Equivalent<K>,
     S: BuildHasher,
     A: Allocator + Clone,
 {
@@ -2184,12 +3136,29 @@ where
     /// let map1 = HashMap::from([(1, 2), (3, 4)]);
     /// let map2: HashMap<_, _> = [(1, 2), (3, 4)].into();
     /// assert_eq!(map1, map2);
+    ///
+    /// // As with `collect`, later values for a duplicate key win.
+    /// let map3: HashMap<_, _> = HashMap::from([(1, 2), (3, 4), (1, 5)]);
+    /// assert_eq!(map3.len(), 2);
+    /// assert_eq!(map3[&1], 5);
     /// ```
     fn from(arr: [(K, V); N]) -> Self {
         arr.into_iter().collect()
     }
 }
 
+/// The action to take for an entry visited by [`HashMap::transform_retain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformAction<K, V> {
+    /// Keep the entry in the map.
+    Keep,
+    /// Remove the entry from the map.
+    Remove,
+    /// Remove the entry from the map, and insert the given key-value pair
+    /// once all entries have been visited.
+    RemoveAndInsert(K, V),
+}
+
 /// An iterator over the entries of a `HashMap` in arbitrary order.
 /// The iterator element type is `(&'a K, &'a V)`.
 ///
@@ -2334,6 +3303,16 @@ impl<K, V, A: Allocator + Clone> IntoIter<K, V, A> {
     }
 }
 
+impl<K: Clone, V: Clone, A: Allocator + Clone> Clone for IntoIter<K, V, A> {
+    /// Clones the items that have not been yielded yet into a new iterator.
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        IntoIter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
 /// An owning iterator over the keys of a `HashMap` in arbitrary order.
 /// The iterator element type is `K`.
 ///
@@ -2598,13 +3577,13 @@ impl<K, V, A: Allocator + Clone> Drain<'_, K, V, A> {
     }
 }
 
-/// A draining iterator over entries of a `HashMap` which don't satisfy the predicate
+/// A draining iterator over entries of a `HashMap` which satisfy the predicate
 /// `f(&k, &mut v)` in arbitrary order. The iterator element type is `(K, V)`.
 ///
-/// This `struct` is created by the [`drain_filter`] method on [`HashMap`]. See its
+/// This `struct` is created by the [`extract_if`] method on [`HashMap`]. See its
 /// documentation for more.
 ///
-/// [`drain_filter`]: struct.HashMap.html#method.drain_filter
+/// [`extract_if`]: struct.HashMap.html#method.extract_if
 /// [`HashMap`]: struct.HashMap.html
 ///
 /// # Examples
@@ -2614,30 +3593,30 @@ impl<K, V, A: Allocator + Clone> Drain<'_, K, V, A> {
 ///
 /// let mut map: HashMap<i32, &str> = [(1, "a"), (2, "b"), (3, "c")].into();
 ///
-/// let mut drain_filter = map.drain_filter(|k, _v| k % 2 != 0);
-/// let mut vec = vec![drain_filter.next(), drain_filter.next()];
+/// let mut extract_if = map.extract_if(|k, _v| k % 2 != 0);
+/// let mut vec = vec![extract_if.next(), extract_if.next()];
 ///
-/// // The `DrainFilter` iterator produces items in arbitrary order, so the
+/// // The `ExtractIf` iterator produces items in arbitrary order, so the
 /// // items must be sorted to test them against a sorted array.
 /// vec.sort_unstable();
 /// assert_eq!(vec, [Some((1, "a")),Some((3, "c"))]);
 ///
 /// // It is fused iterator
-/// assert_eq!(drain_filter.next(), None);
-/// assert_eq!(drain_filter.next(), None);
-/// drop(drain_filter);
+/// assert_eq!(extract_if.next(), None);
+/// assert_eq!(extract_if.next(), None);
+/// drop(extract_if);
 ///
 /// assert_eq!(map.len(), 1);
 /// ```
-pub struct DrainFilter<'a, K, V, F, A: Allocator + Clone = Global>
+pub struct ExtractIf<'a, K, V, F, A: Allocator + Clone = Global>
 where
     F: FnMut(&K, &mut V) -> bool,
 {
     f: F,
-    inner: DrainFilterInner<'a, K, V, A>,
+    inner: ExtractIfInner<'a, K, V, A>,
 }
 
-impl<'a, K, V, F, A> Drop for DrainFilter<'a, K, V, F, A>
+impl<'a, K, V, F, A> Drop for ExtractIf<'a, K, V, F, A>
 where
     F: FnMut(&K, &mut V) -> bool,
     A: Allocator + Clone,
@@ -2661,7 +3640,7 @@ impl<T: Iterator> Drop for ConsumeAllOnDrop<'_, T> {
     }
 }
 
-impl<K, V, F, A> Iterator for DrainFilter<'_, K, V, F, A>
+impl<K, V, F, A> Iterator for ExtractIf<'_, K, V, F, A>
 where
     F: FnMut(&K, &mut V) -> bool,
     A: Allocator + Clone,
@@ -2679,15 +3658,38 @@ where
     }
 }
 
-impl<K, V, F> FusedIterator for DrainFilter<'_, K, V, F> where F: FnMut(&K, &mut V) -> bool {}
+impl<K, V, F, A> FusedIterator for ExtractIf<'_, K, V, F, A>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    A: Allocator + Clone,
+{
+}
+
+impl<K, V, F, A> fmt::Debug for ExtractIf<'_, K, V, F, A>
+where
+    F: FnMut(&K, &mut V) -> bool,
+    A: Allocator + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("remaining", &self.inner.iter.len())
+            .finish()
+    }
+}
+
+/// A draining iterator over entries of a `HashMap` which satisfy a predicate.
+///
+/// This type has been renamed to [`ExtractIf`].
+#[deprecated(since = "0.12.4", note = "renamed to `ExtractIf`")]
+pub type DrainFilter<'a, K, V, F, A = Global> = ExtractIf<'a, K, V, F, A>;
 
-/// Portions of `DrainFilter` shared with `set::DrainFilter`
-pub(super) struct DrainFilterInner<'a, K, V, A: Allocator + Clone> {
+/// Portions of `ExtractIf` shared with `set::DrainFilter`
+pub(super) struct ExtractIfInner<'a, K, V, A: Allocator + Clone> {
     pub iter: RawIter<(K, V)>,
     pub table: &'a mut RawTable<(K, V), A>,
 }
 
-impl<K, V, A: Allocator + Clone> DrainFilterInner<'_, K, V, A> {
+impl<K, V, A: Allocator + Clone> ExtractIfInner<'_, K, V, A> {
     #[cfg_attr(feature = "inline-more", inline)]
     pub(super) fn next<F>(&mut self, f: &mut F) -> Option<(K, V)>
     where
@@ -3103,10 +4105,9 @@ impl<'a, K, V, S, A: Allocator + Clone> RawEntryBuilderMut<'a, K, V, S, A> {
     pub fn from_key<Q: ?Sized>(self, k: &Q) -> RawEntryMut<'a, K, V, S, A>
     where
         S: BuildHasher,
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
-        let hash = make_hash::<K, Q, S>(&self.map.hash_builder, k);
+        let hash = make_hash::<Q, S>(&self.map.hash_builder, k);
         self.from_key_hashed_nocheck(hash, k)
     }
 
@@ -3136,8 +4137,7 @@ impl<'a, K, V, S, A: Allocator + Clone> RawEntryBuilderMut<'a, K, V, S, A> {
     #[allow(clippy::wrong_self_convention)]
     pub fn from_key_hashed_nocheck<Q: ?Sized>(self, hash: u64, k: &Q) -> RawEntryMut<'a, K, V, S, A>
     where
-        K: Borrow<Q>,
-        Q: Eq,
+        Q: Equivalent<K>,
     {
         self.from_hash(hash, equivalent(k))
     }
@@ -3211,10 +4211,9 @@ impl<'a, K, V, S, A: Allocator + Clone> RawEntryBuilder<'a, K, V, S, A> {
     pub fn from_key<Q: ?Sized>(self, k: &Q) -> Option<(&'a K, &'a V)>
     where
         S: BuildHasher,
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
-        let hash = make_hash::<K, Q, S>(&self.map.hash_builder, k);
+        let hash = make_hash::<Q, S>(&self.map.hash_builder, k);
         self.from_key_hashed_nocheck(hash, k)
     }
 
@@ -3242,8 +4241,7 @@ impl<'a, K, V, S, A: Allocator + Clone> RawEntryBuilder<'a, K, V, S, A> {
     #[allow(clippy::wrong_self_convention)]
     pub fn from_key_hashed_nocheck<Q: ?Sized>(self, hash: u64, k: &Q) -> Option<(&'a K, &'a V)>
     where
-        K: Borrow<Q>,
-        Q: Eq,
+        Q: Equivalent<K>,
     {
         self.from_hash(hash, equivalent(k))
     }
@@ -3503,6 +4501,35 @@ impl<'a, K, V, S, A: Allocator + Clone> RawOccupiedEntryMut<'a, K, V, S, A> {
         unsafe { &self.elem.as_ref().0 }
     }
 
+    /// Returns the [`Bucket`] holding this entry in the underlying [`RawTable`].
+    /// This function is only available if the `raw` feature of the crate is enabled.
+    ///
+    /// The returned bucket is invalidated by any subsequent mutation of the
+    /// table (insertion, removal, growth, clearing, ...). Dereferencing it after
+    /// such a mutation is undefined behavior.
+    ///
+    /// [`RawTable`]: crate::raw::RawTable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{HashMap, RawEntryMut};
+    ///
+    /// let mut map: HashMap<&str, u32> = [("a", 100), ("b", 200)].into();
+    ///
+    /// let bucket = match map.raw_entry_mut().from_key(&"a") {
+    ///     RawEntryMut::Vacant(_) => panic!(),
+    ///     RawEntryMut::Occupied(o) => o.bucket(),
+    /// };
+    /// // The map has not been mutated since, so the bucket is still valid.
+    /// assert_eq!(unsafe { bucket.as_ref() }, &("a", 100));
+    /// ```
+    #[cfg(feature = "raw")]
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn bucket(&self) -> Bucket<(K, V)> {
+        self.elem.clone()
+    }
+
     /// Gets a mutable reference to the key in the entry.
     ///
     /// # Examples
@@ -3950,7 +4977,7 @@ impl<'a, K, V, S, A: Allocator + Clone> RawVacantEntryMut<'a, K, V, S, A> {
         let &mut (ref mut k, ref mut v) = self.table.insert_entry(
             hash,
             (key, value),
-            make_hasher::<K, _, V, S>(self.hash_builder),
+            make_hasher::<_, V, S>(self.hash_builder),
         );
         (k, v)
     }
@@ -4008,6 +5035,45 @@ impl<'a, K, V, S, A: Allocator + Clone> RawVacantEntryMut<'a, K, V, S, A> {
         (k, v)
     }
 
+    /// Sets the value of the entry with the VacantEntry's key, and returns the
+    /// [`Bucket`] it was inserted into in the underlying [`RawTable`].
+    /// This function is only available if the `raw` feature of the crate is enabled.
+    ///
+    /// The returned bucket is invalidated by any subsequent mutation of the
+    /// table (insertion, removal, growth, clearing, ...). Dereferencing it after
+    /// such a mutation is undefined behavior.
+    ///
+    /// [`RawTable`]: crate::raw::RawTable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{HashMap, RawEntryMut};
+    ///
+    /// let mut map: HashMap<&str, u32> = [("a", 100), ("b", 200)].into();
+    ///
+    /// let bucket = match map.raw_entry_mut().from_key(&"c") {
+    ///     RawEntryMut::Occupied(_) => panic!(),
+    ///     RawEntryMut::Vacant(v) => v.insert_and_return_bucket("c", 300),
+    /// };
+    /// assert_eq!(unsafe { bucket.as_ref() }, &("c", 300));
+    /// assert_eq!(map[&"c"], 300);
+    /// ```
+    #[cfg(feature = "raw")]
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_and_return_bucket(self, key: K, value: V) -> Bucket<(K, V)>
+    where
+        K: Hash,
+        S: BuildHasher,
+    {
+        let hash = make_insert_hash::<K, S>(self.hash_builder, &key);
+        self.table.insert(
+            hash,
+            (key, value),
+            make_hasher::<_, V, S>(self.hash_builder),
+        )
+    }
+
     #[cfg_attr(feature = "inline-more", inline)]
     fn insert_entry(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V, S, A>
     where
@@ -4018,7 +5084,7 @@ impl<'a, K, V, S, A: Allocator + Clone> RawVacantEntryMut<'a, K, V, S, A> {
         let elem = self.table.insert(
             hash,
             (key, value),
-            make_hasher::<K, _, V, S>(self.hash_builder),
+            make_hasher::<_, V, S>(self.hash_builder),
         );
         RawOccupiedEntryMut {
             elem,
@@ -4685,6 +5751,19 @@ impl<'a, K, V> Iterator for Iter<'a, K, V> {
         self.inner.size_hint()
     }
 }
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next_back() {
+            Some(x) => unsafe {
+                let r = x.as_ref();
+                Some((&r.0, &r.1))
+            },
+            None => None,
+        }
+    }
+}
 impl<K, V> ExactSizeIterator for Iter<'_, K, V> {
     #[cfg_attr(feature = "inline-more", inline)]
     fn len(&self) -> usize {
@@ -4713,6 +5792,19 @@ impl<'a, K, V> Iterator for IterMut<'a, K, V> {
         self.inner.size_hint()
     }
 }
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next_back() {
+            Some(x) => unsafe {
+                let r = x.as_mut();
+                Some((&r.0, &mut r.1))
+            },
+            None => None,
+        }
+    }
+}
 impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {
     #[cfg_attr(feature = "inline-more", inline)]
     fn len(&self) -> usize {
@@ -4773,6 +5865,16 @@ impl<'a, K, V> Iterator for Keys<'a, K, V> {
         self.inner.size_hint()
     }
 }
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<&'a K> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next_back() {
+            Some((k, _)) => Some(k),
+            None => None,
+        }
+    }
+}
 impl<K, V> ExactSizeIterator for Keys<'_, K, V> {
     #[cfg_attr(feature = "inline-more", inline)]
     fn len(&self) -> usize {
@@ -4797,6 +5899,16 @@ impl<'a, K, V> Iterator for Values<'a, K, V> {
         self.inner.size_hint()
     }
 }
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<&'a V> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next_back() {
+            Some((_, v)) => Some(v),
+            None => None,
+        }
+    }
+}
 impl<K, V> ExactSizeIterator for Values<'_, K, V> {
     #[cfg_attr(feature = "inline-more", inline)]
     fn len(&self) -> usize {
@@ -4821,6 +5933,16 @@ impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
         self.inner.size_hint()
     }
 }
+impl<'a, K, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<&'a mut V> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next_back() {
+            Some((_, v)) => Some(v),
+            None => None,
+        }
+    }
+}
 impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {
     #[cfg_attr(feature = "inline-more", inline)]
     fn len(&self) -> usize {
@@ -5049,6 +6171,47 @@ impl<'a, K, V, S, A: Allocator + Clone> Entry<'a, K, V, S, A> {
         }
     }
 
+    /// Modifies the value of an occupied entry with `modify`, or inserts the
+    /// result of `insert` into a vacant entry, and returns a mutable reference
+    /// to the value in the entry.
+    ///
+    /// This is the same as `and_modify(modify).or_insert_with(insert)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    ///
+    /// let text = "the quick brown fox jumps over the lazy dog the end";
+    /// let mut counts: HashMap<&str, usize> = HashMap::new();
+    /// for word in text.split_whitespace() {
+    ///     counts
+    ///         .entry(word)
+    ///         .and_modify_or_insert_with(|count| *count += 1, || 1);
+    /// }
+    ///
+    /// assert_eq!(counts["the"], 3);
+    /// assert_eq!(counts["fox"], 1);
+    /// assert_eq!(counts.len(), 9);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn and_modify_or_insert_with<M, I>(self, modify: M, insert: I) -> &'a mut V
+    where
+        K: Hash,
+        S: BuildHasher,
+        M: FnOnce(&mut V),
+        I: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => {
+                let value = entry.into_mut();
+                modify(value);
+                value
+            }
+            Entry::Vacant(entry) => entry.insert(insert()),
+        }
+    }
+
     /// Provides shared access to the key and owned access to the value of
     /// an occupied entry and allows to replace or remove it based on the
     /// value of the returned option.
@@ -5134,6 +6297,14 @@ impl<'a, K, V: Default, S, A: Allocator + Clone> Entry<'a, K, V, S, A> {
     ///
     /// // existing key
     /// assert_eq!(map.entry("horseland").or_default(), &mut Some(3));
+    ///
+    /// // counting occurrences
+    /// let mut counts: HashMap<char, usize> = HashMap::new();
+    /// for c in "poneyland".chars() {
+    ///     *counts.entry(c).or_default() += 1;
+    /// }
+    /// assert_eq!(counts[&'n'], 2);
+    /// assert_eq!(counts[&'p'], 1);
     /// ```
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn or_default(self) -> &'a mut V
@@ -5283,6 +6454,35 @@ impl<'a, K, V, S, A: Allocator + Clone> OccupiedEntry<'a, K, V, S, A> {
         unsafe { &mut self.elem.as_mut().1 }
     }
 
+    /// Converts the `OccupiedEntry` into a shared reference to the key and a
+    /// mutable reference to the value in the entry, both with a lifetime bound
+    /// to the map itself.
+    ///
+    /// This is like [`into_mut`](Self::into_mut), but also gives access to the
+    /// key stored in the map without looking it up again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{Entry, HashMap};
+    ///
+    /// let mut map: HashMap<String, u32> = HashMap::new();
+    /// map.insert("poneyland".to_string(), 12);
+    ///
+    /// let (key, value) = match map.entry("poneyland".to_string()) {
+    ///     Entry::Occupied(entry) => entry.into_key_value_mut(),
+    ///     Entry::Vacant(_) => panic!(),
+    /// };
+    /// *value += key.len() as u32;
+    ///
+    /// assert_eq!(map["poneyland"], 21);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn into_key_value_mut(self) -> (&'a K, &'a mut V) {
+        let &mut (ref key, ref mut value) = unsafe { self.elem.as_mut() };
+        (key, value)
+    }
+
     /// Sets the value of the entry, and returns the entry's old value.
     ///
     /// # Examples
@@ -5305,6 +6505,47 @@ impl<'a, K, V, S, A: Allocator + Clone> OccupiedEntry<'a, K, V, S, A> {
         mem::replace(self.get_mut(), value)
     }
 
+    /// Sets the key of the entry, and returns the entry's old key. The value
+    /// is left untouched.
+    ///
+    /// The new key must be equal to the old one and hash to the same value.
+    /// Equality is checked with a debug assertion.
+    ///
+    /// Unlike [`replace_key`](Self::replace_key), which consumes the entry and
+    /// puts back the key the entry was created with, this takes the new key
+    /// as an argument and keeps the entry usable. It is named after
+    /// [`RawOccupiedEntryMut::insert_key`], which does the same.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::hash_map::{Entry, HashMap};
+    /// use std::rc::Rc;
+    ///
+    /// let key_one = Rc::new("a");
+    /// let key_two = Rc::new("a");
+    ///
+    /// let mut map: HashMap<Rc<&str>, u32> = HashMap::new();
+    /// map.insert(key_one.clone(), 10);
+    ///
+    /// if let Entry::Occupied(mut o) = map.entry(key_one.clone()) {
+    ///     let old_key = o.insert_key(key_two.clone());
+    ///     assert!(Rc::ptr_eq(&old_key, &key_one));
+    /// }
+    ///
+    /// assert_eq!(map[&key_two], 10);
+    /// assert!(Rc::strong_count(&key_one) == 1 && Rc::strong_count(&key_two) == 2);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_key(&mut self, key: K) -> K
+    where
+        K: Eq,
+    {
+        let old_key = unsafe { &mut self.elem.as_mut().0 };
+        debug_assert!(key == *old_key);
+        mem::replace(old_key, key)
+    }
+
     /// Takes the value out of the entry, and returns it.
     /// Keeps the allocated memory for reuse.
     ///
@@ -5563,25 +6804,74 @@ impl<'a, K, V, S, A: Allocator + Clone> VacantEntry<'a, K, V, S, A> {
         K: Hash,
         S: BuildHasher,
     {
+        #[cfg(debug_assertions)]
+        debug_assert_hash_consistent(&self.table.hash_builder, &self.key, self.hash);
         let table = &mut self.table.table;
         let entry = table.insert_entry(
             self.hash,
             (self.key, value),
-            make_hasher::<K, _, V, S>(&self.table.hash_builder),
+            make_hasher::<_, V, S>(&self.table.hash_builder),
         );
         &mut entry.1
     }
 
+    /// Sets the value of the entry with the VacantEntry's key, and returns a
+    /// mutable reference to it along with whether the map had to reallocate
+    /// its storage to make room for the new entry.
+    ///
+    /// This can be used to keep track of the memory used by the map without
+    /// checking its capacity after every insertion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashMap;
+    /// use hashbrown::hash_map::Entry;
+    ///
+    /// let mut map: HashMap<u32, u32> = HashMap::with_capacity(3);
+    /// assert_eq!(map.capacity(), 3);
+    ///
+    /// for i in 0..4 {
+    ///     if let Entry::Vacant(v) = map.entry(i) {
+    ///         let (_, reallocated) = v.insert_reporting(i);
+    ///         // Only the fourth entry doesn't fit into the initial allocation.
+    ///         assert_eq!(reallocated, i == 3);
+    ///     }
+    /// }
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_reporting(self, value: V) -> (&'a mut V, bool)
+    where
+        K: Hash,
+        S: BuildHasher,
+    {
+        #[cfg(debug_assertions)]
+        debug_assert_hash_consistent(&self.table.hash_builder, &self.key, self.hash);
+        let table = &mut self.table.table;
+        // The table only ever reallocates to grow when inserting, so a change
+        // in the number of buckets means that it had to reallocate.
+        let buckets = table.buckets();
+        let elem = table.insert(
+            self.hash,
+            (self.key, value),
+            make_hasher::<_, V, S>(&self.table.hash_builder),
+        );
+        let reallocated = table.buckets() != buckets;
+        (unsafe { &mut elem.as_mut().1 }, reallocated)
+    }
+
     #[cfg_attr(feature = "inline-more", inline)]
     pub(crate) fn insert_entry(self, value: V) -> OccupiedEntry<'a, K, V, S, A>
     where
         K: Hash,
         S: BuildHasher,
     {
+        #[cfg(debug_assertions)]
+        debug_assert_hash_consistent(&self.table.hash_builder, &self.key, self.hash);
         let elem = self.table.table.insert(
             self.hash,
             (self.key, value),
-            make_hasher::<K, _, V, S>(&self.table.hash_builder),
+            make_hasher::<_, V, S>(&self.table.hash_builder),
         );
         OccupiedEntry {
             hash: self.hash,
@@ -5862,6 +7152,14 @@ impl<'a, 'b, K, Q: ?Sized, V: Default, S, A: Allocator + Clone> EntryRef<'a, 'b,
     ///
     /// // existing key
     /// assert_eq!(map.entry_ref("horseland").or_default(), &mut Some(3));
+    ///
+    /// // counting occurrences
+    /// let mut counts: HashMap<String, usize> = HashMap::new();
+    /// for word in "a rose is a rose".split(' ') {
+    ///     *counts.entry_ref(word).or_default() += 1;
+    /// }
+    /// assert_eq!(counts["rose"], 2);
+    /// assert_eq!(counts["is"], 1);
     /// ```
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn or_default(self) -> &'a mut V
@@ -6305,7 +7603,7 @@ impl<'a, 'b, K, Q: ?Sized, V, S, A: Allocator + Clone> VacantEntryRef<'a, 'b, K,
         let entry = table.insert_entry(
             self.hash,
             (self.key.into_owned(), value),
-            make_hasher::<K, _, V, S>(&self.table.hash_builder),
+            make_hasher::<_, V, S>(&self.table.hash_builder),
         );
         &mut entry.1
     }
@@ -6319,7 +7617,7 @@ impl<'a, 'b, K, Q: ?Sized, V, S, A: Allocator + Clone> VacantEntryRef<'a, 'b, K,
         let elem = self.table.table.insert(
             self.hash,
             (self.key.into_owned(), value),
-            make_hasher::<K, _, V, S>(&self.table.hash_builder),
+            make_hasher::<_, V, S>(&self.table.hash_builder),
         );
         OccupiedEntryRef {
             hash: self.hash,
@@ -6599,6 +7897,27 @@ fn assert_covariance() {
     }
 }
 
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn is_send<T: Send>(_: T) {}
+    fn is_sync<T: Sync>(_: &T) {}
+
+    // The allocator is only required to be `Clone`, so these also hold for
+    // allocators which are not `Copy`.
+    fn drain<K: Send + Sync, V: Send + Sync, A: Allocator + Clone + Send + Sync>(
+        d: Drain<'_, K, V, A>,
+    ) {
+        is_sync(&d);
+        is_send(d);
+    }
+    fn into_iter<K: Send + Sync, V: Send + Sync, A: Allocator + Clone + Send + Sync>(
+        i: IntoIter<K, V, A>,
+    ) {
+        is_sync(&i);
+        is_send(i);
+    }
+}
+
 #[cfg(test)]
 mod test_map {
     use super::DefaultHashBuilder;
@@ -6695,6 +8014,80 @@ mod test_map {
         assert_eq!(m2.len(), 2);
     }
 
+    #[test]
+    fn test_clone_from_with_hasher() {
+        let mut source = HashMap::with_hasher(DefaultHashBuilder::with_seeds(1, 2, 3, 4));
+        for i in 0..100 {
+            source.insert(i, i * 10);
+        }
+
+        let mut m = HashMap::with_capacity_and_hasher(200, DefaultHashBuilder::default());
+        m.insert(1000, 0);
+        let capacity = m.capacity();
+        m.clone_from_with_hasher(&source, DefaultHashBuilder::with_seeds(5, 6, 7, 8));
+
+        assert_eq!(m.len(), 100);
+        assert_eq!(m.capacity(), capacity);
+        assert!(!m.contains_key(&1000));
+        for i in 0..100 {
+            assert_eq!(m[&i], i * 10);
+        }
+
+        // The entries were rehashed with the new seed rather than copied
+        // bucket by bucket, so they end up in a different order.
+        let source_order: Vec<_> = source.keys().copied().collect();
+        let order: Vec<_> = m.keys().copied().collect();
+        assert_ne!(order, source_order);
+    }
+
+    #[test]
+    fn test_clone_from_panic_in_clone() {
+        use core::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        // Every live value holds a reference to the shared budget, which
+        // counts how many more clones are allowed to succeed.
+        struct Budgeted(Rc<Cell<usize>>);
+        impl Clone for Budgeted {
+            fn clone(&self) -> Self {
+                let left = self.0.get();
+                if left == 0 {
+                    panic!("panic in clone");
+                }
+                self.0.set(left - 1);
+                Budgeted(self.0.clone())
+            }
+        }
+
+        let budget = Rc::new(Cell::new(0));
+        let mut source = HashMap::new();
+        let mut target = HashMap::new();
+        for i in 0..8 {
+            source.insert(i, Budgeted(budget.clone()));
+            target.insert(i + 100, Budgeted(budget.clone()));
+        }
+        assert_eq!(source.raw_capacity(), target.raw_capacity());
+        assert_eq!(Rc::strong_count(&budget), 17);
+
+        budget.set(4);
+        let result = catch_unwind(AssertUnwindSafe(|| target.clone_from(&source)));
+        assert!(result.is_err());
+
+        // The old elements and the partially cloned ones were all dropped, and
+        // the target was left empty.
+        assert_eq!(Rc::strong_count(&budget), 9);
+        assert!(target.is_empty());
+        assert_eq!(target.iter().count(), 0);
+
+        // The target is still usable afterwards.
+        budget.set(8);
+        target.clone_from(&source);
+        assert_eq!(target.len(), 8);
+        assert!((0..8).all(|i| target.contains_key(&i)));
+        assert_eq!(Rc::strong_count(&budget), 17);
+    }
+
     thread_local! { static DROP_VECTOR: RefCell<Vec<i32>> = RefCell::new(Vec::new()) }
 
     #[derive(Hash, PartialEq, Eq)]
@@ -6848,21 +8241,82 @@ mod test_map {
     }
 
     #[test]
-    fn test_empty_remove() {
-        let mut m: HashMap<i32, bool> = HashMap::new();
-        assert_eq!(m.remove(&0), None);
-    }
+    fn test_into_keys_values_drops() {
+        fn droppable_map() -> HashMap<Droppable, Droppable> {
+            DROP_VECTOR.with(|v| {
+                *v.borrow_mut() = vec![0; 200];
+            });
 
-    #[test]
-    fn test_empty_entry() {
-        let mut m: HashMap<i32, bool> = HashMap::new();
-        match m.entry(0) {
-            Occupied(_) => panic!(),
-            Vacant(_) => {}
+            let mut hm = HashMap::new();
+            for i in 0..100 {
+                hm.insert(Droppable::new(i), Droppable::new(i + 100));
+            }
+            hm
         }
-        assert!(*m.entry(0).or_insert(true));
-        assert_eq!(m.len(), 1);
-    }
+
+        {
+            let mut keys = droppable_map().into_keys();
+            let half: Vec<_> = keys.by_ref().take(50).collect();
+
+            DROP_VECTOR.with(|v| {
+                let nk = (0..100).filter(|&i| v.borrow()[i] == 1).count();
+                let nv = (0..100).filter(|&i| v.borrow()[i + 100] == 1).count();
+
+                // Every yielded key has had its value dropped.
+                assert_eq!(nk, 100);
+                assert_eq!(nv, 50);
+                for key in &half {
+                    assert_eq!(v.borrow()[key.k + 100], 0);
+                }
+            });
+        }
+
+        DROP_VECTOR.with(|v| {
+            for i in 0..200 {
+                assert_eq!(v.borrow()[i], 0);
+            }
+        });
+
+        {
+            let mut values = droppable_map().into_values();
+            let half: Vec<_> = values.by_ref().take(50).collect();
+
+            DROP_VECTOR.with(|v| {
+                let nk = (0..100).filter(|&i| v.borrow()[i] == 1).count();
+                let nv = (0..100).filter(|&i| v.borrow()[i + 100] == 1).count();
+
+                // Every yielded value has had its key dropped.
+                assert_eq!(nk, 50);
+                assert_eq!(nv, 100);
+                for value in &half {
+                    assert_eq!(v.borrow()[value.k - 100], 0);
+                }
+            });
+        }
+
+        DROP_VECTOR.with(|v| {
+            for i in 0..200 {
+                assert_eq!(v.borrow()[i], 0);
+            }
+        });
+    }
+
+    #[test]
+    fn test_empty_remove() {
+        let mut m: HashMap<i32, bool> = HashMap::new();
+        assert_eq!(m.remove(&0), None);
+    }
+
+    #[test]
+    fn test_empty_entry() {
+        let mut m: HashMap<i32, bool> = HashMap::new();
+        match m.entry(0) {
+            Occupied(_) => panic!(),
+            Vacant(_) => {}
+        }
+        assert!(*m.entry(0).or_insert(true));
+        assert_eq!(m.len(), 1);
+    }
 
     #[test]
     fn test_empty_entry_ref() {
@@ -6967,6 +8421,92 @@ mod test_map {
         assert_eq!(m.get(&5), Some(&new));
     }
 
+    #[test]
+    fn test_get_key_value_mut() {
+        let mut m: HashMap<std::string::String, usize> = HashMap::new();
+        m.insert("one".to_owned(), 0);
+        m.insert("three".to_owned(), 0);
+
+        // The key can be read while the value is being mutated.
+        for key in ["one", "three"] {
+            let (k, v) = m.get_key_value_mut(key).unwrap();
+            *v = k.len();
+        }
+        assert_eq!(m["one"], 3);
+        assert_eq!(m["three"], 5);
+        assert_eq!(m.get_key_value_mut("two"), None);
+        assert_eq!(m.len(), 2);
+    }
+
+    #[test]
+    fn test_equivalent_lookup() {
+        use crate::Equivalent;
+
+        // A borrowed view of a `(String, u32)` key, which the key itself
+        // cannot be borrowed as. Its derived `Hash` matches that of the tuple.
+        #[derive(Hash)]
+        struct Pair<'a>(&'a str, u32);
+
+        impl Equivalent<(std::string::String, u32)> for Pair<'_> {
+            fn equivalent(&self, key: &(std::string::String, u32)) -> bool {
+                self.0 == key.0 && self.1 == key.1
+            }
+        }
+
+        let mut m: HashMap<(std::string::String, u32), i32> = HashMap::new();
+        m.insert(("a".to_owned(), 1), 10);
+        m.insert(("b".to_owned(), 2), 20);
+
+        assert!(m.contains_key(&Pair("a", 1)));
+        assert!(!m.contains_key(&Pair("a", 2)));
+        assert_eq!(m.get(&Pair("b", 2)), Some(&20));
+        *m.get_mut(&Pair("a", 1)).unwrap() += 1;
+        assert_eq!(m[&Pair("a", 1)], 11);
+        assert_eq!(m.remove(&Pair("b", 2)), Some(20));
+        assert_eq!(m.remove(&Pair("b", 2)), None);
+        assert_eq!(m.len(), 1);
+    }
+
+    #[test]
+    fn test_with_hash() {
+        let mut m: HashMap<u32, u32> = HashMap::new();
+        let hashes: Vec<u64> = (0..100)
+            .map(|k| super::make_insert_hash::<u32, _>(m.hasher(), &k))
+            .collect();
+
+        for (k, &hash) in (0..100).zip(&hashes) {
+            *m.entry_with_hash(hash, k).or_insert(0) += k;
+        }
+        for (k, &hash) in (0..100).zip(&hashes) {
+            // Occupied entries behave exactly like those from `entry`.
+            match m.entry_with_hash(hash, k) {
+                Occupied(mut e) => *e.get_mut() += 1,
+                Vacant(_) => panic!("missing key {}", k),
+            }
+        }
+        assert_eq!(m.len(), 100);
+        for (k, &hash) in (0..100).zip(&hashes) {
+            assert_eq!(m.get_with_hash(hash, &k), Some(&(k + 1)));
+            assert_eq!(m.get(&k), Some(&(k + 1)));
+        }
+        for (k, &hash) in (0..100).zip(&hashes).step_by(2) {
+            assert_eq!(m.remove_with_hash(hash, &k), Some(k + 1));
+            assert_eq!(m.remove_with_hash(hash, &k), None);
+            assert_eq!(m.get_with_hash(hash, &k), None);
+        }
+        assert_eq!(m.len(), 50);
+        assert!((0..100).all(|k| m.contains_key(&k) == (k % 2 == 1)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_entry_with_wrong_hash() {
+        let mut m: HashMap<u32, u32> = HashMap::new();
+        let hash = super::make_insert_hash::<u32, _>(m.hasher(), &1);
+        m.entry_with_hash(hash.wrapping_add(1), 1);
+    }
+
     #[test]
     fn test_insert_overwrite() {
         let mut m = HashMap::new();
@@ -7016,6 +8556,38 @@ mod test_map {
         assert_eq!(None, map.get(&30));
     }
 
+    #[test]
+    fn test_insert_unique_unchecked_grows() {
+        let mut map = HashMap::new();
+        for i in 0..1000 {
+            map.insert_unique_unchecked(i, i * 10);
+            assert!(map.capacity() >= map.len());
+        }
+        assert_eq!(map.len(), 1000);
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut map = HashMap::new();
+        assert_eq!(map.try_insert(1, 10).unwrap(), &mut 10);
+
+        match map.try_insert(1, 20) {
+            Err(err) => {
+                assert_eq!(err.entry.key(), &1);
+                assert_eq!(err.entry.get(), &10);
+                assert_eq!(err.value, 20);
+            }
+            Ok(_) => panic!("key should be occupied"),
+        }
+
+        // The rejected value must not have replaced the original one.
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&1], 10);
+    }
+
     #[test]
     fn test_is_empty() {
         let mut m = HashMap::with_capacity(4);
@@ -7058,6 +8630,136 @@ mod test_map {
         assert_eq!(observed, 0xFFFF_FFFF);
     }
 
+    #[test]
+    fn test_iterators_are_fused() {
+        use super::{
+            Drain, ExtractIf, IntoIter, IntoKeys, IntoValues, Iter, IterMut, Keys, Values,
+            ValuesMut,
+        };
+        use crate::raw::Allocator;
+        use core::iter::FusedIterator;
+
+        fn assert_fused<I: FusedIterator>() {}
+
+        fn check<A: Allocator + Clone>() {
+            assert_fused::<Iter<'_, u32, u32>>();
+            assert_fused::<IterMut<'_, u32, u32>>();
+            assert_fused::<Keys<'_, u32, u32>>();
+            assert_fused::<Values<'_, u32, u32>>();
+            assert_fused::<ValuesMut<'_, u32, u32>>();
+            assert_fused::<IntoIter<u32, u32, A>>();
+            assert_fused::<IntoKeys<u32, u32, A>>();
+            assert_fused::<IntoValues<u32, u32, A>>();
+            assert_fused::<Drain<'_, u32, u32, A>>();
+            assert_fused::<ExtractIf<'_, u32, u32, fn(&u32, &mut u32) -> bool, A>>();
+        }
+
+        check::<crate::raw::Global>();
+    }
+
+    #[test]
+    fn test_into_iter_clone() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let m: HashMap<i32, Rc<()>> = (0..100).map(|i| (i, counter.clone())).collect();
+
+        let mut iter = m.into_iter();
+        for _ in 0..50 {
+            iter.next().unwrap();
+        }
+        assert_eq!(Rc::strong_count(&counter), 51);
+
+        let cloned = iter.clone();
+        assert_eq!(cloned.len(), 50);
+        assert_eq!(Rc::strong_count(&counter), 101);
+
+        let mut rest: Vec<_> = iter.map(|(k, _)| k).collect();
+        let mut cloned_rest: Vec<_> = cloned.map(|(k, _)| k).collect();
+        rest.sort_unstable();
+        cloned_rest.sort_unstable();
+        assert_eq!(rest.len(), 50);
+        assert_eq!(rest, cloned_rest);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // Dropping a clone before it is exhausted drops its elements too.
+        let m: HashMap<i32, Rc<()>> = (0..10).map(|i| (i, counter.clone())).collect();
+        let mut iter = m.into_iter();
+        iter.next();
+        drop(iter.clone());
+        assert_eq!(Rc::strong_count(&counter), 10);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        let empty: HashMap<i32, i32> = HashMap::new();
+        assert_eq!(empty.into_iter().clone().next(), None);
+    }
+
+    #[test]
+    fn test_iterate_rev() {
+        let mut m = HashMap::with_capacity(4);
+        for i in 0..100 {
+            assert!(m.insert(i, i * 2).is_none());
+        }
+
+        // Reverse iteration yields exactly the forward order, backwards.
+        let forward: Vec<_> = m.iter().map(|(&k, &v)| (k, v)).collect();
+        let mut reverse: Vec<_> = m.iter().rev().map(|(&k, &v)| (k, v)).collect();
+        reverse.reverse();
+        assert_eq!(reverse, forward);
+
+        let mut keys: Vec<_> = m.keys().rev().copied().collect();
+        keys.reverse();
+        assert_eq!(keys, forward.iter().map(|&(k, _)| k).collect::<Vec<_>>());
+
+        let mut values: Vec<_> = m.values().rev().copied().collect();
+        values.reverse();
+        assert_eq!(values, forward.iter().map(|&(_, v)| v).collect::<Vec<_>>());
+
+        for (i, (_, v)) in m.iter_mut().rev().enumerate() {
+            *v = i;
+        }
+        for (i, v) in m.values_mut().rev().enumerate() {
+            assert_eq!(*v, i);
+            *v = 0;
+        }
+        assert!(m.values().all(|&v| v == 0));
+
+        // Consuming from both ends yields every entry exactly once.
+        let mut iter = m.keys();
+        let mut observed = Vec::new();
+        while let Some(&k) = iter.next() {
+            observed.push(k);
+            if let Some(&k) = iter.next_back() {
+                observed.push(k);
+            }
+        }
+        observed.sort_unstable();
+        assert_eq!(observed, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_iterate_sorted() {
+        use core::hash::BuildHasherDefault;
+        use std::collections::hash_map::DefaultHasher;
+
+        type Map = HashMap<u32, u32, BuildHasherDefault<DefaultHasher>>;
+
+        // Same contents, different insertion order and capacity.
+        let a: Map = (0..100).map(|i| (i, i * 2)).collect();
+        let mut b = Map::with_capacity_and_hasher(500, Default::default());
+        b.extend((0..100).rev().map(|i| (i, i * 2)));
+
+        let sorted: Vec<_> = a.iter_sorted().collect();
+        assert_eq!(sorted, b.iter_sorted().collect::<Vec<_>>());
+        assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(sorted.len(), 100);
+
+        let by_hash: Vec<_> = a.iter_sorted_by_hash().collect();
+        assert_eq!(by_hash, b.iter_sorted_by_hash().collect::<Vec<_>>());
+        assert_eq!(by_hash.len(), 100);
+    }
+
     #[test]
     fn test_keys() {
         let vec = vec![(1, 'a'), (2, 'b'), (3, 'c')];
@@ -7147,6 +8849,36 @@ mod test_map {
         assert_eq!(m1, m2);
     }
 
+    #[test]
+    fn test_eq_different_hashers() {
+        use std::collections::hash_map::RandomState;
+
+        let mut m1 = HashMap::with_hasher(RandomState::new());
+        let mut m2 = HashMap::with_hasher(DefaultHashBuilder::with_seeds(1, 2, 3, 4));
+        for i in 0..100 {
+            m1.insert(i, i * 2);
+            m2.insert(i, i * 2);
+        }
+        assert!(m1.eq_contents(&m2));
+        assert!(m2.eq_contents(&m1));
+
+        m2.insert(100, 200);
+        assert!(!m1.eq_contents(&m2));
+        m1.insert(100, 0);
+        assert!(!m1.eq_contents(&m2));
+        assert!(!m2.eq_contents(&m1));
+    }
+
+    #[test]
+    fn test_eq_infers_hasher() {
+        // `==` is only implemented between maps with the same hasher type, so
+        // the type of the right-hand side can be inferred.
+        let m: HashMap<i32, i32> = [(1, 2)].into_iter().collect();
+        assert_ne!(m, Default::default());
+        assert!(m != HashMap::default());
+        assert!(m == [(1, 2)].into_iter().collect());
+    }
+
     #[test]
     fn test_show() {
         let mut map = HashMap::new();
@@ -7272,6 +9004,92 @@ mod test_map {
         assert_eq!(m.remove(&0), Some(0));
     }
 
+    #[test]
+    fn test_clear_and_shrink_to() {
+        use std::rc::Rc;
+
+        let counter = Rc::new(());
+        let mut m = HashMap::new();
+        for i in 0..1000 {
+            m.insert(i, counter.clone());
+        }
+        let grown_cap = m.capacity();
+        assert!(grown_cap >= 1000);
+
+        m.clear_and_shrink_to(16);
+        assert!(m.is_empty());
+        assert_eq!(Rc::strong_count(&counter), 1);
+        let cap = m.capacity();
+        assert!(cap >= 16);
+        assert!(cap < grown_cap);
+        assert_eq!(cap, HashMap::<i32, Rc<()>>::with_capacity(16).capacity());
+
+        // The shrunk map is usable and does not need to grow for 16 elements.
+        for i in 0..16 {
+            m.insert(i, counter.clone());
+        }
+        assert_eq!(m.capacity(), cap);
+        assert_eq!(m.len(), 16);
+
+        // A larger limit than the current capacity just clears the map.
+        m.clear_and_shrink_to(1000);
+        assert!(m.is_empty());
+        assert_eq!(m.capacity(), cap);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // Zero frees the allocation.
+        m.insert(0, counter.clone());
+        m.clear_and_shrink_to(0);
+        assert!(m.is_empty());
+        assert_eq!(m.capacity(), 0);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        fn check(m: &HashMap<i32, i32>) {
+            assert_eq!(m.len(), 10);
+            for i in 0..10 {
+                assert_eq!(m.get(&i), Some(&(i * 10)));
+            }
+        }
+
+        let mut m = HashMap::with_capacity(100);
+        for i in 0..10 {
+            m.insert(i, i * 10);
+        }
+        let initial_cap = m.capacity();
+        assert!(initial_cap >= 100);
+
+        // A target larger than the current capacity leaves the map untouched.
+        m.shrink_to(1000);
+        assert_eq!(m.capacity(), initial_cap);
+        check(&m);
+
+        // A target between `len` and the current capacity keeps that headroom.
+        m.shrink_to(50);
+        let cap = m.capacity();
+        assert!(cap >= 50);
+        assert!(cap < initial_cap);
+        check(&m);
+
+        // A target smaller than `len` only shrinks down to `len`.
+        m.shrink_to(2);
+        assert!(m.capacity() >= 10);
+        assert!(m.capacity() < cap);
+        check(&m);
+
+        // Zero behaves like `shrink_to_fit`.
+        let cap = m.capacity();
+        m.shrink_to(0);
+        assert_eq!(m.capacity(), cap);
+        check(&m);
+
+        m.clear();
+        m.shrink_to(0);
+        assert_eq!(m.capacity(), 0);
+    }
+
     #[test]
     fn test_from_iter() {
         let xs = [(1, 1), (2, 2), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)];
@@ -7285,6 +9103,24 @@ mod test_map {
         assert_eq!(map.iter().len(), xs.len() - 1);
     }
 
+    #[test]
+    fn test_from_array() {
+        let xs = [(1, 'a'), (2, 'b'), (1, 'c'), (3, 'd'), (2, 'e')];
+
+        let map = HashMap::from(xs);
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[&1], 'c');
+        assert_eq!(map[&2], 'e');
+        assert_eq!(map[&3], 'd');
+        assert!(map.capacity() >= xs.len());
+
+        let collected: HashMap<_, _> = xs.into_iter().collect();
+        assert_eq!(map, collected);
+
+        let empty: HashMap<i32, char> = HashMap::from([]);
+        assert!(empty.is_empty());
+    }
+
     #[test]
     fn test_size_hint() {
         let xs = [(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)];
@@ -7468,6 +9304,74 @@ mod test_map {
         assert_eq!(map.len(), 6);
     }
 
+    #[test]
+    fn test_entry_ref_converts_key_only_when_vacant() {
+        use core::borrow::Borrow;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use std::borrow::Cow;
+
+        static CONVERSIONS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(PartialEq, Eq, Hash)]
+        struct Key(std::string::String);
+
+        impl Borrow<str> for Key {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<&str> for Key {
+            fn from(s: &str) -> Self {
+                CONVERSIONS.fetch_add(1, Ordering::SeqCst);
+                Key(s.to_owned())
+            }
+        }
+
+        let mut map: HashMap<Key, usize> = HashMap::new();
+        for s in ["poneyland", "horseyland", "poneyland", "poneyland"] {
+            *map.entry_ref(s).or_insert(0) += 1;
+        }
+
+        assert_eq!(map["poneyland"], 3);
+        assert_eq!(map["horseyland"], 1);
+        // Only the two vacant lookups converted their key.
+        assert_eq!(CONVERSIONS.load(Ordering::SeqCst), 2);
+
+        fn conversions<R>(f: impl FnOnce() -> R) -> usize {
+            let before = CONVERSIONS.load(Ordering::SeqCst);
+            drop(f());
+            CONVERSIONS.load(Ordering::SeqCst) - before
+        }
+
+        // None of the ways to use an occupied entry convert the key.
+        let cow: Cow<'_, str> = Cow::Owned("poneyland".to_owned());
+        assert_eq!(conversions(|| *map.entry_ref(cow.as_ref()).or_insert(0)), 0);
+        assert_eq!(
+            conversions(|| *map.entry_ref(cow.as_ref()).or_insert_with(|| 0)),
+            0
+        );
+        assert_eq!(conversions(|| *map.entry_ref(cow.as_ref()).or_default()), 0);
+        assert_eq!(
+            conversions(|| {
+                map.entry_ref(cow.as_ref())
+                    .and_modify(|v| *v += 10)
+                    .or_insert(0);
+            }),
+            0
+        );
+        assert_eq!(map["poneyland"], 13);
+        assert_eq!(
+            conversions(|| *map.entry_ref(cow.as_ref()).insert(5).get()),
+            0
+        );
+        assert_eq!(map["poneyland"], 5);
+
+        let cow: Cow<'_, str> = Cow::Borrowed("donkeyland");
+        assert_eq!(conversions(|| *map.entry_ref(cow.as_ref()).or_insert(6)), 1);
+        assert_eq!(map.len(), 3);
+    }
+
     #[test]
     fn test_entry_take_doesnt_corrupt() {
         #![allow(deprecated)] //rand
@@ -7589,6 +9493,137 @@ mod test_map {
         }
     }
 
+    #[test]
+    fn test_merge_with() {
+        // Disjoint keys are all moved in, the resolver is never called.
+        let mut a: HashMap<i32, i32> = (0..50).map(|i| (i, i)).collect();
+        let b: HashMap<i32, i32> = (50..100).map(|i| (i, i)).collect();
+        a.merge_with(b, |_, _, _| panic!("no overlapping keys"));
+        assert_eq!(a.len(), 100);
+        assert!((0..100).all(|i| a[&i] == i));
+
+        // Fully overlapping keys go through the resolver, which sums values.
+        let b: HashMap<i32, i32> = (0..100).map(|i| (i, i * 10)).collect();
+        let mut calls = 0;
+        a.merge_with(b, |&k, v, other| {
+            assert_eq!(other, k * 10);
+            calls += 1;
+            *v += other;
+        });
+        assert_eq!(calls, 100);
+        assert_eq!(a.len(), 100);
+        assert!((0..100).all(|i| a[&i] == i * 11));
+
+        // Partial overlap, and values are moved rather than cloned.
+        let mut a: HashMap<&str, Vec<i32>> = [("x", vec![1]), ("y", vec![2])].into();
+        let b: HashMap<&str, Vec<i32>> = [("y", vec![3]), ("z", vec![4])].into();
+        a.merge_with(b, |_, v, mut other| v.append(&mut other));
+        assert_eq!(a.len(), 3);
+        assert_eq!(a["x"], [1]);
+        assert_eq!(a["y"], [2, 3]);
+        assert_eq!(a["z"], [4]);
+
+        // `merge` overwrites overlapping values.
+        a.merge([("x", vec![5]), ("w", vec![6])].into());
+        assert_eq!(a.len(), 4);
+        assert_eq!(a["x"], [5]);
+        assert_eq!(a["w"], [6]);
+    }
+
+    #[cfg(debug_assertions)]
+    mod inconsistent_keys {
+        use super::HashMap;
+        use core::cell::Cell;
+        use core::hash::{BuildHasherDefault, Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+
+        type Map<K> = HashMap<K, u32, BuildHasherDefault<DefaultHasher>>;
+
+        // Hashes differently every time it is hashed.
+        #[derive(PartialEq, Eq)]
+        struct Unstable(Cell<u64>);
+
+        impl Hash for Unstable {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.set(self.0.get() + 1);
+                self.0.get().hash(state);
+            }
+        }
+
+        // Never equal to anything, not even itself.
+        struct NeverEq(u32);
+
+        impl PartialEq for NeverEq {
+            fn eq(&self, _: &Self) -> bool {
+                false
+            }
+        }
+
+        impl Eq for NeverEq {}
+
+        impl Hash for NeverEq {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        // Hashes its contents, which can be changed while it is in the map.
+        #[derive(PartialEq, Eq)]
+        struct Mutable(Cell<u32>);
+
+        impl Hash for Mutable {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.get().hash(state);
+            }
+        }
+
+        #[test]
+        #[should_panic = "the hash of a key changed"]
+        fn insert_unstable_hash() {
+            let mut m = Map::default();
+            m.insert(Unstable(Cell::new(0)), 0);
+        }
+
+        #[test]
+        #[should_panic = "the hash of a key changed"]
+        fn entry_unstable_hash() {
+            let mut m = Map::default();
+            m.entry(Unstable(Cell::new(0))).or_insert(0);
+        }
+
+        #[test]
+        #[should_panic = "cannot be found again"]
+        fn insert_never_eq() {
+            let mut m = Map::default();
+            m.insert(NeverEq(0), 0);
+        }
+
+        #[test]
+        #[should_panic = "changed while it was stored in the table"]
+        fn key_mutated_while_stored() {
+            let mut m = Map::default();
+            for i in 0..3 {
+                m.insert(Mutable(Cell::new(i)), i);
+            }
+            let key = m.keys().find(|k| k.0.get() == 1).unwrap();
+            key.0.set(1000);
+            // Growing the table rehashes every key.
+            for i in 3..100 {
+                m.insert(Mutable(Cell::new(i)), i);
+            }
+        }
+
+        #[test]
+        fn consistent_keys() {
+            let mut m = Map::default();
+            for i in 0..100 {
+                m.insert(Mutable(Cell::new(i)), i);
+                *m.entry(Mutable(Cell::new(i + 1000))).or_default() += 1;
+            }
+            assert_eq!(m.len(), 200);
+        }
+    }
+
     #[test]
     fn test_capacity_not_less_than_len() {
         let mut a = HashMap::new();
@@ -7632,6 +9667,49 @@ mod test_map {
         assert_eq!(a[key], value);
     }
 
+    #[test]
+    fn test_occupied_entry_into_key_value_mut() {
+        let mut a: HashMap<&str, Vec<u32>> = HashMap::new();
+        a.insert("hello", vec![1]);
+
+        let (key, value) = {
+            // The entry is dropped at the end of this block, but the returned
+            // references live as long as the borrow of the map.
+            let entry = match a.entry("hello") {
+                Vacant(_) => panic!(),
+                Occupied(e) => e,
+            };
+            entry.into_key_value_mut()
+        };
+        value.push(key.len() as u32);
+        assert_eq!(*key, "hello");
+
+        assert_eq!(a.len(), 1);
+        assert_eq!(a["hello"], [1, 5]);
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn test_raw_entry_bucket() {
+        let mut a: HashMap<u32, u32> = (0..32).map(|i| (i, i * 10)).collect();
+
+        let bucket = match a.raw_entry_mut().from_key(&100) {
+            RawEntryMut::Occupied(_) => panic!(),
+            RawEntryMut::Vacant(v) => v.insert_and_return_bucket(100, 1000),
+        };
+        // No mutation happened since the insertion, so the bucket is valid.
+        assert_eq!(unsafe { bucket.as_ref() }, &(100, 1000));
+        unsafe { bucket.as_mut().1 += 1 };
+        assert_eq!(a[&100], 1001);
+
+        let bucket = match a.raw_entry_mut().from_key(&7) {
+            RawEntryMut::Occupied(o) => o.bucket(),
+            RawEntryMut::Vacant(_) => panic!(),
+        };
+        assert_eq!(unsafe { bucket.as_ref() }, &(7, 70));
+        assert_eq!(a.len(), 33);
+    }
+
     #[test]
     fn test_occupied_entry_ref_key() {
         let mut a = HashMap::new();
@@ -7686,6 +9764,119 @@ mod test_map {
         assert_eq!(a[key], value);
     }
 
+    #[test]
+    fn test_get_or_insert_with() {
+        use std::string::String;
+
+        let mut m: HashMap<String, usize> = HashMap::new();
+        let mut calls = 0;
+        for &word in &["a", "b", "a", "a", "c", "b"] {
+            *m.get_or_insert_with(word, || {
+                calls += 1;
+                0
+            }) += 1;
+        }
+
+        // `make` only runs for keys that were missing.
+        assert_eq!(calls, 3);
+        assert_eq!(m.len(), 3);
+        assert_eq!(m["a"], 3);
+        assert_eq!(m["b"], 2);
+        assert_eq!(m["c"], 1);
+    }
+
+    #[test]
+    fn test_get_or_try_insert_with() {
+        use std::rc::Rc;
+
+        let mut m: HashMap<Rc<u32>, u32> = HashMap::new();
+        let key = Rc::new(1);
+
+        // Failure on the vacant path leaves no entry behind and drops the key.
+        let result: Result<&mut u32, &str> = m.get_or_try_insert_with(key.clone(), || Err("no"));
+        assert_eq!(result, Err("no"));
+        assert!(m.is_empty());
+        assert_eq!(m.capacity(), 0);
+        assert_eq!(Rc::strong_count(&key), 1);
+
+        // Success inserts the value.
+        let result: Result<&mut u32, &str> = m.get_or_try_insert_with(key.clone(), || Ok(10));
+        *result.unwrap() += 1;
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[&key], 11);
+        assert_eq!(Rc::strong_count(&key), 2);
+
+        // Occupied keys never call `make`, even if it would fail.
+        let result: Result<&mut u32, &str> =
+            m.get_or_try_insert_with(key.clone(), || panic!("called make"));
+        assert_eq!(result, Ok(&mut 11));
+        assert_eq!(Rc::strong_count(&key), 2);
+
+        // A failure after other inserts leaves the existing entries intact.
+        for i in 2..50 {
+            let _ = m.get_or_try_insert_with(Rc::new(i), || Ok::<_, ()>(i));
+        }
+        let before = m.clone();
+        let result: Result<&mut u32, ()> = m.get_or_try_insert_with(Rc::new(100), || Err(()));
+        assert!(result.is_err());
+        assert_eq!(m, before);
+        assert!(!m.contains_key(&100));
+    }
+
+    #[test]
+    fn test_vacant_entry_insert_reporting() {
+        let mut map: HashMap<u32, u32> = HashMap::with_capacity(100);
+        let capacity = map.capacity();
+        let mut reallocations = 0;
+        for i in 0..=capacity as u32 {
+            let was_full = map.len() == map.capacity();
+            match map.entry(i) {
+                Vacant(entry) => {
+                    let (value, reallocated) = entry.insert_reporting(i * 10);
+                    assert_eq!(*value, i * 10);
+                    assert_eq!(reallocated, was_full);
+                    reallocations += usize::from(reallocated);
+                }
+                Occupied(_) => unreachable!(),
+            }
+        }
+        // Only the entry past the load factor limit needed a reallocation.
+        assert_eq!(reallocations, 1);
+        assert!(map.capacity() > capacity);
+
+        // Reinserting a removed key doesn't reallocate either.
+        map.remove(&0);
+        match map.entry(0) {
+            Vacant(entry) => assert!(!entry.insert_reporting(0).1),
+            Occupied(_) => unreachable!(),
+        }
+        assert_eq!(map.len(), capacity + 1);
+    }
+
+    #[test]
+    fn test_vacant_entry_into_key() {
+        use std::string::String;
+
+        let mut a: HashMap<String, i32> = HashMap::new();
+        let key = String::from("hello there");
+        let ptr = key.as_ptr();
+
+        // The key is handed back without being cloned.
+        let key = match a.entry(key) {
+            Occupied(_) => panic!(),
+            Vacant(e) => e.into_key(),
+        };
+        assert_eq!(key.as_ptr(), ptr);
+        assert!(a.is_empty());
+
+        // A borrowed key is converted to the owned key type.
+        match a.entry_ref("hello there") {
+            EntryRef::Occupied(_) => panic!(),
+            EntryRef::Vacant(e) => assert_eq!(e.into_key(), key),
+        }
+        assert!(a.is_empty());
+    }
+
     #[test]
     fn test_occupied_entry_replace_entry_with() {
         let mut a = HashMap::new();
@@ -7729,6 +9920,28 @@ mod test_map {
         assert_eq!(a.len(), 0);
     }
 
+    #[test]
+    fn test_occupied_entry_insert_key() {
+        use std::rc::Rc;
+
+        let key_one = Rc::new(1);
+        let key_two = Rc::new(1);
+
+        let mut a = HashMap::new();
+        a.insert(key_one.clone(), "value");
+
+        let old_key = match a.entry(key_one.clone()) {
+            Occupied(mut e) => e.insert_key(key_two.clone()),
+            Vacant(_) => panic!(),
+        };
+        assert!(Rc::ptr_eq(&old_key, &key_one));
+
+        let (stored_key, value) = a.get_key_value(&1).unwrap();
+        assert!(Rc::ptr_eq(stored_key, &key_two));
+        assert_eq!(*value, "value");
+        assert_eq!(a.len(), 1);
+    }
+
     #[test]
     fn test_occupied_entry_ref_replace_entry_with() {
         let mut a: HashMap<std::string::String, &str> = HashMap::new();
@@ -8005,6 +10218,40 @@ mod test_map {
         }
     }
 
+    #[test]
+    fn test_replace_entry_with_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let value = Rc::new(10);
+        let mut map: HashMap<i32, Rc<i32>> = HashMap::new();
+        for i in 0..8 {
+            map.insert(i, value.clone());
+        }
+        assert_eq!(Rc::strong_count(&value), 9);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            map.entry(3)
+                .and_replace_entry_with(|_, _| panic!("panic in replace_entry_with"));
+        }));
+        assert!(result.is_err());
+
+        // The entry was taken out of the map before `f` ran, and its value
+        // was dropped exactly once while unwinding.
+        assert_eq!(Rc::strong_count(&value), 8);
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.iter().count(), 7);
+        assert!(!map.contains_key(&3));
+        for i in (0..8).filter(|&i| i != 3) {
+            assert_eq!(map.get(&i).map(|v| **v), Some(10));
+        }
+
+        // The map is still usable afterwards.
+        map.insert(3, value.clone());
+        assert_eq!(map.len(), 8);
+        assert_eq!(Rc::strong_count(&value), 9);
+    }
+
     #[test]
     fn test_replace_entry_ref_with_doesnt_corrupt() {
         #![allow(deprecated)] //rand
@@ -8049,10 +10296,105 @@ mod test_map {
     }
 
     #[test]
-    fn test_drain_filter() {
+    fn test_retain_while() {
+        use core::ops::ControlFlow;
+
+        let mut map: HashMap<i32, i32> = (0..100).map(|x| (x, x * 10)).collect();
+        let order: Vec<i32> = map.keys().copied().collect();
+
+        // Remove the first 30 visited entries, then stop.
+        let mut visited = Vec::new();
+        map.retain_while(|&k, v| {
+            if visited.len() == 30 {
+                return ControlFlow::Break(());
+            }
+            visited.push(k);
+            *v += 1;
+            ControlFlow::Continue(false)
+        });
+        assert_eq!(visited, order[..30]);
+        assert_eq!(map.len(), 70);
+        for &k in &order[..30] {
+            assert!(!map.contains_key(&k));
+        }
+        for &k in &order[30..] {
+            assert_eq!(map[&k], k * 10);
+        }
+
+        // Without breaking this behaves like `retain`.
+        let mut expected = map.clone();
+        expected.retain(|&k, _| k % 3 == 0);
+        map.retain_while(|&k, _| ControlFlow::Continue(k % 3 == 0));
+        assert_eq!(map, expected);
+    }
+
+    #[test]
+    fn test_transform_retain() {
+        use super::TransformAction::{Keep, Remove, RemoveAndInsert};
+
+        let mut map: HashMap<i32, i32> = (0..100).map(|x| (x, x * 10)).collect();
+        let mut visited = 0;
+        map.transform_retain(|&k, v| {
+            visited += 1;
+            match k % 4 {
+                0 => Keep,
+                1 => Remove,
+                2 => {
+                    *v += 1;
+                    Keep
+                }
+                _ => RemoveAndInsert(k + 1000, *v),
+            }
+        });
+        // Queued pairs are not visited.
+        assert_eq!(visited, 100);
+        assert_eq!(map.len(), 75);
+        for k in 0..100 {
+            match k % 4 {
+                0 => assert_eq!(map.get(&k), Some(&(k * 10))),
+                1 => assert_eq!(map.get(&k), None),
+                2 => assert_eq!(map.get(&k), Some(&(k * 10 + 1))),
+                _ => {
+                    assert_eq!(map.get(&k), None);
+                    assert_eq!(map.get(&(k + 1000)), Some(&(k * 10)));
+                }
+            }
+        }
+
+        // A queued key which is already present replaces its value.
+        map.transform_retain(|&k, _| if k == 1 { Keep } else { Remove });
+        assert!(map.is_empty());
+        map.insert(1, 1);
+        map.insert(2, 2);
+        map.transform_retain(|&k, &mut v| if k == 2 { RemoveAndInsert(1, v) } else { Keep });
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&1], 2);
+    }
+
+    #[test]
+    fn test_transform_retain_grows() {
+        use super::TransformAction::RemoveAndInsert;
+
+        // Fill the table to capacity, then replace every entry. Erasing from
+        // a full table mostly leaves tombstones behind, so applying the queued
+        // inserts has to reserve space again.
+        let mut map: HashMap<i32, i32> = HashMap::with_capacity(100);
+        let n = map.capacity() as i32;
+        map.extend((0..n).map(|k| (k, k)));
+        assert_eq!(map.len(), map.capacity());
+        let buckets = map.raw_capacity();
+
+        map.transform_retain(|&k, &mut v| RemoveAndInsert(k + n, v * 2));
+        assert_eq!(map.len(), n as usize);
+        assert!(map.raw_capacity() > buckets);
+        assert!((0..n).all(|k| !map.contains_key(&k) && map[&(k + n)] == k * 2));
+    }
+
+    #[test]
+    fn test_extract_if() {
         {
             let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
-            let drained = map.drain_filter(|&k, _| k % 2 == 0);
+            let drained = map.extract_if(|&k, _| k % 2 == 0);
             let mut out = drained.collect::<Vec<_>>();
             out.sort_unstable();
             assert_eq!(vec![(0, 0), (2, 20), (4, 40), (6, 60)], out);
@@ -8060,11 +10402,75 @@ mod test_map {
         }
         {
             let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
-            drop(map.drain_filter(|&k, _| k % 2 == 0));
+            drop(map.extract_if(|&k, _| k % 2 == 0));
             assert_eq!(map.len(), 4);
         }
     }
 
+    #[test]
+    fn test_extract_if_debug() {
+        let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+        let mut calls = 0;
+        let mut iter = map.extract_if(|&k, _| {
+            calls += 1;
+            k % 2 == 0
+        });
+        assert_eq!(format!("{:?}", iter), "ExtractIf { remaining: 8 }");
+        assert_eq!(format!("{:?}", iter), "ExtractIf { remaining: 8 }");
+        assert!(iter.next().is_some());
+        drop(iter);
+        assert_eq!(calls, 8);
+        assert_eq!(map.len(), 4);
+    }
+
+    #[test]
+    fn test_extract_if_early_drop() {
+        let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+        let buckets = map.table.buckets();
+        let mut calls = 0;
+        {
+            let mut iter = map.extract_if(|&k, _| {
+                calls += 1;
+                k % 2 == 0
+            });
+            assert!(iter.next().is_some());
+        }
+        // Dropping the iterator runs the predicate over the remaining entries.
+        assert_eq!(calls, 8);
+        assert_eq!(map.len(), 4);
+        assert!(map.keys().all(|&k| k % 2 != 0));
+        // Removed entries may leave tombstones, but nothing is reallocated.
+        assert_eq!(map.table.buckets(), buckets);
+    }
+
+    #[test]
+    fn test_extract_if_pred_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut map: HashMap<i32, i32> = (0..8).map(|x| (x, x * 10)).collect();
+        let buckets = map.table.buckets();
+        let mut calls = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            map.extract_if(|_, _| {
+                calls += 1;
+                if calls == 4 {
+                    panic!("panic in predicate");
+                }
+                true
+            })
+            .for_each(drop);
+        }));
+        assert!(result.is_err());
+
+        // The map must stay consistent, whatever was extracted before the panic.
+        assert_eq!(map.iter().count(), map.len());
+        assert!(map.len() < 8);
+        for (k, v) in &map {
+            assert_eq!(*v, k * 10);
+        }
+        assert_eq!(map.table.buckets(), buckets);
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)] // FIXME: no OOM signalling (https://github.com/rust-lang/miri/issues/613)
     fn test_try_reserve() {
@@ -8095,6 +10501,186 @@ mod test_map {
         }
     }
 
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_from_iter_allocates_once() {
+        use crate::raw::TestAlloc;
+
+        type Map = HashMap<u64, u64, DefaultHashBuilder, TestAlloc>;
+        let pairs: Vec<(u64, u64)> = (0..100_000).map(|i| (i, i)).collect();
+
+        // An exact size hint lets the table be allocated up front.
+        let map: Map = pairs.iter().copied().collect();
+        assert_eq!(map.allocator().allocations(), 1);
+        let mut map = Map::default();
+        map.extend(pairs.iter().copied());
+        assert_eq!(map.allocator().allocations(), 1);
+
+        // Without a lower bound the table grows as it goes.
+        let map: Map = pairs.iter().copied().filter(|_| true).collect();
+        assert!(map.allocator().allocations() > 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_reserve_grows_geometrically() {
+        use crate::raw::TestAlloc;
+
+        let alloc = TestAlloc::default();
+        let mut map = HashMap::new_in(alloc.clone());
+        for i in 0..100_000 {
+            map.reserve(1);
+            let capacity = map.capacity();
+            map.insert(i, i);
+            assert_eq!(map.capacity(), capacity);
+        }
+
+        // Every reallocation at least doubles the number of buckets, starting
+        // from 4 buckets, and 100_000 elements need 2^17 buckets.
+        assert_eq!(alloc.allocations(), 16);
+    }
+
+    #[test]
+    fn test_reserve_exact() {
+        use crate::TryReserveError::CapacityOverflow;
+
+        let mut map: HashMap<i32, i32> = HashMap::with_capacity(100);
+        let (buckets, len) = (map.table.buckets(), map.capacity() as i32);
+        for i in 0..len {
+            map.insert(i, i);
+        }
+
+        // Removing from a full table leaves tombstones which use up the
+        // remaining growth, even though most of the buckets are free.
+        map.retain(|&k, _| k % 3 != 0);
+        let mut geometric = map.clone();
+        let additional = map.growth_left() + 1;
+        assert!(map.len() + additional <= len as usize);
+
+        // `reserve` doubles the table, `reserve_exact` only rehashes it.
+        geometric.reserve(additional);
+        assert_eq!(geometric.table.buckets(), buckets * 2);
+        map.reserve_exact(additional);
+        assert_eq!(map.table.buckets(), buckets);
+        assert!(map.growth_left() >= additional);
+        map.try_reserve_exact(additional).unwrap();
+        assert_eq!(map.table.buckets(), buckets);
+        for i in 0..len {
+            assert_eq!(map.get(&i).is_some(), i % 3 != 0);
+        }
+
+        let mut map: HashMap<i32, i32> = HashMap::new();
+        map.reserve_exact(100);
+        assert_eq!(map.capacity(), HashMap::<i32, i32>::capacity_for(100));
+        if let Err(CapacityOverflow) = map.try_reserve_exact(usize::MAX) {
+        } else {
+            panic!("usize::MAX should trigger an overflow!");
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_growth_left() {
+        use crate::raw::TestAlloc;
+
+        let alloc = TestAlloc::default();
+        let mut map = HashMap::with_capacity_in(100, alloc.clone());
+        assert_eq!(alloc.allocations(), 1);
+        assert_eq!(map.growth_left(), map.capacity());
+
+        // Inserting `growth_left` new keys never reallocates or rehashes.
+        let mut next = 0;
+        let mut insert_batch = |map: &mut HashMap<_, _, _, _>| {
+            let (buckets, allocations) = (map.table.buckets(), alloc.allocations());
+            for _ in 0..map.growth_left() {
+                map.insert(next, next);
+                next += 1;
+            }
+            assert_eq!(map.table.buckets(), buckets);
+            assert_eq!(alloc.allocations(), allocations);
+        };
+        insert_batch(&mut map);
+        assert_eq!(map.len(), map.capacity());
+
+        // Removals may leave tombstones, which only make `growth_left` more
+        // conservative.
+        map.retain(|&k, _| k % 4 == 0);
+        let len = map.len();
+        insert_batch(&mut map);
+        assert!(map.len() >= len);
+        assert_eq!(alloc.allocations(), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_shrink_to_fit_empty_frees_allocation() {
+        use crate::raw::TestAlloc;
+
+        let alloc = TestAlloc::default();
+        let mut map = HashMap::new_in(alloc.clone());
+        for i in 0..100 {
+            map.insert(i, i * 10);
+        }
+        assert!(alloc.bytes() > 0);
+
+        // Removing every element leaves an empty map that still owns memory.
+        for i in 0..100 {
+            map.remove(&i);
+        }
+        assert!(alloc.bytes() > 0);
+        map.shrink_to_fit();
+        assert_eq!(alloc.bytes(), 0);
+        assert_eq!(alloc.live(), 0);
+        assert_eq!(map.capacity(), 0);
+        #[cfg(feature = "raw")]
+        assert_eq!(map.raw_table().allocation_size(), 0);
+
+        for i in 0..100 {
+            map.insert(i, i * 10);
+        }
+        map.clear();
+        map.shrink_to_fit();
+        assert_eq!(alloc.bytes(), 0);
+        assert_eq!(map.capacity(), 0);
+
+        // The map is usable again after being shrunk to nothing.
+        map.insert(1, 10);
+        assert_eq!(map[&1], 10);
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_try_reserve_alloc_error_leaves_map_intact() {
+        use crate::raw::TestAlloc;
+        use crate::TryReserveError::AllocError;
+
+        let alloc = TestAlloc::default();
+        let mut map = HashMap::new_in(alloc.clone());
+        for i in 0..100 {
+            map.insert(i, i * 10);
+        }
+        let capacity = map.capacity();
+
+        alloc.set_fail(true);
+        if let Err(AllocError { .. }) = map.try_reserve(capacity * 2) {
+        } else {
+            panic!("a failing allocator should trigger an AllocError!");
+        }
+
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.capacity(), capacity);
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+
+        alloc.set_fail(false);
+        map.try_reserve(capacity * 2).unwrap();
+        assert!(map.capacity() >= 100 + capacity * 2);
+        for i in 0..100 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+    }
+
     #[test]
     fn test_raw_entry() {
         use super::RawEntryMut::{Occupied, Vacant};
@@ -8196,6 +10782,46 @@ mod test_map {
         }
     }
 
+    #[test]
+    fn test_raw_entry_without_eq() {
+        use super::RawEntryMut::Vacant;
+
+        // Neither `Eq` nor `Hash` is implemented, so lookups have to go
+        // through an external hash and a custom equality.
+        #[derive(Debug, PartialEq)]
+        struct Sample(f64);
+
+        fn hash(sample: &Sample) -> u64 {
+            sample.0.to_bits().wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        }
+
+        fn lookup(map: &HashMap<Sample, u32>, key: f64) -> Option<(&Sample, &u32)> {
+            map.raw_entry()
+                .from_hash(hash(&Sample(key)), |k| k.0.to_bits() == key.to_bits())
+        }
+
+        let mut map: HashMap<Sample, u32> = HashMap::new();
+        for i in 0..100 {
+            let sample = Sample(f64::from(i) / 4.0);
+            let sample_hash = hash(&sample);
+            match map
+                .raw_entry_mut()
+                .from_hash(sample_hash, |k| k.0 == sample.0)
+            {
+                Vacant(entry) => {
+                    entry.insert_with_hasher(sample_hash, sample, i, hash);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        let map = &map;
+        assert_eq!(lookup(map, 2.5), Some((&Sample(2.5), &10)));
+        assert_eq!(lookup(map, 24.75), Some((&Sample(24.75), &99)));
+        assert_eq!(lookup(map, 25.0), None);
+        assert_eq!(lookup(map, f64::NAN), None);
+    }
+
     #[test]
     fn test_key_without_hash_impl() {
         #[derive(Debug)]
@@ -8280,7 +10906,7 @@ mod test_map {
                             let e = map.table.insert(
                                 hash_value,
                                 (i, 2 * i),
-                                super::make_hasher::<usize, _, usize, _>(&hash_builder),
+                                super::make_hasher::<_, usize, _>(&hash_builder),
                             );
                             it.reflect_insert(&e);
                             if let Some(p) = removed.iter().position(|e| e == &(i, 2 * i)) {
@@ -8360,6 +10986,228 @@ mod test_map {
 
         let ys = map.get_many_key_value_mut(["baz", "baz"]);
         assert_eq!(ys, None);
+
+        if let Some([foo, qux]) = map.get_many_mut(["foo", "qux"]) {
+            std::mem::swap(foo, qux);
+        }
+        assert_eq!(map["foo"], 30);
+        assert_eq!(map["qux"], 0);
+
+        let zs = unsafe { map.get_many_unchecked_mut(["bar", "baz"]) };
+        assert_eq!(zs, Some([&mut 10, &mut 20]));
+
+        let zs = unsafe { map.get_many_unchecked_mut(["bar", "dud"]) };
+        assert_eq!(zs, None);
+    }
+
+    #[test]
+    fn test_get_many_key_value_mut_equivalent() {
+        use crate::Equivalent;
+        use core::hash::{Hash, Hasher};
+        use std::string::String;
+
+        fn hash_folded<H: Hasher>(s: &str, state: &mut H) {
+            for b in s.bytes() {
+                b.to_ascii_lowercase().hash(state);
+            }
+        }
+
+        // A case-insensitive key which remembers how it was first spelled.
+        #[derive(Debug)]
+        struct Name(String);
+
+        impl PartialEq for Name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0.eq_ignore_ascii_case(&other.0)
+            }
+        }
+
+        impl Eq for Name {}
+
+        impl Hash for Name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                hash_folded(&self.0, state);
+            }
+        }
+
+        // Looks up a `Name` with any spelling.
+        struct Query<'a>(&'a str);
+
+        impl Hash for Query<'_> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                hash_folded(self.0, state);
+            }
+        }
+
+        impl Equivalent<Name> for Query<'_> {
+            fn equivalent(&self, key: &Name) -> bool {
+                self.0.eq_ignore_ascii_case(&key.0)
+            }
+        }
+
+        let mut map = HashMap::new();
+        map.insert(Name("Alice".to_owned()), 1);
+        map.insert(Name("BOB".to_owned()), 2);
+        map.insert(Name("carol".to_owned()), 3);
+
+        // The stored spelling is returned, not the one used for the lookup.
+        let [(alice, a), (bob, b)] = map
+            .get_many_key_value_mut([&Query("alice"), &Query("Bob")])
+            .unwrap();
+        assert_eq!(alice.0, "Alice");
+        assert_eq!(bob.0, "BOB");
+        core::mem::swap(a, b);
+        assert_eq!(map[&Query("ALICE")], 2);
+        assert_eq!(map[&Query("bob")], 1);
+
+        // Equivalent spellings of the same key are not distinct.
+        assert!(map
+            .get_many_key_value_mut([&Query("carol"), &Query("CAROL")])
+            .is_none());
+        assert!(map
+            .get_many_key_value_mut([&Query("carol"), &Query("dave")])
+            .is_none());
+    }
+
+    #[test]
+    fn test_rehash() {
+        let mut map: HashMap<i32, i32> = HashMap::new();
+        map.rehash();
+        assert_eq!(map.capacity(), 0);
+
+        for i in 0..1000 {
+            map.insert(i, i * 10);
+        }
+        let capacity = map.capacity();
+        map.retain(|&k, _| k % 10 == 0);
+        map.rehash();
+
+        // Rehashing neither reallocates nor leaves capacity locked up in
+        // tombstones.
+        assert_eq!(map.capacity(), capacity);
+        assert_eq!(map.len(), 100);
+        for i in 0..1000 {
+            let expected = if i % 10 == 0 { Some(i * 10) } else { None };
+            assert_eq!(map.get(&i).copied(), expected);
+        }
+    }
+
+    #[test]
+    fn test_capacity_for() {
+        assert_eq!(HashMap::<i32, i32>::capacity_for(0), 0);
+        for len in (0..200).chain([1000, 1 << 16, (1 << 16) + 1]) {
+            let map: HashMap<i32, i32> = HashMap::with_capacity(len);
+            assert_eq!(HashMap::<i32, i32>::capacity_for(len), map.capacity());
+            assert!(map.capacity() >= len);
+        }
+    }
+
+    #[test]
+    fn test_with_exact_capacity() {
+        for len in 1..=200 {
+            let mut map: HashMap<u64, u64> = HashMap::with_exact_capacity(len);
+            let capacity = map.capacity();
+            assert!(capacity >= len);
+            let buckets = map.table.buckets();
+            assert!(buckets <= HashMap::<u64, u64>::with_capacity(len).table.buckets());
+
+            // The table can be filled up without growing, and lookups of
+            // missing keys still terminate.
+            for i in 0..capacity as u64 {
+                map.insert(i, i);
+                assert_eq!(map.get(&(i + 1)), None);
+            }
+            assert_eq!(map.table.buckets(), buckets);
+            for i in 0..capacity as u64 {
+                assert_eq!(map[&i], i);
+            }
+
+            // Removals and reinsertions keep working on the full table.
+            for i in (0..capacity as u64).step_by(3) {
+                assert_eq!(map.remove(&i), Some(i));
+            }
+            for i in (0..capacity as u64).step_by(3) {
+                map.insert(i, i);
+            }
+            assert_eq!(map.len(), capacity);
+            let mut grown = map.clone();
+            grown.insert(u64::MAX, 0);
+            assert_eq!(grown.len(), capacity + 1);
+
+            map.clear();
+            assert!(map.is_empty());
+            map.insert(1, 1);
+            assert_eq!(map[&1], 1);
+        }
+    }
+
+    #[test]
+    #[should_panic = "Hash table capacity overflow"]
+    fn test_capacity_for_overflow() {
+        HashMap::<i32, i32>::capacity_for(usize::MAX);
+    }
+
+    #[test]
+    fn test_get_disjoint_mut() {
+        let mut map: HashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+
+        let none: &[&u32] = &[];
+        assert_eq!(map.get_disjoint_mut(none), Some(Vec::new()));
+
+        // Short and long lists take different paths to detect duplicates.
+        for len in [3, 50] {
+            let keys: Vec<u32> = (0..len).map(|i| i * 2).collect();
+            let refs: Vec<&u32> = keys.iter().collect();
+            let values = map.get_disjoint_mut(&refs).unwrap();
+            assert_eq!(values.len(), len as usize);
+            for (value, key) in values.into_iter().zip(&keys) {
+                assert_eq!(*value % 1000, *key);
+                *value += 1000;
+            }
+
+            let mut overlapping = refs.clone();
+            overlapping.push(refs[len as usize / 2]);
+            assert_eq!(map.get_disjoint_mut(&overlapping), None);
+
+            let mut missing = refs.clone();
+            missing.push(&1000);
+            assert_eq!(map.get_disjoint_mut(&missing), None);
+        }
+
+        for i in 0..100 {
+            // Even keys below 6 were updated by both rounds.
+            let expected = if i % 2 == 1 {
+                i
+            } else if i < 6 {
+                i + 2000
+            } else {
+                i + 1000
+            };
+            assert_eq!(map[&i], expected);
+        }
+    }
+
+    #[test]
+    fn test_try_get_many_mut() {
+        use crate::GetManyMutError;
+
+        let mut map = HashMap::new();
+        map.insert("foo".to_owned(), 0);
+        map.insert("bar".to_owned(), 10);
+        map.insert("baz".to_owned(), 20);
+
+        let xs = map.try_get_many_mut(["foo", "baz"]);
+        assert_eq!(xs, Ok([&mut 0, &mut 20]));
+
+        let xs = map.try_get_many_mut(["foo", "dud", "dip"]);
+        assert_eq!(xs, Err(GetManyMutError::MissingKey(1)));
+
+        let xs = map.try_get_many_mut(["foo", "bar", "baz", "bar", "foo"]);
+        assert_eq!(xs, Err(GetManyMutError::DuplicateKey(1, 3)));
+
+        // Missing keys are reported before duplicates.
+        let xs = map.try_get_many_mut(["foo", "foo", "dud"]);
+        assert_eq!(xs, Err(GetManyMutError::MissingKey(2)));
     }
 
     #[test]
@@ -71,3 +71,69 @@ mod inner {
         unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
     }
 }
+
+/// Allocator shared by the tests which need to observe or fail allocations.
+///
+/// All clones share the same counters, so a handle kept by the test sees the
+/// allocations made through the copy owned by a table. The counters are
+/// atomic so that it can also be used from rayon worker threads.
+#[cfg(all(test, not(feature = "nightly")))]
+pub(crate) mod testing {
+    use super::{Allocator, Global};
+    use crate::alloc::alloc::Layout;
+    use crate::alloc::sync::Arc;
+    use core::ptr::NonNull;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counters {
+        allocations: AtomicUsize,
+        live: AtomicUsize,
+        bytes: AtomicUsize,
+        fail: AtomicBool,
+    }
+
+    #[derive(Clone, Default)]
+    pub(crate) struct TestAlloc(Arc<Counters>);
+
+    impl TestAlloc {
+        /// Total number of successful allocations made so far.
+        pub(crate) fn allocations(&self) -> usize {
+            self.0.allocations.load(Ordering::SeqCst)
+        }
+
+        /// Number of allocations which have not been freed yet.
+        pub(crate) fn live(&self) -> usize {
+            self.0.live.load(Ordering::SeqCst)
+        }
+
+        /// Number of bytes currently allocated.
+        pub(crate) fn bytes(&self) -> usize {
+            self.0.bytes.load(Ordering::SeqCst)
+        }
+
+        /// Makes every following allocation fail, or succeed again.
+        pub(crate) fn set_fail(&self, fail: bool) {
+            self.0.fail.store(fail, Ordering::SeqCst);
+        }
+    }
+
+    unsafe impl Allocator for TestAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, ()> {
+            if self.0.fail.load(Ordering::SeqCst) {
+                return Err(());
+            }
+            let ptr = Global.allocate(layout)?;
+            self.0.allocations.fetch_add(1, Ordering::SeqCst);
+            self.0.live.fetch_add(1, Ordering::SeqCst);
+            self.0.bytes.fetch_add(layout.size(), Ordering::SeqCst);
+            Ok(ptr)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.live.fetch_sub(1, Ordering::SeqCst);
+            self.0.bytes.fetch_sub(layout.size(), Ordering::SeqCst);
+            Global.deallocate(ptr, layout);
+        }
+    }
+}
@@ -45,6 +45,38 @@ impl BitMask {
     pub fn remove_lowest_bit(self) -> Self {
         BitMask(self.0 & (self.0 - 1))
     }
+
+    /// Returns a new `BitMask` with the highest bit removed.
+    #[inline]
+    #[must_use]
+    pub fn remove_highest_bit(self) -> Self {
+        if self.0 == 0 {
+            self
+        } else {
+            let bit = BitMaskWord::BITS - 1 - self.0.leading_zeros();
+            BitMask(self.0 & !(1 << bit))
+        }
+    }
+
+    /// Returns a new `BitMask` with the bits for the entry at `index` and all
+    /// entries above it removed.
+    #[inline]
+    #[must_use]
+    #[cfg(feature = "rayon")]
+    pub fn remove_bits_from(self, index: usize) -> Self {
+        let above = BitMaskWord::MAX
+            .checked_shl((index * BITMASK_STRIDE) as u32)
+            .unwrap_or(0);
+        BitMask(self.0 & !above)
+    }
+
+    /// Returns the number of set bits in the `BitMask`.
+    #[inline]
+    #[cfg(feature = "rayon")]
+    pub fn count_ones(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
     /// Returns whether the `BitMask` has at least one set bit.
     #[inline]
     pub fn any_bit_set(self) -> bool {
@@ -61,6 +93,16 @@ impl BitMask {
         }
     }
 
+    /// Returns the last set bit in the `BitMask`, if there is one.
+    #[inline]
+    pub fn highest_set_bit(self) -> Option<usize> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some((BitMaskWord::BITS - 1 - self.0.leading_zeros()) as usize / BITMASK_STRIDE)
+        }
+    }
+
     /// Returns the first set bit in the `BitMask`, if there is one. The
     /// bitmask must not be empty.
     #[inline]
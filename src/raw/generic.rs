@@ -48,6 +48,10 @@ impl Group {
     /// Number of bytes in the group.
     pub const WIDTH: usize = mem::size_of::<Self>();
 
+    /// Whether matching a whole group is a single SIMD operation. It is not
+    /// for word-sized groups, which keep the portable per-byte loops.
+    pub const IS_SIMD: bool = false;
+
     /// Returns a full group of empty bytes, suitable for use as the initial
     /// value for an empty hash table.
     ///
@@ -111,6 +115,20 @@ impl Group {
         BitMask((cmp.wrapping_sub(repeat(0x01)) & !cmp & repeat(0x80)).to_le())
     }
 
+    /// Returns a `BitMask` indicating all bytes in the group which have any
+    /// of the given values.
+    ///
+    /// This may return false positives in the same way as `match_byte`.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn match_any_of<const N: usize>(self, bytes: [u8; N]) -> BitMask {
+        let mut mask = 0;
+        for &byte in &bytes {
+            mask |= self.match_byte(byte).0;
+        }
+        BitMask(mask)
+    }
+
     /// Returns a `BitMask` indicating all bytes in the group which are
     /// `EMPTY`.
     #[inline]
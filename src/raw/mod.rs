@@ -1,6 +1,10 @@
 use crate::alloc::alloc::{handle_alloc_error, Layout};
+use crate::alloc::vec::Vec;
 use crate::scopeguard::{guard, ScopeGuard};
-use crate::TryReserveError;
+use crate::{GetManyMutError, TryReserveError};
+#[cfg(feature = "raw")]
+use core::convert::Infallible;
+use core::fmt;
 use core::iter::FusedIterator;
 use core::marker::PhantomData;
 use core::mem;
@@ -15,16 +19,32 @@ cfg_if! {
     // runtime dispatch and wouldn't gain us much anyways: the probability of
     // finding a match drops off drastically after the first few buckets.
     //
-    // I attempted an implementation on ARM using NEON instructions, but it
-    // turns out that most NEON instructions have multi-cycle latency, which in
-    // the end outweighs any gains over the generic implementation.
+    // On AArch64 we use NEON, which also scans 16 buckets at once. NEON has
+    // no `movemask` instruction so the match results are packed into a
+    // nibble-per-byte mask instead. Big-endian targets use the generic
+    // implementation since the packing relies on the lane order, as do
+    // compilers older than 1.59 which lack the intrinsics (see `build.rs`).
+    //
+    // The `small-groups` feature forces the generic implementation, whose
+    // groups are only a word wide, to reduce the footprint of small tables.
     if #[cfg(all(
         target_feature = "sse2",
         any(target_arch = "x86", target_arch = "x86_64"),
-        not(miri)
+        not(miri),
+        not(feature = "small-groups")
     ))] {
         mod sse2;
         use sse2 as imp;
+    } else if #[cfg(all(
+        target_arch = "aarch64",
+        target_feature = "neon",
+        target_endian = "little",
+        hashbrown_neon,
+        not(miri),
+        not(feature = "small-groups")
+    ))] {
+        mod neon;
+        use neon as imp;
     } else {
         #[path = "generic.rs"]
         mod generic;
@@ -33,11 +53,15 @@ cfg_if! {
 }
 
 mod alloc;
+#[cfg(all(test, not(feature = "nightly")))]
+pub(crate) use self::alloc::testing::TestAlloc;
 pub(crate) use self::alloc::{do_alloc, Allocator, Global};
 
 mod bitmask;
 
-use self::bitmask::{BitMask, BitMaskIter};
+use self::bitmask::BitMask;
+#[cfg(feature = "raw")]
+use self::bitmask::BitMaskIter;
 use self::imp::Group;
 
 // Branch prediction hint. This is currently only available on nightly but it
@@ -69,6 +93,32 @@ fn unlikely(b: bool) -> bool {
     b
 }
 
+/// Hints to the CPU that the cache line containing `ptr` will be read soon.
+///
+/// This compiles down to nothing on targets without a stable prefetch
+/// intrinsic.
+#[cfg(feature = "raw")]
+#[inline(always)]
+fn prefetch_read<T>(ptr: *const T) {
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse"
+    ))]
+    unsafe {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86;
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64 as x86;
+
+        x86::_mm_prefetch(ptr.cast::<i8>(), x86::_MM_HINT_T0);
+    }
+    #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse"
+    )))]
+    let _ = ptr;
+}
+
 #[inline]
 unsafe fn offset_from<T>(to: *const T, from: *const T) -> usize {
     to.offset_from(from) as usize
@@ -206,6 +256,16 @@ fn capacity_to_buckets(cap: usize) -> Option<usize> {
     Some(adjusted_cap.next_power_of_two())
 }
 
+/// Returns the number of buckets needed to hold `cap` elements while only
+/// leaving a single bucket empty, ignoring the maximum load factor.
+#[cfg_attr(feature = "inline-more", inline)]
+fn capacity_to_exact_buckets(cap: usize) -> Option<usize> {
+    // `capacity_to_buckets` never returns fewer buckets, so its overflow check
+    // covers this one too.
+    let buckets = capacity_to_buckets(cap)?;
+    Some(usize::min(buckets, (cap + 1).next_power_of_two()))
+}
+
 /// Returns the maximum effective capacity for the given bucket mask, taking
 /// the maximum load factor into account.
 #[inline]
@@ -248,6 +308,12 @@ impl TableLayout {
             size.checked_mul(buckets)?.checked_add(ctrl_align - 1)? & !(ctrl_align - 1);
         let len = ctrl_offset.checked_add(buckets + Group::WIDTH)?;
 
+        // We need an additional check to ensure that the allocation doesn't
+        // exceed `isize::MAX` (https://github.com/rust-lang/rust/pull/95295).
+        if len > isize::MAX as usize - (ctrl_align - 1) {
+            return None;
+        }
+
         Some((
             unsafe { Layout::from_size_align_unchecked(len, ctrl_align) },
             ctrl_offset,
@@ -303,13 +369,19 @@ impl<T> Bucket<T> {
         }
     }
     #[inline]
-    unsafe fn to_base_index(&self, base: NonNull<T>) -> usize {
+    pub(crate) unsafe fn to_base_index(&self, base: NonNull<T>) -> usize {
         if mem::size_of::<T>() == 0 {
             self.ptr.as_ptr() as usize - 1
         } else {
             offset_from(base.as_ptr(), self.ptr.as_ptr())
         }
     }
+    /// Returns a raw pointer to the element in this bucket.
+    ///
+    /// The pointer is only valid to dereference while the bucket is full and
+    /// the table is neither moved nor resized. It must not be used to create
+    /// a `&mut T` while any other reference to the same element is live. For
+    /// zero-sized types this is a dangling but well-aligned pointer.
     #[inline]
     pub fn as_ptr(&self) -> *mut T {
         if mem::size_of::<T>() == 0 {
@@ -319,8 +391,30 @@ impl<T> Bucket<T> {
             unsafe { self.ptr.as_ptr().sub(1) }
         }
     }
+    /// Returns `true` if both buckets refer to the same slot.
+    ///
+    /// This is only meaningful for buckets from the same table. Unlike
+    /// comparing [`as_ptr`](Self::as_ptr), it also distinguishes slots of
+    /// zero-sized types.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+    /// Returns the bucket `offset` positions after this one, in the same order
+    /// as bucket indices.
+    ///
+    /// Buckets are laid out in memory downward from the control bytes, so this
+    /// moves the element pointer towards lower addresses: if `self` is
+    /// `table.bucket(i)` then the result is `table.bucket(i + offset)`.
+    ///
+    /// # Safety
+    ///
+    /// `i + offset` must be less than the number of buckets in the table. The
+    /// control byte of the returned bucket is not checked, so it may be empty
+    /// or deleted and must not be read from unless it is known to be full.
     #[inline]
-    unsafe fn next_n(&self, offset: usize) -> Self {
+    pub unsafe fn next_n(&self, offset: usize) -> Self {
         let ptr = if mem::size_of::<T>() == 0 {
             (self.ptr.as_ptr() as usize + offset) as *mut T
         } else {
@@ -357,6 +451,35 @@ impl<T> Bucket<T> {
     }
 }
 
+/// A reference to an empty bucket into which an element can be inserted.
+///
+/// This is returned by [`RawTable::find_or_find_insert_slot`] and only
+/// remains valid until the table is next modified.
+pub struct InsertSlot {
+    index: usize,
+}
+
+/// A copyable handle to a bucket, holding only its index.
+///
+/// This is returned by [`RawTable::bucket_token`] and can be turned back into
+/// a `Bucket` with [`RawTable::bucket_from_token`]. Unlike a `Bucket` it does
+/// not point into the table's memory, but it is still only valid until the
+/// table is next reallocated (by growing, shrinking or rehashing).
+#[cfg(feature = "raw")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BucketToken {
+    index: usize,
+}
+
+#[cfg(feature = "raw")]
+impl BucketToken {
+    /// Returns the index of the bucket this token refers to.
+    #[inline]
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
 /// A raw hash table with an unsafe API.
 pub struct RawTable<T, A: Allocator + Clone = Global> {
     table: RawTableInner<A>,
@@ -410,6 +533,14 @@ impl<T> RawTable<T, Global> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self::with_capacity_in(capacity, Global)
     }
+
+    /// Allocates a new hash table with the smallest number of buckets that can
+    /// hold the given number of elements without reallocating.
+    ///
+    /// See [`with_exact_capacity_in`](Self::with_exact_capacity_in).
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        Self::with_exact_capacity_in(capacity, Global)
+    }
 }
 
 impl<T, A: Allocator + Clone> RawTable<T, A> {
@@ -484,6 +615,36 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
+    /// Allocates a new hash table using the given allocator, with the smallest
+    /// number of buckets that can hold the given number of elements without
+    /// reallocating.
+    ///
+    /// Unlike [`with_capacity_in`](Self::with_capacity_in), this ignores the
+    /// maximum load factor and lets every bucket but one be filled, which
+    /// makes probe sequences longer once the table is nearly full. The table
+    /// goes back to the normal load factor once it is cleared, rehashed or
+    /// grown.
+    ///
+    /// The number of buckets is still a power of two, so this only allocates
+    /// less when the load factor would have doubled the bucket count, such as
+    /// for 15 elements (16 buckets instead of 32). For most small capacities
+    /// it returns the same table as `with_capacity_in`.
+    pub fn with_exact_capacity_in(capacity: usize, alloc: A) -> Self {
+        // Avoid `Result::unwrap_or_else` because it bloats LLVM IR.
+        match RawTableInner::fallible_with_exact_capacity(
+            alloc,
+            TableLayout::new::<T>(),
+            capacity,
+            Fallibility::Infallible,
+        ) {
+            Ok(table) => Self {
+                table,
+                marker: PhantomData,
+            },
+            Err(_) => unsafe { hint::unreachable_unchecked() },
+        }
+    }
+
     /// Returns a reference to the underlying allocator.
     #[inline]
     pub fn allocator(&self) -> &A {
@@ -516,6 +677,17 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
     }
 
     /// Returns a pointer to an element in the table.
+    ///
+    /// Together with [`bucket_index`](Self::bucket_index) and
+    /// [`is_bucket_full`](Self::is_bucket_full) this allows keeping `usize`
+    /// handles to elements. A handle is only valid until the table is next
+    /// reallocated (by growing, shrinking or rehashing), after which elements
+    /// may live at different indices.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the number of buckets. The bucket is returned
+    /// regardless of occupancy, so it must be full before it is read from.
     #[inline]
     pub unsafe fn bucket(&self, index: usize) -> Bucket<T> {
         debug_assert_ne!(self.table.bucket_mask, 0);
@@ -523,6 +695,47 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         Bucket::from_base_index(self.data_end(), index)
     }
 
+    /// Returns `true` if the bucket at `index` holds an element.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the number of buckets.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub unsafe fn is_bucket_full(&self, index: usize) -> bool {
+        debug_assert!(index < self.buckets());
+        is_full(*self.table.ctrl(index))
+    }
+
+    /// Returns a token for `bucket` which can later be resolved back into the
+    /// same bucket with [`bucket_from_token`](Self::bucket_from_token).
+    ///
+    /// # Safety
+    ///
+    /// `bucket` must belong to this table.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub unsafe fn bucket_token(&self, bucket: &Bucket<T>) -> BucketToken {
+        BucketToken {
+            index: self.bucket_index(bucket),
+        }
+    }
+
+    /// Returns the bucket referred to by a token from
+    /// [`bucket_token`](Self::bucket_token).
+    ///
+    /// # Safety
+    ///
+    /// The token must have been created by this table, and the table must not
+    /// have been reallocated since. The bucket is not checked for occupancy,
+    /// so if its element may have been removed then use
+    /// [`is_bucket_full`](Self::is_bucket_full) before reading from it.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub unsafe fn bucket_from_token(&self, token: BucketToken) -> Bucket<T> {
+        self.bucket(token.index)
+    }
+
     /// Erases an element from the table without dropping it.
     #[cfg_attr(feature = "inline-more", inline)]
     #[deprecated(since = "0.8.1", note = "use erase or remove instead")]
@@ -577,14 +790,33 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
     }
 
     /// Marks all table buckets as empty without dropping their contents.
+    ///
+    /// This only resets the control bytes with a single fill and keeps the
+    /// allocation, so it is cheap regardless of how many elements the table
+    /// holds. Any elements that are still in the table are leaked; callers
+    /// which manage element drops themselves must drop or move them out
+    /// beforehand.
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn clear_no_drop(&mut self) {
         self.table.clear_no_drop();
     }
 
     /// Removes all elements from the table without freeing the backing memory.
+    ///
+    /// If `T` doesn't need to be dropped then this doesn't visit the buckets
+    /// and is equivalent to [`clear_no_drop`](Self::clear_no_drop).
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn clear(&mut self) {
+        if self.table.items == 0
+            && self.table.growth_left == bucket_mask_to_capacity(self.table.bucket_mask)
+        {
+            // The table is empty and has no DELETED control bytes (otherwise
+            // they would be taking up some of the capacity), so there is
+            // nothing to reset. The length has to be checked as well, since
+            // a table created with `with_exact_capacity_in` can hold more than
+            // `bucket_mask_to_capacity` elements.
+            return;
+        }
         // Ensure that the table is reset even if one of the drops panic
         let mut self_ = guard(self, |self_| self_.clear_no_drop());
         unsafe {
@@ -592,6 +824,28 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
+    /// Removes all elements from the table, and reallocates it to hold
+    /// `max_capacity` elements if it currently has more buckets than needed
+    /// for that.
+    ///
+    /// Since the table is empty afterwards, shrinking doesn't need to move
+    /// any elements.
+    pub fn clear_and_shrink_to(&mut self, max_capacity: usize) {
+        let shrink = if max_capacity == 0 {
+            !self.table.is_empty_singleton()
+        } else {
+            capacity_to_buckets(max_capacity).map_or(false, |buckets| buckets < self.buckets())
+        };
+        if shrink {
+            // The old table drops its elements after it has been replaced, so
+            // `self` is left valid even if one of the drops panics.
+            let table = Self::with_capacity_in(max_capacity, self.table.alloc.clone());
+            drop(mem::replace(self, table));
+        } else {
+            self.clear();
+        }
+    }
+
     unsafe fn drop_elements(&mut self) {
         if mem::needs_drop::<T>() && !self.is_empty() {
             for item in self.iter() {
@@ -644,7 +898,26 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         if additional > self.table.growth_left {
             // Avoid `Result::unwrap_or_else` because it bloats LLVM IR.
             if self
-                .reserve_rehash(additional, hasher, Fallibility::Infallible)
+                .reserve_rehash(additional, hasher, Fallibility::Infallible, false)
+                .is_err()
+            {
+                unsafe { hint::unreachable_unchecked() }
+            }
+        }
+    }
+
+    /// Ensures that at least `additional` items can be inserted into the table
+    /// without reallocation, growing it no more than necessary.
+    ///
+    /// Unlike [`reserve`](Self::reserve), this does not at least double the
+    /// number of buckets when the table has to grow, so calling it repeatedly
+    /// with small values can reallocate on every call.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve_exact(&mut self, additional: usize, hasher: impl Fn(&T) -> u64) {
+        if additional > self.table.growth_left {
+            // Avoid `Result::unwrap_or_else` because it bloats LLVM IR.
+            if self
+                .reserve_rehash(additional, hasher, Fallibility::Infallible, true)
                 .is_err()
             {
                 unsafe { hint::unreachable_unchecked() }
@@ -661,13 +934,29 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         hasher: impl Fn(&T) -> u64,
     ) -> Result<(), TryReserveError> {
         if additional > self.table.growth_left {
-            self.reserve_rehash(additional, hasher, Fallibility::Fallible)
+            self.reserve_rehash(additional, hasher, Fallibility::Fallible, false)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Tries to ensure that at least `additional` items can be inserted into
+    /// the table without reallocation, growing it no more than necessary.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve_exact(
+        &mut self,
+        additional: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        if additional > self.table.growth_left {
+            self.reserve_rehash(additional, hasher, Fallibility::Fallible, true)
         } else {
             Ok(())
         }
     }
 
-    /// Out-of-line slow path for `reserve` and `try_reserve`.
+    /// Out-of-line slow path for `reserve`, `try_reserve` and their `_exact`
+    /// variants.
     #[cold]
     #[inline(never)]
     fn reserve_rehash(
@@ -675,12 +964,14 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         additional: usize,
         hasher: impl Fn(&T) -> u64,
         fallibility: Fallibility,
+        exact: bool,
     ) -> Result<(), TryReserveError> {
         unsafe {
             self.table.reserve_rehash_inner(
                 additional,
                 &|table, index| hasher(table.bucket::<T>(index).as_ref()),
                 fallibility,
+                exact,
                 TableLayout::new::<T>(),
                 if mem::needs_drop::<T>() {
                     Some(mem::transmute(ptr::drop_in_place::<T> as unsafe fn(*mut T)))
@@ -709,6 +1000,37 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
+    /// Rehashes the elements of the table in place, without reallocating.
+    ///
+    /// This turns all DELETED control bytes left behind by removals back into
+    /// EMPTY ones and moves every element as close as possible to the start
+    /// of its probe sequence, which shortens lookups in tables that have
+    /// accumulated many tombstones. The table does this on its own when an
+    /// insertion would otherwise have to grow a table with plenty of capacity
+    /// locked up in tombstones.
+    ///
+    /// If `hasher` panics then some of the table's contents may be lost.
+    #[cold]
+    #[inline(never)]
+    pub fn rehash_in_place(&mut self, hasher: impl Fn(&T) -> u64) {
+        if self.table.is_empty_singleton() {
+            return;
+        }
+        unsafe {
+            self.table.rehash_in_place(
+                &|table, index| hasher(table.bucket::<T>(index).as_ref()),
+                mem::size_of::<T>(),
+                if mem::needs_drop::<T>() {
+                    Some(mem::transmute::<unsafe fn(*mut T), fn(*mut u8)>(
+                        ptr::drop_in_place::<T>,
+                    ))
+                } else {
+                    None
+                },
+            );
+        }
+    }
+
     /// Inserts a new element into the table, and returns its raw bucket.
     ///
     /// This does not check if the given element already exists in the table.
@@ -758,6 +1080,22 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
     /// Inserts a new element into the table, and returns a mutable reference to it.
     ///
     /// This does not check if the given element already exists in the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "raw")]
+    /// # {
+    /// use hashbrown::raw::RawTable;
+    ///
+    /// // Counts stored with their key, which is also used as the hash.
+    /// let hasher = |&(key, _): &(u64, u32)| key;
+    /// let mut table = RawTable::new();
+    ///
+    /// table.insert_entry(7, (7, 0), hasher).1 += 1;
+    /// assert_eq!(table.get(7, |&(key, _)| key == 7), Some(&(7, 1)));
+    /// # }
+    /// ```
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn insert_entry(&mut self, hash: u64, value: T, hasher: impl Fn(&T) -> u64) -> &mut T {
         unsafe { self.insert(hash, value, hasher).as_mut() }
@@ -766,14 +1104,26 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
     /// Inserts a new element into the table, without growing the table.
     ///
     /// There must be enough space in the table to insert the new element.
+    /// After a call to [`reserve`] for `additional` items, up to `additional`
+    /// elements can be inserted with this method; calling it more often than
+    /// that without reserving again is undefined behavior. Checking the load
+    /// factor is therefore left to the caller, which makes this suitable for
+    /// bulk insertion after a single reservation.
     ///
     /// This does not check if the given element already exists in the table.
+    ///
+    /// [`reserve`]: RawTable::reserve
     #[cfg_attr(feature = "inline-more", inline)]
-    #[cfg(any(feature = "raw", feature = "rustc-internal-api"))]
+    #[cfg(any(feature = "raw", feature = "rustc-internal-api", feature = "rayon"))]
     pub unsafe fn insert_no_grow(&mut self, hash: u64, value: T) -> Bucket<T> {
         let (index, old_ctrl) = self.table.prepare_insert_slot(hash);
         let bucket = self.table.bucket(index);
 
+        debug_assert!(
+            self.table.growth_left > 0 || !special_is_empty(old_ctrl),
+            "insert_no_grow called on a table without spare capacity"
+        );
+
         // If we are replacing a DELETED entry then we don't need to update
         // the load counter.
         self.table.growth_left -= special_is_empty(old_ctrl) as usize;
@@ -810,6 +1160,50 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
+    /// Searches for an element in the table. If the element is not found,
+    /// returns `Err` with the position of a slot where an element with the
+    /// same hash could be inserted.
+    ///
+    /// This function may resize the table if additional space is required for
+    /// inserting an element, so that the returned slot can always be used.
+    #[inline]
+    pub fn find_or_find_insert_slot(
+        &mut self,
+        hash: u64,
+        mut eq: impl FnMut(&T) -> bool,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<Bucket<T>, InsertSlot> {
+        self.reserve(1, hasher);
+
+        match self
+            .table
+            .find_or_find_insert_slot_inner(hash, &mut |index| unsafe {
+                eq(self.bucket(index).as_ref())
+            }) {
+            Ok(index) => Ok(unsafe { self.bucket(index) }),
+            Err(index) => Err(InsertSlot { index }),
+        }
+    }
+
+    /// Inserts a new element into the table in the given slot, and returns its
+    /// raw bucket.
+    ///
+    /// # Safety
+    ///
+    /// `slot` must have been returned by a call to
+    /// [`find_or_find_insert_slot`](RawTable::find_or_find_insert_slot) on
+    /// this table with the same `hash`, and the table must not have been
+    /// mutated since.
+    #[inline]
+    pub unsafe fn insert_in_slot(&mut self, hash: u64, slot: InsertSlot, value: T) -> Bucket<T> {
+        let old_ctrl = *self.table.ctrl(slot.index);
+        self.table.record_item_insert_at(slot.index, old_ctrl, hash);
+
+        let bucket = self.bucket(slot.index);
+        bucket.write(value);
+        bucket
+    }
+
     /// Searches for an element in the table.
     #[inline]
     pub fn find(&self, hash: u64, mut eq: impl FnMut(&T) -> bool) -> Option<Bucket<T>> {
@@ -824,6 +1218,56 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
+    /// Hints to the CPU that a lookup for `hash` is about to happen.
+    ///
+    /// This prefetches the first group of control bytes in the probe sequence
+    /// for `hash`, along with the bucket at the start of that group, so that a
+    /// following `find` for the same hash is less likely to stall on memory.
+    /// Issuing prefetches for upcoming lookups while processing the current
+    /// one can hide memory latency in batches of lookups on large tables.
+    ///
+    /// This is only a performance hint: it never changes the table and is a
+    /// no-op on targets without a prefetch instruction.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn prefetch(&self, hash: u64) {
+        let pos = h1(hash) & self.table.bucket_mask;
+        unsafe {
+            prefetch_read(self.table.ctrl(pos));
+            if !self.table.is_empty_singleton() {
+                prefetch_read(self.bucket(pos).as_ptr());
+            }
+        }
+    }
+
+    /// Searches for a batch of elements in the table.
+    ///
+    /// This is equivalent to calling [`find`] for each hash in `hashes`,
+    /// except that the first group of every probe sequence is prefetched
+    /// before any of the lookups are performed, so the memory accesses for the
+    /// whole batch can overlap. `eq` is called with the index of the hash in
+    /// `hashes` along with the candidate element.
+    ///
+    /// [`find`]: RawTable::find
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn find_batch<const N: usize>(
+        &self,
+        hashes: [u64; N],
+        mut eq: impl FnMut(usize, &T) -> bool,
+    ) -> [Option<Bucket<T>>; N] {
+        for &hash in &hashes {
+            self.prefetch(hash);
+        }
+
+        let mut i = 0;
+        hashes.map(|hash| {
+            let index = i;
+            i += 1;
+            self.find(hash, |x| eq(index, x))
+        })
+    }
+
     /// Gets a reference to an element in the table.
     #[inline]
     pub fn get(&self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<&T> {
@@ -858,28 +1302,99 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         hashes: [u64; N],
         eq: impl FnMut(usize, &T) -> bool,
     ) -> Option<[&'_ mut T; N]> {
+        self.try_get_many_mut(hashes, eq).ok()
+    }
+
+    /// Attempts to get mutable references to `N` entries in the table at once,
+    /// reporting which query failed if that is not possible.
+    ///
+    /// This behaves like [`get_many_mut`](Self::get_many_mut), but returns
+    /// [`GetManyMutError::MissingKey`] with the index of the first query that
+    /// was not found, or [`GetManyMutError::DuplicateKey`] with the indices of
+    /// the first two queries that resolved to the same entry.
+    pub fn try_get_many_mut<const N: usize>(
+        &mut self,
+        hashes: [u64; N],
+        eq: impl FnMut(usize, &T) -> bool,
+    ) -> Result<[&'_ mut T; N], GetManyMutError> {
         unsafe {
             let ptrs = self.get_many_mut_pointers(hashes, eq)?;
 
             for (i, &cur) in ptrs.iter().enumerate() {
-                if ptrs[..i].iter().any(|&prev| ptr::eq::<T>(prev, cur)) {
-                    return None;
+                if let Some(prev) = ptrs[..i].iter().position(|&prev| ptr::eq::<T>(prev, cur)) {
+                    return Err(GetManyMutError::DuplicateKey(prev, i));
                 }
             }
             // All bucket are distinct from all previous buckets so we're clear to return the result
             // of the lookup.
 
             // TODO use `MaybeUninit::array_assume_init` here instead once that's stable.
-            Some(mem::transmute_copy(&ptrs))
+            Ok(mem::transmute_copy(&ptrs))
+        }
+    }
+
+    /// Attempts to get mutable references to a runtime number of entries in
+    /// the table at once.
+    ///
+    /// This behaves like [`get_many_mut`](Self::get_many_mut), but takes a
+    /// slice of hashes and returns the references in a `Vec`. `None` is
+    /// returned if any of the hashes is not found or if two of the queries
+    /// resolve to the same entry.
+    pub fn get_disjoint_mut(
+        &mut self,
+        hashes: &[u64],
+        mut eq: impl FnMut(usize, &T) -> bool,
+    ) -> Option<Vec<&'_ mut T>> {
+        let mut indices = Vec::with_capacity(hashes.len());
+        for (i, &hash) in hashes.iter().enumerate() {
+            let bucket = self.find(hash, |k| eq(i, k))?;
+            indices.push(unsafe { self.bucket_index(&bucket) });
         }
+
+        // Comparing every pair is cheaper than sorting a copy for the short
+        // lists that this is usually called with.
+        if indices.len() <= 16 {
+            for (i, index) in indices.iter().enumerate() {
+                if indices[..i].contains(index) {
+                    return None;
+                }
+            }
+        } else {
+            let mut sorted = indices.clone();
+            sorted.sort_unstable();
+            if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+                return None;
+            }
+        }
+
+        // All buckets are distinct so none of the references alias.
+        Some(
+            indices
+                .into_iter()
+                .map(|index| unsafe { self.bucket(index).as_mut() })
+                .collect(),
+        )
     }
 
+    /// Attempts to get mutable references to `N` entries in the table at once, without validating
+    /// that the entries are distinct.
+    ///
+    /// Returns an array of length `N` with the results of each query. `None` will be returned if
+    /// any of the hashes are not found.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the `N` queries resolve to pairwise distinct buckets. Returning
+    /// two mutable references to the same entry is *[undefined behavior]* even if the resulting
+    /// references are not used.
+    ///
+    /// [undefined behavior]: https://doc.rust-lang.org/reference/behavior-considered-undefined.html
     pub unsafe fn get_many_unchecked_mut<const N: usize>(
         &mut self,
         hashes: [u64; N],
         eq: impl FnMut(usize, &T) -> bool,
     ) -> Option<[&'_ mut T; N]> {
-        let ptrs = self.get_many_mut_pointers(hashes, eq)?;
+        let ptrs = self.get_many_mut_pointers(hashes, eq).ok()?;
         Some(mem::transmute_copy(&ptrs))
     }
 
@@ -887,18 +1402,21 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         &mut self,
         hashes: [u64; N],
         mut eq: impl FnMut(usize, &T) -> bool,
-    ) -> Option<[*mut T; N]> {
+    ) -> Result<[*mut T; N], GetManyMutError> {
         // TODO use `MaybeUninit::uninit_array` here instead once that's stable.
         let mut outs: MaybeUninit<[*mut T; N]> = MaybeUninit::uninit();
         let outs_ptr = outs.as_mut_ptr();
 
         for (i, &hash) in hashes.iter().enumerate() {
-            let cur = self.find(hash, |k| eq(i, k))?;
+            let cur = match self.find(hash, |k| eq(i, k)) {
+                Some(cur) => cur,
+                None => return Err(GetManyMutError::MissingKey(i)),
+            };
             *(*outs_ptr).get_unchecked_mut(i) = cur.as_mut();
         }
 
         // TODO use `MaybeUninit::array_assume_init` here instead once that's stable.
-        Some(outs.assume_init())
+        Ok(outs.assume_init())
     }
 
     /// Returns the number of elements the map can hold without reallocating.
@@ -910,6 +1428,35 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         self.table.items + self.table.growth_left
     }
 
+    /// Returns a lower bound on the number of elements that can be inserted
+    /// before the table needs to grow or rehash.
+    ///
+    /// Buckets holding tombstones left by removed elements do not count
+    /// towards this, so it may be less than the number of free buckets. An
+    /// insertion which reuses a tombstone does not decrease it, so more than
+    /// this many insertions may fit.
+    #[inline]
+    pub fn growth_left(&self) -> usize {
+        self.table.growth_left
+    }
+
+    /// Returns the capacity that a table created by `with_capacity(capacity)`
+    /// would have, without allocating it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of buckets needed for `capacity` overflows `usize`.
+    #[inline]
+    pub fn capacity_for(capacity: usize) -> usize {
+        if capacity == 0 {
+            return 0;
+        }
+        match capacity_to_buckets(capacity) {
+            Some(buckets) => bucket_mask_to_capacity(buckets - 1),
+            None => panic!("Hash table capacity overflow"),
+        }
+    }
+
     /// Returns the number of elements in the table.
     #[inline]
     pub fn len(&self) -> usize {
@@ -928,6 +1475,25 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         self.table.bucket_mask + 1
     }
 
+    /// Returns the size in bytes of the table's backing allocation, including
+    /// the control bytes and any alignment padding.
+    ///
+    /// This is exactly the size of the layout which was requested from the
+    /// allocator, or 0 if the table has not allocated any memory.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn allocation_size(&self) -> usize {
+        if self.table.is_empty_singleton() {
+            0
+        } else {
+            // Avoid `Option::unwrap_or_else` because it bloats LLVM IR.
+            match calculate_layout::<T>(self.buckets()) {
+                Some((layout, _)) => layout.size(),
+                None => unsafe { hint::unreachable_unchecked() },
+            }
+        }
+    }
+
     /// Returns an iterator over every element in the table. It is up to
     /// the caller to ensure that the `RawTable` outlives the `RawIter`.
     /// Because we cannot make the `next` method unsafe on the `RawIter`
@@ -941,33 +1507,148 @@ impl<T, A: Allocator + Clone> RawTable<T, A> {
         }
     }
 
-    /// Returns an iterator over occupied buckets that could match a given hash.
+    /// Folds every element in the table into an accumulator, in the same
+    /// order as [`iter`](Self::iter).
     ///
-    /// `RawTable` only stores 7 bits of the hash value, so this iterator may
-    /// return items that have a hash value different than the one provided. You
-    /// should always validate the returned values before using them.
-    ///
-    /// It is up to the caller to ensure that the `RawTable` outlives the
-    /// `RawIterHash`. Because we cannot make the `next` method unsafe on the
-    /// `RawIterHash` struct, we have to make the `iter_hash` method unsafe.
-    #[cfg_attr(feature = "inline-more", inline)]
+    /// This walks the control bytes one group at a time and visits all full
+    /// buckets of a group before loading the next one, which avoids the
+    /// per-element bookkeeping of `RawIter` and is faster for tight
+    /// aggregation loops.
     #[cfg(feature = "raw")]
-    pub unsafe fn iter_hash(&self, hash: u64) -> RawIterHash<'_, T, A> {
-        RawIterHash::new(self, hash)
-    }
-
-    /// Returns an iterator which removes all elements from the table without
-    /// freeing the memory.
-    #[cfg_attr(feature = "inline-more", inline)]
-    pub fn drain(&mut self) -> RawDrain<'_, T, A> {
-        unsafe {
-            let iter = self.iter();
-            self.drain_iter_from(iter)
+    #[inline]
+    pub fn fold<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, &T) -> B,
+    {
+        match self.try_fold(init, |acc, x| Ok::<B, Infallible>(f(acc, x))) {
+            Ok(acc) => acc,
+            Err(never) => match never {},
         }
     }
 
-    /// Returns an iterator which removes all elements from the table without
-    /// freeing the memory.
+    /// Like [`fold`](Self::fold), but stops at the first element for which
+    /// `f` returns an error and returns that error.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn try_fold<B, E, F>(&self, init: B, mut f: F) -> Result<B, E>
+    where
+        F: FnMut(B, &T) -> Result<B, E>,
+    {
+        let mut acc = init;
+        let mut remaining = self.table.items;
+        let mut group_start = 0;
+        unsafe {
+            // Every full bucket is visited exactly once, so we can stop as soon
+            // as all items have been seen instead of scanning the whole table.
+            // The trailing bytes of a table smaller than a group are always
+            // EMPTY, so they never match.
+            while remaining != 0 {
+                let full = Group::load_aligned(self.table.ctrl(group_start)).match_full();
+                for bit in full {
+                    acc = f(acc, self.bucket(group_start + bit).as_ref())?;
+                    remaining -= 1;
+                }
+                group_start += Group::WIDTH;
+            }
+        }
+        Ok(acc)
+    }
+
+    /// Returns an iterator over the element in `bucket` and every element
+    /// after it, in the same order as [`iter`](Self::iter).
+    ///
+    /// This can be used to resume an earlier iteration: passing the next
+    /// bucket that a `RawIter` would have yielded makes the new iterator visit
+    /// exactly the elements which that iterator had not reached yet. Creating
+    /// the iterator takes time proportional to the number of those elements
+    /// since they must be counted.
+    ///
+    /// # Safety
+    ///
+    /// `bucket` must belong to this table, and the table must not have been
+    /// resized or rehashed since `bucket` was obtained. As with `iter`, it is
+    /// up to the caller to ensure that the `RawTable` outlives the `RawIter`.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[cfg(feature = "raw")]
+    pub unsafe fn iter_from(&self, bucket: Bucket<T>) -> RawIter<T> {
+        let index = self.bucket_index(&bucket);
+        debug_assert!(index < self.buckets());
+
+        // Start from the group containing `bucket` and skip the buckets in
+        // that group which come before it.
+        let start = index & !(Group::WIDTH - 1);
+        let mut iter = RawIterRange::new(
+            self.table.ctrl(start),
+            self.bucket(start),
+            self.buckets() - start,
+        );
+        while let Some(bit) = iter.current_group.lowest_set_bit() {
+            if start + bit >= index {
+                break;
+            }
+            iter.current_group = iter.current_group.remove_lowest_bit();
+        }
+
+        RawIter {
+            items: iter.clone().count(),
+            iter,
+        }
+    }
+
+    /// Returns an iterator over occupied buckets that could match a given hash.
+    ///
+    /// `RawTable` only stores 7 bits of the hash value, so this iterator may
+    /// return items that have a hash value different than the one provided. You
+    /// should always validate the returned values before using them.
+    ///
+    /// It is up to the caller to ensure that the `RawTable` outlives the
+    /// `RawIterHash`. Because we cannot make the `next` method unsafe on the
+    /// `RawIterHash` struct, we have to make the `iter_hash` method unsafe.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[cfg(feature = "raw")]
+    pub unsafe fn iter_hash(&self, hash: u64) -> RawIterHash<'_, T, A> {
+        RawIterHash::new(self, hash)
+    }
+
+    /// Returns an iterator over occupied buckets that could match any of the
+    /// given hashes, scanning each group of the probe sequence only once.
+    ///
+    /// All of the hashes must select the same probe sequence, which is the
+    /// case for hashes that only differ in their top 7 bits. This allows a
+    /// caller that stores several related entries under one probe sequence
+    /// to look them all up at once.
+    ///
+    /// As with [`iter_hash`](Self::iter_hash), the returned items may have a
+    /// hash value different than all of the ones provided, so they must
+    /// always be validated before being used.
+    ///
+    /// It is up to the caller to ensure that the `RawTable` outlives the
+    /// `RawIterHashAny`.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[cfg(feature = "raw")]
+    pub unsafe fn iter_hash_any<const N: usize>(
+        &self,
+        hashes: [u64; N],
+    ) -> RawIterHashAny<'_, T, A, N> {
+        RawIterHashAny::new(self, hashes)
+    }
+
+    /// Returns an iterator which removes all elements from the table without
+    /// freeing the memory.
+    ///
+    /// Elements are yielded by value. If the iterator is dropped before it is
+    /// exhausted, the remaining elements are dropped. Either way the table is
+    /// left empty, keeping its buckets for reuse.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn drain(&mut self) -> RawDrain<'_, T, A> {
+        unsafe {
+            let iter = self.iter();
+            self.drain_iter_from(iter)
+        }
+    }
+
+    /// Returns an iterator which removes all elements from the table without
+    /// freeing the memory.
     ///
     /// Iteration starts at the provided iterator's current location.
     ///
@@ -1114,6 +1795,32 @@ impl<A: Allocator + Clone> RawTableInner<A> {
         }
     }
 
+    #[inline]
+    fn fallible_with_exact_capacity(
+        alloc: A,
+        table_layout: TableLayout,
+        capacity: usize,
+        fallibility: Fallibility,
+    ) -> Result<Self, TryReserveError> {
+        if capacity == 0 {
+            Ok(Self::new_in(alloc))
+        } else {
+            unsafe {
+                let buckets = capacity_to_exact_buckets(capacity)
+                    .ok_or_else(|| fallibility.capacity_overflow())?;
+
+                let mut result =
+                    Self::new_uninitialized(alloc, table_layout, buckets, fallibility)?;
+                result.ctrl(0).write_bytes(EMPTY, result.num_ctrl_bytes());
+
+                // A single empty bucket is enough for lookups to terminate.
+                result.growth_left = result.bucket_mask;
+
+                Ok(result)
+            }
+        }
+    }
+
     /// Searches for an empty or deleted bucket which is suitable for inserting
     /// a new element and sets the hash for that slot.
     ///
@@ -1126,6 +1833,29 @@ impl<A: Allocator + Clone> RawTableInner<A> {
         (index, old_ctrl)
     }
 
+    /// Fixes up an insertion slot returned by a group scan, which may point
+    /// at a full bucket in tables smaller than the group width.
+    #[inline]
+    unsafe fn fix_insert_slot(&self, index: usize) -> usize {
+        // In tables smaller than the group width, trailing control
+        // bytes outside the range of the table are filled with
+        // EMPTY entries. These will unfortunately trigger a
+        // match, but once masked may point to a full bucket that
+        // is already occupied. We detect this situation here and
+        // perform a second scan starting at the beginning of the
+        // table. This second scan is guaranteed to find an empty
+        // slot (due to the load factor) before hitting the trailing
+        // control bytes (containing EMPTY).
+        if unlikely(is_full(*self.ctrl(index))) {
+            debug_assert!(self.bucket_mask < Group::WIDTH);
+            Group::load_aligned(self.ctrl(0))
+                .match_empty_or_deleted()
+                .lowest_set_bit_nonzero()
+        } else {
+            index
+        }
+    }
+
     /// Searches for an empty or deleted bucket which is suitable for inserting
     /// a new element.
     ///
@@ -1138,27 +1868,59 @@ impl<A: Allocator + Clone> RawTableInner<A> {
                 let group = Group::load(self.ctrl(probe_seq.pos));
                 if let Some(bit) = group.match_empty_or_deleted().lowest_set_bit() {
                     let result = (probe_seq.pos + bit) & self.bucket_mask;
+                    return self.fix_insert_slot(result);
+                }
+            }
+            probe_seq.move_next(self.bucket_mask);
+        }
+    }
 
-                    // In tables smaller than the group width, trailing control
-                    // bytes outside the range of the table are filled with
-                    // EMPTY entries. These will unfortunately trigger a
-                    // match, but once masked may point to a full bucket that
-                    // is already occupied. We detect this situation here and
-                    // perform a second scan starting at the beginning of the
-                    // table. This second scan is guaranteed to find an empty
-                    // slot (due to the load factor) before hitting the trailing
-                    // control bytes (containing EMPTY).
-                    if unlikely(is_full(*self.ctrl(result))) {
-                        debug_assert!(self.bucket_mask < Group::WIDTH);
-                        debug_assert_ne!(probe_seq.pos, 0);
-                        return Group::load_aligned(self.ctrl(0))
-                            .match_empty_or_deleted()
-                            .lowest_set_bit_nonzero();
-                    }
+    /// Searches for an element in the table, or a potential slot where that
+    /// element could be inserted, in a single probe sequence.
+    ///
+    /// There must be at least 1 empty bucket in the table.
+    #[inline]
+    fn find_or_find_insert_slot_inner(
+        &self,
+        hash: u64,
+        eq: &mut dyn FnMut(usize) -> bool,
+    ) -> Result<usize, usize> {
+        let mut insert_slot = None;
+
+        let h2_hash = h2(hash);
+        let mut probe_seq = self.probe_seq(hash);
+
+        loop {
+            let group = unsafe { Group::load(self.ctrl(probe_seq.pos)) };
+
+            for bit in group.match_byte(h2_hash) {
+                let index = (probe_seq.pos + bit) & self.bucket_mask;
+
+                if likely(eq(index)) {
+                    return Ok(index);
+                }
+            }
+
+            // We didn't find the element we were looking for in the group, try
+            // to get an insertion slot from the group if we don't have one yet.
+            if likely(insert_slot.is_none()) {
+                if let Some(bit) = group.match_empty_or_deleted().lowest_set_bit() {
+                    insert_slot = Some((probe_seq.pos + bit) & self.bucket_mask);
+                }
+            }
 
-                    return result;
+            // Only stop the search if the group contains at least one empty
+            // element. Otherwise, the element that we are looking for might be
+            // in a following group.
+            if likely(group.match_empty().any_bit_set()) {
+                // A group containing an empty element always yields an
+                // insertion slot, so `insert_slot` has been set by now.
+                match insert_slot {
+                    Some(index) => return Err(unsafe { self.fix_insert_slot(index) }),
+                    None => unsafe { hint::unreachable_unchecked() },
                 }
             }
+
             probe_seq.move_next(self.bucket_mask);
         }
     }
@@ -1374,6 +2136,9 @@ impl<A: Allocator + Clone> RawTableInner<A> {
 
     /// Reserves or rehashes to make room for `additional` more elements.
     ///
+    /// Unless `exact` is set, growing the table at least doubles its number of
+    /// buckets.
+    ///
     /// This uses dynamic dispatch to reduce the amount of
     /// code generated, but it is eliminated by LLVM optimizations when inlined.
     #[allow(clippy::inline_always)]
@@ -1383,6 +2148,7 @@ impl<A: Allocator + Clone> RawTableInner<A> {
         additional: usize,
         hasher: &dyn Fn(&mut Self, usize) -> u64,
         fallibility: Fallibility,
+        exact: bool,
         layout: TableLayout,
         drop: Option<fn(*mut u8)>,
     ) -> Result<(), TryReserveError> {
@@ -1397,6 +2163,8 @@ impl<A: Allocator + Clone> RawTableInner<A> {
             // capacity that is locked up due to DELETED entries.
             self.rehash_in_place(hasher, layout.size, drop);
             Ok(())
+        } else if exact {
+            self.resize_inner(new_items, hasher, fallibility, layout)
         } else {
             // Otherwise, conservatively resize to at least the next size up
             // to avoid churning deletes into frequent rehashes.
@@ -1425,14 +2193,19 @@ impl<A: Allocator + Clone> RawTableInner<A> {
     ) -> Result<(), TryReserveError> {
         let mut new_table = self.prepare_resize(layout, capacity, fallibility)?;
 
-        // Copy all elements to the new table.
-        for i in 0..self.buckets() {
-            if !is_full(*self.ctrl(i)) {
-                continue;
-            }
-
+        let move_bucket = |old_table: &mut Self, i: usize| {
             // This may panic.
-            let hash = hasher(self, i);
+            let hash = hasher(old_table, i);
+
+            // The control byte still holds the top bits of the hash the
+            // element was inserted with. A mismatch means that the hash of
+            // the element changed while it was stored in the table, e.g.
+            // through interior mutability, so it can no longer be found.
+            debug_assert_eq!(
+                *old_table.ctrl(i),
+                h2(hash),
+                "the hash of an element changed while it was stored in the table"
+            );
 
             // We can use a simpler version of insert() here since:
             // - there are no DELETED entries.
@@ -1441,10 +2214,30 @@ impl<A: Allocator + Clone> RawTableInner<A> {
             let (index, _) = new_table.prepare_insert_slot(hash);
 
             ptr::copy_nonoverlapping(
-                self.bucket_ptr(i, layout.size),
+                old_table.bucket_ptr(i, layout.size),
                 new_table.bucket_ptr(index, layout.size),
                 layout.size,
             );
+        };
+
+        // Copy all elements to the new table. With SIMD groups the old
+        // control bytes are scanned a group at a time so that runs of empty
+        // buckets are skipped with a single match instead of one branch per
+        // bucket. The trailing bytes of a table smaller than a group are
+        // always EMPTY, so they never match.
+        if Group::IS_SIMD {
+            for group_start in (0..self.buckets()).step_by(Group::WIDTH) {
+                let full = Group::load_aligned(self.ctrl(group_start)).match_full();
+                for bit in full {
+                    move_bucket(self, group_start + bit);
+                }
+            }
+        } else {
+            for i in 0..self.buckets() {
+                if is_full(*self.ctrl(i)) {
+                    move_bucket(self, i);
+                }
+            }
         }
 
         // We successfully copied all elements without panicking. Now replace
@@ -1849,8 +2642,14 @@ pub(crate) struct RawIterRange<T> {
     // Must be aligned to the group size.
     next_ctrl: *const u8,
 
-    // Pointer one past the last control byte of this range.
+    // Pointer one past the last control byte of this range that has not been
+    // loaded yet. Iterating from the back moves this down one group at a time.
     end: *const u8,
+
+    // Mask of full buckets in the group most recently loaded from the back,
+    // which starts at `end`. Bits are cleared from this mask as each element
+    // is processed. Empty until `next_back` loads a group.
+    back_group: BitMask,
 }
 
 impl<T> RawIterRange<T> {
@@ -1872,6 +2671,7 @@ impl<T> RawIterRange<T> {
             data,
             next_ctrl,
             end,
+            back_group: BitMask(0),
         }
     }
 
@@ -1902,7 +2702,7 @@ impl<T> RawIterRange<T> {
                 //   current group to process.
                 let mid = (len / 2) & !(Group::WIDTH - 1);
 
-                let tail = Self::new(
+                let mut tail = Self::new(
                     self.next_ctrl.add(mid),
                     self.data.next_n(Group::WIDTH).next_n(mid),
                     len - mid,
@@ -1912,6 +2712,7 @@ impl<T> RawIterRange<T> {
                     tail.data.ptr
                 );
                 debug_assert_eq!(self.end, tail.end);
+                tail.back_group = mem::replace(&mut self.back_group, BitMask(0));
                 self.end = self.next_ctrl.add(mid);
                 debug_assert_eq!(self.end.add(Group::WIDTH), tail.next_ctrl);
                 (self, Some(tail))
@@ -1919,6 +2720,64 @@ impl<T> RawIterRange<T> {
         }
     }
 
+    /// Drops the buckets of `current_group` that were already yielded from
+    /// the back.
+    ///
+    /// `RawIter` iterates without checking the range (see `next_impl`), so its
+    /// front can load the group that `next_back` is processing. That group is
+    /// then shared by `current_group` and `back_group`, with the bits above
+    /// the highest one left in `back_group` already yielded. This makes the
+    /// range safe to iterate with `DO_CHECK_PTR_RANGE` set again.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[cfg(feature = "rayon")]
+    pub(crate) fn settle_back_group(&mut self) {
+        if self.next_ctrl > self.end {
+            if let Some(bit) = self.back_group.highest_set_bit() {
+                self.current_group = self.current_group.remove_bits_from(bit + 1);
+                self.back_group = BitMask(0);
+            }
+        }
+    }
+
+    /// Converts the remaining part of this range into a `RawIterRangeIndexed`.
+    ///
+    /// `items` must be the exact number of elements that this range has left
+    /// to yield.
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[cfg(feature = "rayon")]
+    pub(crate) unsafe fn into_indexed(mut self, items: usize) -> RawIterRangeIndexed<T> {
+        self.settle_back_group();
+
+        // `data` points to the buckets of the group that we are currently
+        // processing while `next_ctrl` points to the control bytes of the
+        // group after it.
+        let ctrl = self.next_ctrl.sub(Group::WIDTH);
+
+        // The buckets above the highest set bit of the group loaded from the
+        // back have been yielded by `next_back`. If there is no such group but
+        // nothing is left after the current one, `next_back` may have consumed
+        // the current group from the top instead.
+        let end = if let Some(bit) = self.back_group.highest_set_bit() {
+            offset_from(self.end, ctrl) + bit + 1
+        } else if self.next_ctrl >= self.end {
+            match self.current_group.highest_set_bit() {
+                Some(bit) => bit + 1,
+                None => 0,
+            }
+        } else {
+            offset_from(self.end, ctrl)
+        };
+
+        RawIterRangeIndexed {
+            data: self.data,
+            ctrl,
+            current_group: self.current_group,
+            next_group: Group::WIDTH,
+            end,
+            items,
+        }
+    }
+
     /// # Safety
     /// If DO_CHECK_PTR_RANGE is false, caller must ensure that we never try to iterate
     /// after yielding all elements.
@@ -1931,7 +2790,17 @@ impl<T> RawIterRange<T> {
             }
 
             if DO_CHECK_PTR_RANGE && self.next_ctrl >= self.end {
-                return None;
+                if !self.back_group.any_bit_set() {
+                    return None;
+                }
+
+                // All remaining groups have been loaded from the back, so
+                // take over the group that `next_back` is processing rather
+                // than loading it again.
+                self.current_group = mem::replace(&mut self.back_group, BitMask(0));
+                self.data = self.back_data();
+                self.next_ctrl = self.end.add(Group::WIDTH);
+                continue;
             }
 
             // We might read past self.end up to the next group boundary,
@@ -1939,11 +2808,64 @@ impl<T> RawIterRange<T> {
             // than the group size where the trailing control bytes are all
             // EMPTY. On larger tables self.end is guaranteed to be aligned
             // to the group size (since tables are power-of-two sized).
+            //
+            // Without the range check we may also load the group that
+            // `next_back` is processing. Both ends then yield from the same
+            // group, the front from the lowest bit and the back from the
+            // highest, and the caller's item count stops them before they
+            // meet.
             self.current_group = Group::load_aligned(self.next_ctrl).match_full();
             self.data = self.data.next_n(Group::WIDTH);
             self.next_ctrl = self.next_ctrl.add(Group::WIDTH);
         }
     }
+
+    /// Counterpart of `next_impl` which yields buckets from the end of the
+    /// range.
+    ///
+    /// # Safety
+    /// If DO_CHECK_PTR_RANGE is false, caller must ensure that we never try to iterate
+    /// after yielding all elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    unsafe fn next_back_impl<const DO_CHECK_PTR_RANGE: bool>(&mut self) -> Option<Bucket<T>> {
+        loop {
+            if let Some(index) = self.back_group.highest_set_bit() {
+                self.back_group = self.back_group.remove_highest_bit();
+                return Some(self.back_data().next_n(index));
+            }
+
+            if self.next_ctrl >= self.end {
+                // Every group after the one being processed from the front
+                // has been visited, so continue from the top of that group.
+                // The two ends share its mask, which ensures that no bucket
+                // is yielded twice.
+                return match self.current_group.highest_set_bit() {
+                    Some(index) => {
+                        self.current_group = self.current_group.remove_highest_bit();
+                        Some(self.data.next_n(index))
+                    }
+                    None if DO_CHECK_PTR_RANGE => None,
+                    None => hint::unreachable_unchecked(),
+                };
+            }
+
+            // `end` is aligned to the group size here since there is at least
+            // one full group between it and `next_ctrl`.
+            self.end = self.end.sub(Group::WIDTH);
+            self.back_group = Group::load_aligned(self.end).match_full();
+        }
+    }
+
+    /// Returns the buckets for the group in `back_group`, which starts at
+    /// `end`.
+    ///
+    /// This is derived from `data` rather than stored, to keep the iterators
+    /// of all maps small.
+    #[inline]
+    unsafe fn back_data(&self) -> Bucket<T> {
+        self.data
+            .next_n(offset_from(self.end, self.next_ctrl.sub(Group::WIDTH)))
+    }
 }
 
 // We make raw iterators unconditionally Send and Sync, and let the PhantomData
@@ -1959,6 +2881,7 @@ impl<T> Clone for RawIterRange<T> {
             next_ctrl: self.next_ctrl,
             current_group: self.current_group,
             end: self.end,
+            back_group: self.back_group,
         }
     }
 }
@@ -1983,13 +2906,187 @@ impl<T> Iterator for RawIterRange<T> {
             0
         };
 
-        // Add a group width to include the group we are currently processing.
-        (0, Some(Group::WIDTH + remaining_buckets))
+        // Add a group width to include the group we are currently processing,
+        // and another for the group being processed from the back.
+        (0, Some(2 * Group::WIDTH + remaining_buckets))
+    }
+}
+
+impl<T> DoubleEndedIterator for RawIterRange<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<Bucket<T>> {
+        unsafe {
+            // SAFETY: We set checker flag to true.
+            self.next_back_impl::<true>()
+        }
     }
 }
 
 impl<T> FusedIterator for RawIterRange<T> {}
 
+/// Iterator over a sub-range of a table which knows exactly how many elements
+/// it has left. Unlike `RawIterRange` this iterator can be consumed from both
+/// ends and split at an arbitrary element, which is what rayon needs for
+/// indexed parallel iterators.
+///
+/// Like `RawIterRange`, this scans the control bytes a group at a time. The
+/// two ends may both look at the same group, with the front yielding from its
+/// lowest bit and the back from its highest. They never yield the same bucket
+/// since `items` stops them before they meet.
+#[cfg(feature = "rayon")]
+pub(crate) struct RawIterRangeIndexed<T> {
+    // Pointer to the buckets for the first group of the range.
+    data: Bucket<T>,
+
+    // Pointer to the control bytes for the first group of the range. Must be
+    // aligned to the group size.
+    ctrl: *const u8,
+
+    // Mask of full buckets in the group before `next_group` that have not
+    // been yielded from the front. Bits are cleared from this mask as each
+    // element is processed.
+    current_group: BitMask,
+
+    // Offset, relative to `data` and `ctrl`, of the next group to load from
+    // the front. This is always a multiple of the group size.
+    next_group: usize,
+
+    // Offset one past the last bucket that has not been yielded from the
+    // back.
+    end: usize,
+
+    // Number of elements left to yield.
+    items: usize,
+}
+
+#[cfg(feature = "rayon")]
+impl<T> RawIterRangeIndexed<T> {
+    /// Loads the mask of full buckets in the group at offset `group`, leaving
+    /// out those at or after `end`.
+    #[inline]
+    unsafe fn load_group(&self, group: usize) -> BitMask {
+        debug_assert!(group < self.end);
+        Group::load_aligned(self.ctrl.add(group))
+            .match_full()
+            .remove_bits_from(self.end - group)
+    }
+
+    /// Splits the range in two so that the first half yields exactly `index`
+    /// elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub(crate) fn split_at(self, index: usize) -> (Self, Self) {
+        debug_assert!(index <= self.items);
+
+        let (mid, right_group, right_next_group) = if index == self.items {
+            (self.end, BitMask(0), self.next_group)
+        } else {
+            // Skip whole groups by counting their full buckets, then find the
+            // bucket holding element `index` within the group that has it.
+            // There are more than `index` elements left, so this stops before
+            // reaching `end`.
+            let mut group = self.next_group - Group::WIDTH;
+            let mut mask = self.current_group;
+            let mut skip = index;
+            loop {
+                let count = mask.count_ones();
+                if skip < count {
+                    break;
+                }
+                skip -= count;
+                group += Group::WIDTH;
+                mask = unsafe { self.load_group(group) };
+            }
+            for _ in 0..skip {
+                mask = mask.remove_lowest_bit();
+            }
+            let bit = unsafe { mask.lowest_set_bit_nonzero() };
+            (group + bit, mask, group + Group::WIDTH)
+        };
+
+        let left = Self {
+            data: self.data.clone(),
+            ctrl: self.ctrl,
+            current_group: self.current_group,
+            next_group: self.next_group,
+            end: mid,
+            items: index,
+        };
+        let right = Self {
+            data: self.data,
+            ctrl: self.ctrl,
+            current_group: right_group,
+            next_group: right_next_group,
+            end: self.end,
+            items: self.items - index,
+        };
+        (left, right)
+    }
+}
+
+// We make raw iterators unconditionally Send and Sync, and let the PhantomData
+// in the actual iterator implementations determine the real Send/Sync bounds.
+#[cfg(feature = "rayon")]
+unsafe impl<T> Send for RawIterRangeIndexed<T> {}
+#[cfg(feature = "rayon")]
+unsafe impl<T> Sync for RawIterRangeIndexed<T> {}
+
+#[cfg(feature = "rayon")]
+impl<T> Iterator for RawIterRangeIndexed<T> {
+    type Item = Bucket<T>;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next(&mut self) -> Option<Bucket<T>> {
+        if self.items == 0 {
+            return None;
+        }
+
+        unsafe {
+            loop {
+                if let Some(bit) = self.current_group.lowest_set_bit() {
+                    self.current_group = self.current_group.remove_lowest_bit();
+                    self.items -= 1;
+                    return Some(self.data.next_n(self.next_group - Group::WIDTH + bit));
+                }
+
+                self.current_group = self.load_group(self.next_group);
+                self.next_group += Group::WIDTH;
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items, Some(self.items))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> DoubleEndedIterator for RawIterRangeIndexed<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<Bucket<T>> {
+        if self.items == 0 {
+            return None;
+        }
+
+        unsafe {
+            loop {
+                let group = (self.end - 1) & !(Group::WIDTH - 1);
+                if let Some(bit) = self.load_group(group).highest_set_bit() {
+                    self.end = group + bit;
+                    self.items -= 1;
+                    return Some(self.data.next_n(self.end));
+                }
+                self.end = group;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ExactSizeIterator for RawIterRangeIndexed<T> {}
+#[cfg(feature = "rayon")]
+impl<T> FusedIterator for RawIterRangeIndexed<T> {}
+
 /// Iterator which returns a raw pointer to every full bucket in the table.
 ///
 /// For maximum flexibility this iterator is not bound by a lifetime, but you
@@ -2002,6 +3099,8 @@ impl<T> FusedIterator for RawIterRange<T> {}
 ///   created will be yielded by that iterator (unless `reflect_insert` is called).
 /// - The order in which the iterator yields bucket is unspecified and may
 ///   change in the future.
+/// - `reflect_remove` and `reflect_insert` must not be used once the iterator
+///   has been advanced with `next_back`.
 pub struct RawIter<T> {
     pub(crate) iter: RawIterRange<T>,
     items: usize,
@@ -2172,9 +3271,37 @@ impl<T> Iterator for RawIter<T> {
     }
 }
 
+impl<T> DoubleEndedIterator for RawIter<T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next_back(&mut self) -> Option<Bucket<T>> {
+        if self.items == 0 {
+            return None;
+        }
+
+        let nxt = unsafe {
+            // SAFETY: We check number of items to yield using `items` field.
+            self.iter.next_back_impl::<false>()
+        };
+
+        if nxt.is_some() {
+            self.items -= 1;
+        }
+
+        nxt
+    }
+}
+
 impl<T> ExactSizeIterator for RawIter<T> {}
 impl<T> FusedIterator for RawIter<T> {}
 
+impl<T> fmt::Debug for RawIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawIter")
+            .field("remaining", &self.items)
+            .finish()
+    }
+}
+
 /// Iterator which consumes a table and returns elements.
 pub struct RawIntoIter<T, A: Allocator + Clone = Global> {
     iter: RawIter<T>,
@@ -2190,6 +3317,27 @@ impl<T, A: Allocator + Clone> RawIntoIter<T, A> {
     }
 }
 
+impl<T: Clone, A: Allocator + Clone> Clone for RawIntoIter<T, A> {
+    fn clone(&self) -> Self {
+        // The clone is only ever iterated over, so the remaining elements are
+        // packed into the first buckets of a new table instead of keeping
+        // their original positions. This means no hasher is needed.
+        let mut table = RawTable::with_capacity_in(self.iter.items, self.alloc.clone());
+        unsafe {
+            for (index, from) in self.iter.clone().enumerate() {
+                // If this panics then the new table drops the elements cloned
+                // so far, since `items` and the control bytes are kept in sync.
+                let item = from.as_ref().clone();
+                table.bucket(index).write(item);
+                table.table.set_ctrl_h2(index, 0);
+                table.table.items += 1;
+                table.table.growth_left -= 1;
+            }
+        }
+        table.into_iter()
+    }
+}
+
 unsafe impl<T, A: Allocator + Clone> Send for RawIntoIter<T, A>
 where
     T: Send,
@@ -2251,6 +3399,14 @@ impl<T, A: Allocator + Clone> Iterator for RawIntoIter<T, A> {
 impl<T, A: Allocator + Clone> ExactSizeIterator for RawIntoIter<T, A> {}
 impl<T, A: Allocator + Clone> FusedIterator for RawIntoIter<T, A> {}
 
+impl<T, A: Allocator + Clone> fmt::Debug for RawIntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawIntoIter")
+            .field("remaining", &self.iter.items)
+            .finish()
+    }
+}
+
 /// Iterator which consumes elements without freeing the table storage.
 pub struct RawDrain<'a, T, A: Allocator + Clone = Global> {
     iter: RawIter<T>,
@@ -2273,13 +3429,13 @@ impl<T, A: Allocator + Clone> RawDrain<'_, T, A> {
     }
 }
 
-unsafe impl<T, A: Allocator + Copy> Send for RawDrain<'_, T, A>
+unsafe impl<T, A: Allocator + Clone> Send for RawDrain<'_, T, A>
 where
     T: Send,
     A: Send,
 {
 }
-unsafe impl<T, A: Allocator + Copy> Sync for RawDrain<'_, T, A>
+unsafe impl<T, A: Allocator + Clone> Sync for RawDrain<'_, T, A>
 where
     T: Sync,
     A: Sync,
@@ -2325,21 +3481,59 @@ impl<T, A: Allocator + Clone> Iterator for RawDrain<'_, T, A> {
 impl<T, A: Allocator + Clone> ExactSizeIterator for RawDrain<'_, T, A> {}
 impl<T, A: Allocator + Clone> FusedIterator for RawDrain<'_, T, A> {}
 
+impl<T, A: Allocator + Clone> fmt::Debug for RawDrain<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawDrain")
+            .field("remaining", &self.iter.items)
+            .finish()
+    }
+}
+
 /// Iterator over occupied buckets that could match a given hash.
 ///
 /// `RawTable` only stores 7 bits of the hash value, so this iterator may return
 /// items that have a hash value different than the one provided. You should
 /// always validate the returned values before using them.
+#[cfg(feature = "raw")]
 pub struct RawIterHash<'a, T, A: Allocator + Clone = Global> {
-    inner: RawIterHashInner<'a, A>,
+    inner: RawIterHashInner<'a, A, 1>,
+    _marker: PhantomData<T>,
+}
+
+#[cfg(feature = "raw")]
+impl<T, A: Allocator + Clone> fmt::Debug for RawIterHash<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The number of remaining matches isn't known without probing.
+        f.debug_struct("RawIterHash").finish_non_exhaustive()
+    }
+}
+
+/// Iterator over occupied buckets that could match any of a set of hashes
+/// sharing a probe sequence.
+///
+/// `RawTable` only stores 7 bits of the hash value, so this iterator may return
+/// items that have a hash value different than the ones provided. You should
+/// always validate the returned values before using them.
+#[cfg(feature = "raw")]
+pub struct RawIterHashAny<'a, T, A: Allocator + Clone, const N: usize> {
+    inner: RawIterHashInner<'a, A, N>,
     _marker: PhantomData<T>,
 }
 
-struct RawIterHashInner<'a, A: Allocator + Clone> {
+#[cfg(feature = "raw")]
+impl<T, A: Allocator + Clone, const N: usize> fmt::Debug for RawIterHashAny<'_, T, A, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The number of remaining matches isn't known without probing.
+        f.debug_struct("RawIterHashAny").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "raw")]
+struct RawIterHashInner<'a, A: Allocator + Clone, const N: usize> {
     table: &'a RawTableInner<A>,
 
-    // The top 7 bits of the hash.
-    h2_hash: u8,
+    // The top 7 bits of each hash.
+    h2_hashes: [u8; N],
 
     // The sequence of groups to probe in the search.
     probe_seq: ProbeSeq,
@@ -2350,29 +3544,43 @@ struct RawIterHashInner<'a, A: Allocator + Clone> {
     bitmask: BitMaskIter,
 }
 
+#[cfg(feature = "raw")]
+impl<'a, T, A: Allocator + Clone, const N: usize> RawIterHashAny<'a, T, A, N> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn new(table: &'a RawTable<T, A>, hashes: [u64; N]) -> Self {
+        let probe_hash = hashes.first().copied().unwrap_or(0);
+        debug_assert!(hashes
+            .iter()
+            .all(|&hash| table.table.probe_seq(hash).pos == table.table.probe_seq(probe_hash).pos));
+        RawIterHashAny {
+            inner: RawIterHashInner::new(&table.table, probe_hash, hashes.map(h2)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "raw")]
 impl<'a, T, A: Allocator + Clone> RawIterHash<'a, T, A> {
     #[cfg_attr(feature = "inline-more", inline)]
-    #[cfg(feature = "raw")]
     fn new(table: &'a RawTable<T, A>, hash: u64) -> Self {
         RawIterHash {
-            inner: RawIterHashInner::new(&table.table, hash),
+            inner: RawIterHashInner::new(&table.table, hash, [h2(hash)]),
             _marker: PhantomData,
         }
     }
 }
-impl<'a, A: Allocator + Clone> RawIterHashInner<'a, A> {
+#[cfg(feature = "raw")]
+impl<'a, A: Allocator + Clone, const N: usize> RawIterHashInner<'a, A, N> {
     #[cfg_attr(feature = "inline-more", inline)]
-    #[cfg(feature = "raw")]
-    fn new(table: &'a RawTableInner<A>, hash: u64) -> Self {
+    fn new(table: &'a RawTableInner<A>, hash: u64, h2_hashes: [u8; N]) -> Self {
         unsafe {
-            let h2_hash = h2(hash);
             let probe_seq = table.probe_seq(hash);
             let group = Group::load(table.ctrl(probe_seq.pos));
-            let bitmask = group.match_byte(h2_hash).into_iter();
+            let bitmask = group.match_any_of(h2_hashes).into_iter();
 
             RawIterHashInner {
                 table,
-                h2_hash,
+                h2_hashes,
                 probe_seq,
                 group,
                 bitmask,
@@ -2381,6 +3589,7 @@ impl<'a, A: Allocator + Clone> RawIterHashInner<'a, A> {
     }
 }
 
+#[cfg(feature = "raw")]
 impl<'a, T, A: Allocator + Clone> Iterator for RawIterHash<'a, T, A> {
     type Item = Bucket<T>;
 
@@ -2394,7 +3603,28 @@ impl<'a, T, A: Allocator + Clone> Iterator for RawIterHash<'a, T, A> {
     }
 }
 
-impl<'a, A: Allocator + Clone> Iterator for RawIterHashInner<'a, A> {
+#[cfg(feature = "raw")]
+impl<T, A: Allocator + Clone> FusedIterator for RawIterHash<'_, T, A> {}
+
+#[cfg(feature = "raw")]
+impl<'a, T, A: Allocator + Clone, const N: usize> Iterator for RawIterHashAny<'a, T, A, N> {
+    type Item = Bucket<T>;
+
+    fn next(&mut self) -> Option<Bucket<T>> {
+        unsafe {
+            match self.inner.next() {
+                Some(index) => Some(self.inner.table.bucket(index)),
+                None => None,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "raw")]
+impl<T, A: Allocator + Clone, const N: usize> FusedIterator for RawIterHashAny<'_, T, A, N> {}
+
+#[cfg(feature = "raw")]
+impl<'a, A: Allocator + Clone, const N: usize> Iterator for RawIterHashInner<'a, A, N> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -2409,7 +3639,7 @@ impl<'a, A: Allocator + Clone> Iterator for RawIterHashInner<'a, A> {
                 }
                 self.probe_seq.move_next(self.table.bucket_mask);
                 self.group = Group::load(self.table.ctrl(self.probe_seq.pos));
-                self.bitmask = self.group.match_byte(self.h2_hash).into_iter();
+                self.bitmask = self.group.match_any_of(self.h2_hashes).into_iter();
             }
         }
     }
@@ -2418,20 +3648,7 @@ impl<'a, A: Allocator + Clone> Iterator for RawIterHashInner<'a, A> {
 #[cfg(test)]
 mod test_map {
     use super::*;
-
-    fn rehash_in_place<T>(table: &mut RawTable<T>, hasher: impl Fn(&T) -> u64) {
-        unsafe {
-            table.table.rehash_in_place(
-                &|table, index| hasher(table.bucket::<T>(index).as_ref()),
-                mem::size_of::<T>(),
-                if mem::needs_drop::<T>() {
-                    Some(mem::transmute(ptr::drop_in_place::<T> as unsafe fn(*mut T)))
-                } else {
-                    None
-                },
-            );
-        }
-    }
+    use crate::alloc::vec::Vec;
 
     #[test]
     fn rehash() {
@@ -2448,7 +3665,7 @@ mod test_map {
             assert!(table.find(i + 100, |x| *x == i + 100).is_none());
         }
 
-        rehash_in_place(&mut table, hasher);
+        table.rehash_in_place(hasher);
 
         for i in 0..100 {
             unsafe {
@@ -2457,4 +3674,1006 @@ mod test_map {
             assert!(table.find(i + 100, |x| *x == i + 100).is_none());
         }
     }
+
+    #[test]
+    fn rehash_reclaims_tombstones() {
+        // Returns the number of groups a lookup for `hash` probes before it
+        // can stop at an EMPTY control byte.
+        fn probe_len<T>(table: &RawTable<T>, hash: u64) -> usize {
+            let mut probe_seq = table.table.probe_seq(hash);
+            let mut len = 1;
+            loop {
+                let group = unsafe { Group::load(table.table.ctrl(probe_seq.pos)) };
+                if group.match_empty().any_bit_set() {
+                    return len;
+                }
+                probe_seq.move_next(table.table.bucket_mask);
+                len += 1;
+            }
+        }
+        fn total_probe_len<T>(table: &RawTable<T>, hashes: impl Iterator<Item = u64>) -> usize {
+            hashes.map(|hash| probe_len(table, hash)).sum()
+        }
+
+        // Every element starts probing at bucket 0, so they form a single run
+        // of full groups in which removals have to leave tombstones.
+        let hasher = |i: &u64| i << 57;
+        let mut table = RawTable::with_capacity(896);
+        let buckets = table.buckets();
+        let n = table.capacity() as u64;
+        for i in 0..n {
+            table.insert(hasher(&i), i, hasher);
+        }
+        // Remove 90% of the elements.
+        for i in (0..n).filter(|i| i % 10 != 0) {
+            assert!(table.remove_entry(hasher(&i), |x| *x == i).is_some());
+        }
+        let live = (0..n).filter(|i| i % 10 == 0);
+        let missing = n..n * 2;
+        let before = total_probe_len(&table, missing.clone().map(|i| hasher(&i)));
+        let capacity = table.capacity();
+        assert!((0..buckets).any(|i| unsafe { *table.table.ctrl(i) } == DELETED));
+
+        table.rehash_in_place(hasher);
+
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(table.len(), live.clone().count());
+        assert!(table.capacity() > capacity);
+        assert!((0..buckets).all(|i| unsafe { *table.table.ctrl(i) } != DELETED));
+        for i in live {
+            assert_eq!(table.get(hasher(&i), |x| *x == i), Some(&i));
+        }
+        for i in missing.clone() {
+            assert!(table.get(hasher(&i), |x| *x == i).is_none());
+        }
+        let after = total_probe_len(&table, missing.map(|i| hasher(&i)));
+        assert!(after < before, "{} >= {}", after, before);
+    }
+
+    #[test]
+    fn iterators_are_fused() {
+        fn assert_fused<I: FusedIterator>() {}
+
+        fn check<A: Allocator + Clone>() {
+            assert_fused::<RawIterRange<u32>>();
+            assert_fused::<RawIter<u32>>();
+            assert_fused::<RawIntoIter<u32, A>>();
+            assert_fused::<RawDrain<'_, u32, A>>();
+            #[cfg(feature = "raw")]
+            assert_fused::<RawIterHash<'_, u32, A>>();
+            #[cfg(feature = "rayon")]
+            assert_fused::<RawIterRangeIndexed<u32>>();
+        }
+
+        check::<Global>();
+    }
+
+    #[test]
+    fn iter_double_ended() {
+        // Each pattern picks the end to take the next element from: bit `k`
+        // of the pattern decides step `k`, repeating every 8 steps.
+        const PATTERNS: [u8; 5] = [0x00, 0xff, 0x55, 0x33, 0x01];
+
+        let hasher = |i: &u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for &n in &[0, 1, 3, 7, 15, 28, 100, 500] {
+            let mut table = RawTable::new();
+            for i in 0..n {
+                table.insert(hasher(&i), i, hasher);
+            }
+
+            for &pattern in &PATTERNS {
+                let mut iter = unsafe { table.iter() };
+                let mut range = unsafe { table.iter() }.iter;
+                let mut from_iter = Vec::new();
+                let mut from_range = Vec::new();
+                for k in 0..n {
+                    let (a, b) = if pattern & (1 << (k % 8)) == 0 {
+                        (iter.next(), range.next())
+                    } else {
+                        (iter.next_back(), range.next_back())
+                    };
+                    assert_eq!(iter.len() as u64, n - k - 1);
+                    from_iter.push(unsafe { *a.unwrap().as_ref() });
+                    from_range.push(unsafe { *b.unwrap().as_ref() });
+                }
+                assert!(iter.next().is_none() && iter.next_back().is_none());
+                assert!(range.next().is_none() && range.next_back().is_none());
+
+                // Both iterators make the same choices, so they also agree on
+                // the order in which elements are yielded.
+                assert_eq!(from_iter, from_range);
+                from_iter.sort_unstable();
+                assert_eq!(from_iter, (0..n).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn clear_no_drop_keeps_elements_alive() {
+        use std::rc::Rc;
+
+        let hasher = |x: &(u64, Rc<()>)| x.0;
+        let rc = Rc::new(());
+        let mut table = RawTable::new();
+        for i in 0..100u64 {
+            table.insert(i, (i, rc.clone()), hasher);
+        }
+        let buckets = table.buckets();
+
+        // Move the elements out, then reset the control bytes without
+        // dropping anything.
+        let moved: Vec<_> = unsafe { table.iter().map(|bucket| bucket.read()).collect() };
+        table.clear_no_drop();
+        assert!(table.is_empty());
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(unsafe { table.iter().count() }, 0);
+        assert_eq!(Rc::strong_count(&rc), 101);
+        drop(moved);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // The allocation is reused for new elements.
+        table.insert(0, (0, rc.clone()), hasher);
+        assert!(table.get(0, |x| x.0 == 0).is_some());
+        assert_eq!(table.buckets(), buckets);
+    }
+
+    #[test]
+    fn clear_removes_tombstones() {
+        // All elements share a probe sequence, so erasing them leaves DELETED
+        // control bytes behind.
+        let mut table = RawTable::new();
+        for i in 0..28u64 {
+            table.insert(0, i, |_| 0);
+        }
+        let full_capacity = table.capacity();
+        for i in 0..28u64 {
+            assert_eq!(table.remove_entry(0, |x| *x == i), Some(i));
+        }
+        assert!(table.is_empty());
+        assert!(table.capacity() < full_capacity);
+
+        table.clear();
+        assert_eq!(table.capacity(), full_capacity);
+
+        // Clearing an already clean table is a no-op.
+        table.clear();
+        assert_eq!(table.capacity(), full_capacity);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn try_with_capacity() {
+        let table = RawTable::<u64>::try_with_capacity(100).unwrap();
+        assert!(table.capacity() >= 100);
+        assert!(table.is_empty());
+
+        // The number of buckets overflows.
+        assert_eq!(
+            RawTable::<u64>::try_with_capacity(usize::MAX).err(),
+            Some(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            RawTable::<u64>::try_with_capacity(usize::MAX / 4).err(),
+            Some(TryReserveError::CapacityOverflow)
+        );
+
+        // The number of buckets fits but the size of the allocation doesn't.
+        assert_eq!(
+            RawTable::<[u8; 1024]>::try_with_capacity(usize::MAX >> 12).err(),
+            Some(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(
+            RawTable::<[u8; 1024]>::try_with_capacity_in(usize::MAX >> 12, Global).err(),
+            Some(TryReserveError::CapacityOverflow)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn iter_from() {
+        let hasher = |i: &u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for &n in &[1, 3, 28, 100] {
+            let mut table = RawTable::new();
+            for i in 0..n {
+                table.insert(hasher(&i), i, hasher);
+            }
+
+            unsafe {
+                for skip in 0..n as usize {
+                    let mut iter = table.iter();
+                    let visited: Vec<u64> = iter.by_ref().take(skip).map(|b| *b.as_ref()).collect();
+                    let remaining: Vec<u64> = iter.clone().map(|b| *b.as_ref()).collect();
+
+                    // Resuming from the next bucket visits exactly the
+                    // elements that the first iterator had not reached.
+                    let resumed = table.iter_from(iter.next().unwrap());
+                    assert_eq!(resumed.len(), remaining.len());
+                    let resumed: Vec<u64> = resumed.map(|b| *b.as_ref()).collect();
+                    assert_eq!(resumed, remaining);
+
+                    let mut all = visited;
+                    all.extend(resumed);
+                    all.sort_unstable();
+                    assert_eq!(all, (0..n).collect::<Vec<_>>());
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "raw")]
+    fn matching_hash(table: &RawTable<u64>, hash: u64, hasher: impl Fn(&u64) -> u64) -> Vec<u64> {
+        let mut found: Vec<u64> = unsafe {
+            table
+                .iter_hash(hash)
+                .map(|bucket| *bucket.as_ref())
+                .filter(|x| hasher(x) == hash)
+                .collect()
+        };
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn iter_hash() {
+        // Non-colliding keys: every hash matches exactly one element.
+        let hasher = |i: &u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(hasher(&i), i, hasher);
+        }
+        for i in 0..100 {
+            assert_eq!(matching_hash(&table, hasher(&i), hasher), [i]);
+        }
+        assert!(matching_hash(&table, hasher(&100), hasher).is_empty());
+
+        // Colliding keys: several elements share each hash.
+        let hasher = |i: &u64| (i % 8).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(hasher(&i), i, hasher);
+        }
+        for h in 0..8 {
+            let expected: Vec<u64> = (0..100).filter(|i| i % 8 == h).collect();
+            assert_eq!(matching_hash(&table, hasher(&h), hasher), expected);
+        }
+
+        // Degenerate table: all elements share a single probe sequence
+        // spanning many groups.
+        let hasher = |_: &u64| 0;
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(0, i, hasher);
+        }
+        assert_eq!(unsafe { table.iter_hash(0).count() }, 100);
+        assert_eq!(
+            matching_hash(&table, 0, hasher),
+            (0..100).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn iter_hash_any() {
+        // Elements are `key * 8 + tag`. All tags of a key share the low bits
+        // of their hash, and so its probe sequence, and only differ in the
+        // bits used for the control byte.
+        let shift = mem::size_of::<usize>().min(8) * 8 - 7;
+        let hash_of = |key: u64, tag: u64| {
+            let low = key.wrapping_mul(0x9E37_79B9_7F4A_7C15) & ((1 << shift) - 1);
+            low | (tag << shift)
+        };
+        let hasher = |x: &u64| hash_of(x / 8, x % 8);
+
+        let mut table = RawTable::new();
+        for key in 0..200 {
+            for tag in (0..8).filter(|tag| (key + tag) % 3 != 0) {
+                let x = key * 8 + tag;
+                table.insert(hasher(&x), x, hasher);
+            }
+        }
+
+        for key in 0..201 {
+            let hashes = [hash_of(key, 1), hash_of(key, 2), hash_of(key, 6)];
+            let mut found: Vec<u64> = unsafe {
+                table
+                    .iter_hash_any(hashes)
+                    .map(|bucket| *bucket.as_ref())
+                    .filter(|x| hashes.contains(&hasher(x)))
+                    .collect()
+            };
+            found.sort_unstable();
+            let expected: Vec<u64> = [1, 2, 6]
+                .iter()
+                .filter(|&&tag| key < 200 && (key + tag) % 3 != 0)
+                .map(|tag| key * 8 + tag)
+                .collect();
+            assert_eq!(found, expected);
+        }
+
+        // A single hash behaves exactly like `iter_hash`.
+        let hash = hash_of(5, 1);
+        let any: Vec<_> = unsafe { table.iter_hash_any([hash]).map(|b| b.as_ptr()).collect() };
+        let one: Vec<_> = unsafe { table.iter_hash(hash).map(|b| b.as_ptr()).collect() };
+        assert_eq!(any, one);
+    }
+
+    /// Checks every `Group` operation against a scalar model of the control
+    /// bytes, so that all of the SIMD and generic implementations agree.
+    #[test]
+    fn group_matches_scalar_model() {
+        #[repr(align(16))]
+        struct Aligned([u8; 16]);
+
+        fn bits(mask: BitMask) -> Vec<usize> {
+            mask.into_iter().collect()
+        }
+
+        let mut state = 0x1234_5678_u32;
+        let mut next_byte = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        };
+
+        for _ in 0..1000 {
+            let mut bytes = Aligned([0; 16]);
+            for byte in &mut bytes.0[..Group::WIDTH] {
+                *byte = match next_byte() % 4 {
+                    0 => EMPTY,
+                    1 => DELETED,
+                    _ => next_byte() & 0x7f,
+                };
+            }
+            let ctrl = &bytes.0[..Group::WIDTH];
+            let indices = |f: &dyn Fn(u8) -> bool| -> Vec<usize> {
+                (0..Group::WIDTH).filter(|&i| f(ctrl[i])).collect()
+            };
+            let group = unsafe { Group::load_aligned(ctrl.as_ptr()) };
+
+            let empty = indices(&|b| b == EMPTY);
+            let special = indices(&|b| is_special(b));
+            let full = indices(&|b| is_full(b));
+            assert_eq!(bits(group.match_empty()), empty);
+            assert_eq!(bits(group.match_empty_or_deleted()), special);
+            assert_eq!(bits(group.match_full()), full);
+
+            let mask = group.match_empty_or_deleted();
+            assert_eq!(mask.any_bit_set(), !special.is_empty());
+            assert_eq!(mask.lowest_set_bit(), special.first().copied());
+            assert_eq!(
+                mask.trailing_zeros(),
+                special.first().copied().unwrap_or(Group::WIDTH)
+            );
+            assert_eq!(
+                mask.leading_zeros(),
+                special
+                    .last()
+                    .map_or(Group::WIDTH, |&i| Group::WIDTH - 1 - i)
+            );
+
+            // The generic implementation may report false positives for bytes
+            // which only differ from the searched value in their lowest bit.
+            let h2 = next_byte() & 0x7f;
+            let matched = bits(group.match_byte(h2));
+            for i in indices(&|b| b == h2) {
+                assert!(matched.contains(&i));
+            }
+            for &i in &matched {
+                assert!(ctrl[i] == h2 || ctrl[i] == h2 ^ 1);
+            }
+
+            // Matching several bytes at once is the union of matching each of
+            // them, with the same false positives.
+            #[cfg(feature = "raw")]
+            {
+                let needles = [
+                    h2,
+                    ctrl[next_byte() as usize % Group::WIDTH] & 0x7f,
+                    next_byte() & 0x7f,
+                ];
+                let matched = bits(group.match_any_of(needles));
+                let mut union: Vec<usize> = needles
+                    .iter()
+                    .flat_map(|&needle| bits(group.match_byte(needle)))
+                    .collect();
+                union.sort_unstable();
+                union.dedup();
+                assert_eq!(matched, union);
+                for i in indices(&|b| needles.contains(&b)) {
+                    assert!(matched.contains(&i));
+                }
+                assert_eq!(bits(group.match_any_of([h2])), bits(group.match_byte(h2)));
+                assert!(bits(group.match_any_of([])).is_empty());
+            }
+
+            let mut converted = Aligned([0; 16]);
+            unsafe {
+                group
+                    .convert_special_to_empty_and_full_to_deleted()
+                    .store_aligned(converted.0.as_mut_ptr());
+            }
+            for (&before, &after) in ctrl.iter().zip(&converted.0) {
+                let expected = if is_full(before) { DELETED } else { EMPTY };
+                assert_eq!(after, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn prefetch() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::new();
+        // Prefetching on the empty singleton must not touch any data bucket.
+        table.prefetch(0);
+        table.prefetch(u64::MAX);
+
+        for i in 0..100 {
+            table.insert(i, i, hasher);
+        }
+        for i in 0..200 {
+            table.prefetch(i);
+            assert_eq!(table.get(i, |x| *x == i).is_some(), i < 100);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn find_batch() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::new();
+        let empty = table.find_batch([0, 1, 2], |_, _| true);
+        assert!(empty.iter().all(Option::is_none));
+
+        for i in 0..100 {
+            table.insert(i, i, hasher);
+        }
+        let keys = [3, 150, 0, 99, 3, 100, 42, 1000];
+        let batch = table.find_batch(keys, |index, x| *x == keys[index]);
+        for (key, found) in keys.iter().zip(batch.iter()) {
+            let single = table.find(*key, |x| x == key);
+            assert_eq!(found.is_some(), *key < 100);
+            assert_eq!(
+                found.as_ref().map(Bucket::as_ptr),
+                single.as_ref().map(Bucket::as_ptr)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn bucket_index_handles() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::with_capacity(32);
+        for i in 0..20 {
+            table.insert(i, i, hasher);
+        }
+        let buckets = table.buckets();
+
+        // Store indices into the table as external handles.
+        let handles: Vec<(u64, usize)> = (0..20)
+            .map(|i| unsafe { (i, table.bucket_index(&table.find(i, |x| *x == i).unwrap())) })
+            .collect();
+
+        unsafe {
+            for &(_, index) in &handles {
+                assert!(table.is_bucket_full(index));
+                *table.bucket(index).as_mut() += 100;
+            }
+            for &(key, index) in &handles {
+                assert_eq!(*table.bucket(index).as_ref(), key + 100);
+            }
+
+            let full = (0..table.buckets())
+                .filter(|&i| table.is_bucket_full(i))
+                .count();
+            assert_eq!(full, table.len());
+
+            // Removing an element empties its slot.
+            let (_, index) = handles[3];
+            table.remove(table.bucket(index));
+            assert!(!table.is_bucket_full(index));
+        }
+
+        // Handles stay valid as long as the table is not reallocated.
+        for i in 20..24 {
+            table.insert(i, i, hasher);
+        }
+        assert_eq!(table.buckets(), buckets);
+        unsafe {
+            for &(key, index) in handles.iter().filter(|&&(key, _)| key != 3) {
+                assert!(table.is_bucket_full(index));
+                assert_eq!(*table.bucket(index).as_ref(), key + 100);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn bucket_token_round_trip() {
+        struct Node {
+            id: u64,
+            edges: Vec<BucketToken>,
+        }
+
+        fn resolve(table: &RawTable<Node>, token: BucketToken) -> u64 {
+            unsafe { table.bucket_from_token(token).as_ref().id }
+        }
+
+        let hasher = |n: &Node| n.id;
+        let mut table = RawTable::with_capacity(16);
+        for id in 0..10 {
+            let node = Node {
+                id,
+                edges: Vec::new(),
+            };
+            table.insert(id, node, hasher);
+        }
+
+        let tokens: Vec<BucketToken> = (0..10)
+            .map(|id| unsafe { table.bucket_token(&table.find(id, |n| n.id == id).unwrap()) })
+            .collect();
+        for (id, &token) in tokens.iter().enumerate() {
+            assert_eq!(resolve(&table, token), id as u64);
+            let bucket = table.find(id as u64, |n| n.id == id as u64).unwrap();
+            unsafe {
+                assert_eq!(token.index(), table.bucket_index(&bucket));
+                assert!(table.bucket_from_token(token).ptr_eq(&bucket));
+            }
+        }
+
+        // Tokens can be stored in the elements themselves.
+        unsafe {
+            table.bucket_from_token(tokens[0]).as_mut().edges = vec![tokens[3], tokens[7]];
+            let edges = &table.bucket_from_token(tokens[0]).as_ref().edges;
+            let targets: Vec<u64> = edges.iter().map(|&t| resolve(&table, t)).collect();
+            assert_eq!(targets, [3, 7]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn bucket_ptr_eq() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::new();
+        for i in 0..10 {
+            table.insert(i, i, hasher);
+        }
+
+        let skip = table.find(4, |x| *x == 4).unwrap();
+        let same = table.find(4, |x| *x == 4).unwrap();
+        assert!(skip.ptr_eq(&same));
+        assert!(skip.ptr_eq(&skip.clone()));
+        let other = table.find(5, |x| *x == 5).unwrap();
+        assert!(!skip.ptr_eq(&other));
+
+        let mut rest: Vec<u64> = unsafe {
+            table
+                .iter()
+                .filter(|bucket| !bucket.ptr_eq(&skip))
+                .map(|bucket| bucket.read())
+                .collect()
+        };
+        rest.sort_unstable();
+        assert_eq!(rest, [0, 1, 2, 3, 5, 6, 7, 8, 9]);
+
+        // Zero-sized elements share a pointer but not a slot.
+        let mut zst = RawTable::new();
+        for _ in 0..2 {
+            zst.insert(0, (), |_| 0);
+        }
+        let buckets: Vec<Bucket<()>> = unsafe { zst.iter().collect() };
+        assert_eq!(buckets[0].as_ptr(), buckets[1].as_ptr());
+        assert!(!buckets[0].ptr_eq(&buckets[1]));
+        assert!(buckets[0].ptr_eq(&buckets[0].clone()));
+    }
+
+    #[test]
+    fn iter_len_is_exact() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(i, i, hasher);
+        }
+
+        let mut iter = unsafe { table.iter() };
+        assert_eq!(iter.len(), 100);
+        for remaining in (50..100).rev() {
+            iter.next().unwrap();
+            assert_eq!(iter.len(), remaining);
+            assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+        }
+
+        // The remaining elements can also be counted exactly in the indexed
+        // form used by parallel iterators, and split without losing any.
+        #[cfg(feature = "rayon")]
+        unsafe {
+            let indexed = iter.clone().iter.into_indexed(iter.len());
+            assert_eq!(indexed.len(), 50);
+            for mid in [0, 1, 25, 49, 50] {
+                let (left, mut right) = iter.clone().iter.into_indexed(50).split_at(mid);
+                assert_eq!(left.len(), mid);
+                assert_eq!(right.len(), 50 - mid);
+                assert_eq!(left.count(), mid);
+                if mid < 50 {
+                    right.next().unwrap();
+                    assert_eq!(right.len(), 49 - mid);
+                }
+                assert_eq!(right.count(), 49 - mid.min(49));
+            }
+        }
+
+        assert_eq!(iter.by_ref().count(), 50);
+        assert_eq!(iter.len(), 0);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.len(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn fold_matches_iter() {
+        let hasher = |i: &u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let mut table = RawTable::new();
+        assert_eq!(table.fold(0, |acc, &x| acc + x), 0);
+
+        // Small tables (fewer buckets than a group), large ones, and tables
+        // with tombstones left by removals.
+        for i in 0..1000 {
+            table.insert(hasher(&i), i, hasher);
+            if i % 7 == 3 {
+                table.remove_entry(hasher(&(i - 1)), |&x| x == i - 1);
+            }
+            let expected: u64 = unsafe { table.iter().map(|b| *b.as_ref()).sum() };
+            assert_eq!(table.fold(0, |acc, &x| acc + x), expected);
+            assert_eq!(table.fold(0, |n, _| n + 1), table.len());
+        }
+
+        // Elements are visited in iteration order.
+        let order: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+        let folded = table.fold(Vec::new(), |mut v, &x| {
+            v.push(x);
+            v
+        });
+        assert_eq!(folded, order);
+
+        // `try_fold` stops at the first error.
+        let mut visited = 0;
+        let result = table.try_fold(0, |acc, &x| {
+            visited += 1;
+            if x == order[10] {
+                Err(x)
+            } else {
+                Ok(acc + x)
+            }
+        });
+        assert_eq!(result, Err(order[10]));
+        assert_eq!(visited, 11);
+        assert_eq!(
+            table.try_fold(0, |acc, &x| Ok::<_, ()>(acc + x)),
+            Ok(order.iter().sum())
+        );
+
+        // Zero-sized elements.
+        let mut zst = RawTable::new();
+        for _ in 0..10 {
+            zst.insert(0, (), |_| 0);
+        }
+        assert_eq!(zst.fold(0, |n, _| n + 1), 10);
+    }
+
+    #[test]
+    fn drain() {
+        use std::rc::Rc;
+
+        let hasher = |i: &(u64, Rc<()>)| i.0;
+        let counter = Rc::new(());
+        let mut table = RawTable::new();
+        for i in 0..100 {
+            table.insert(i, (i, counter.clone()), hasher);
+        }
+        let buckets = table.buckets();
+
+        // Full drain yields every element by value.
+        let mut drained: Vec<u64> = table.drain().map(|(i, _)| i).collect();
+        drained.sort_unstable();
+        assert_eq!(drained, (0..100).collect::<Vec<_>>());
+        assert!(table.is_empty());
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // Dropping the drain early drops the elements it did not yield.
+        for i in 0..100 {
+            table.insert(i, (i, counter.clone()), hasher);
+        }
+        let mut drain = table.drain();
+        let kept: Vec<_> = drain.by_ref().take(10).collect();
+        drop(drain);
+        assert_eq!(Rc::strong_count(&counter), 11);
+        drop(kept);
+        assert_eq!(Rc::strong_count(&counter), 1);
+        assert!(table.is_empty());
+        assert_eq!(table.buckets(), buckets);
+
+        // The retained allocation is reused without growing.
+        for i in 0..100 {
+            table.insert(i, (i, counter.clone()), hasher);
+        }
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(table.len(), 100);
+    }
+
+    #[test]
+    fn resize_keeps_all_elements() {
+        let hasher = |i: &u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+
+        // Tables smaller than a group, sparse tables and full tables.
+        for &(capacity, len) in &[(1, 1), (3, 3), (7, 5), (14, 14), (1000, 10), (1000, 900)] {
+            let mut table = RawTable::with_capacity(capacity);
+            for i in 0..len {
+                table.insert(hasher(&i), i, hasher);
+            }
+            table.reserve(table.capacity() + 1, hasher);
+            assert_eq!(table.len(), len as usize);
+            for i in 0..len {
+                assert!(table.get(hasher(&i), |x| *x == i).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn debug_iterators() {
+        let hasher = |i: &u64| *i;
+        let mut table = RawTable::new();
+        for i in 0..10 {
+            table.insert(i, i, hasher);
+        }
+
+        let mut iter = unsafe { table.iter() };
+        assert_eq!(format!("{:?}", iter), "RawIter { remaining: 10 }");
+        assert_eq!(iter.len(), 10);
+        iter.next();
+        assert_eq!(format!("{:?}", iter), "RawIter { remaining: 9 }");
+
+        let mut drain = table.drain();
+        assert_eq!(format!("{:?}", drain), "RawDrain { remaining: 10 }");
+        assert_eq!(drain.len(), 10);
+        drain.next();
+        assert_eq!(format!("{:?}", drain), "RawDrain { remaining: 9 }");
+        assert_eq!(drain.count(), 9);
+
+        for i in 0..3 {
+            table.insert(i, i, hasher);
+        }
+        let into_iter = table.into_iter();
+        assert_eq!(format!("{:?}", into_iter), "RawIntoIter { remaining: 3 }");
+        assert_eq!(into_iter.count(), 3);
+    }
+
+    #[test]
+    fn get_many_mut() {
+        let mut table = RawTable::new();
+        let hasher = |i: &u64| *i;
+        for i in 0..10 {
+            table.insert(i, i, hasher);
+        }
+
+        // Each query is matched against its own key.
+        let keys = [1, 3, 5];
+        let [a, b, c] = table
+            .get_many_mut(keys, |i, x| *x == keys[i])
+            .expect("all keys are distinct and present");
+        *a += 100;
+        *b += 100;
+        *c += 100;
+        let mut values: Vec<u64> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+        values.sort_unstable();
+        assert_eq!(values, [0, 2, 4, 6, 7, 8, 9, 101, 103, 105]);
+
+        // A missing key.
+        assert!(table
+            .get_many_mut([0, 42], |i, x| *x == [0, 42][i])
+            .is_none());
+
+        // Two queries resolving to the same bucket.
+        assert!(table.get_many_mut([7, 7], |_, x| *x == 7).is_none());
+        assert!(table.get_many_mut([0, 0], |_, x| *x == 0).is_none());
+    }
+
+    #[test]
+    fn find_or_find_insert_slot() {
+        let mut table = RawTable::new();
+        let hasher = |i: &u64| *i;
+        for i in 0..100 {
+            match table.find_or_find_insert_slot(i, |x| *x == i, hasher) {
+                Ok(_) => panic!("{} is not in the table yet", i),
+                Err(slot) => unsafe {
+                    table.insert_in_slot(i, slot, i);
+                },
+            }
+        }
+        assert_eq!(table.len(), 100);
+
+        for i in 0..100 {
+            match table.find_or_find_insert_slot(i, |x| *x == i, hasher) {
+                Ok(bucket) => unsafe { assert_eq!(*bucket.as_ref(), i) },
+                Err(_) => panic!("{} should be in the table", i),
+            }
+        }
+        assert_eq!(table.len(), 100);
+    }
+
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn with_exact_capacity_allocates_less() {
+        fn bytes(f: impl FnOnce(TestAlloc) -> RawTable<u64, TestAlloc>) -> usize {
+            let alloc = TestAlloc::default();
+            let _table = f(alloc.clone());
+            alloc.bytes()
+        }
+
+        for len in 1..=1000 {
+            let exact = bytes(|alloc| RawTable::with_exact_capacity_in(len, alloc));
+            let default = bytes(|alloc| RawTable::with_capacity_in(len, alloc));
+            assert!(exact <= default);
+        }
+
+        // Tables which only miss the load factor by a few elements fit in
+        // half the buckets.
+        for len in [1, 15, 30, 60, 120, 900] {
+            let exact = bytes(|alloc| RawTable::with_exact_capacity_in(len, alloc));
+            let default = bytes(|alloc| RawTable::with_capacity_in(len, alloc));
+            assert!(exact < default, "{} elements use {} bytes", len, exact);
+            assert!(RawTable::<u64>::with_exact_capacity(len).capacity() >= len);
+        }
+
+        // Away from those boundaries the bucket count is the same.
+        for len in [3, 5, 10, 20, 100] {
+            assert_eq!(
+                RawTable::<u64>::with_exact_capacity(len).buckets(),
+                RawTable::<u64>::with_capacity(len).buckets()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "raw", not(feature = "nightly")))]
+    fn allocation_size() {
+        fn check<T>(value: impl Fn(u64) -> T) {
+            let alloc = TestAlloc::default();
+            let mut table = RawTable::new_in(alloc.clone());
+            assert_eq!(table.allocation_size(), 0);
+            for i in 0..100 {
+                table.insert(i, value(i), |_| i);
+                assert_eq!(table.allocation_size(), alloc.bytes());
+            }
+            table.shrink_to(0, |_| 0);
+            assert_eq!(table.allocation_size(), alloc.bytes());
+            table.clear();
+            table.shrink_to(0, |_| 0);
+            assert_eq!(table.allocation_size(), 0);
+            assert_eq!(alloc.bytes(), 0);
+        }
+
+        check(|i| i as u8);
+        check(|i| [i as u8; 3]);
+        check(|i| i);
+        check(|i| i as u128);
+        check(|_| ());
+    }
+
+    #[test]
+    #[cfg(all(feature = "raw", feature = "small-groups"))]
+    fn small_groups() {
+        assert!(Group::WIDTH <= 8);
+
+        // 4 buckets, padded up to the group alignment, followed by the control
+        // bytes and their trailing group.
+        let table: RawTable<u8> = RawTable::with_capacity(1);
+        assert_eq!(table.buckets(), 4);
+        assert_eq!(table.allocation_size(), Group::WIDTH + 4 + Group::WIDTH);
+    }
+
+    #[test]
+    #[cfg(feature = "raw")]
+    fn insert_no_grow_after_reserve() {
+        let mut table = RawTable::new();
+        let hasher = |i: &u64| *i;
+        table.reserve(100, hasher);
+        let buckets = table.buckets();
+        for i in 0..100 {
+            unsafe { table.insert_no_grow(i, i) };
+        }
+        assert_eq!(table.buckets(), buckets);
+        assert_eq!(table.len(), 100);
+        assert!((0..100).all(|i| table.find(i, |x| *x == i).is_some()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "raw", debug_assertions))]
+    #[should_panic = "without spare capacity"]
+    fn insert_no_grow_without_capacity() {
+        let mut table = RawTable::new();
+        table.reserve(3, |i: &u64| *i);
+        for i in 0..=table.capacity() as u64 {
+            unsafe { table.insert_no_grow(i, i) };
+        }
+    }
+
+    #[test]
+    fn erase_in_probe_chain() {
+        // Every element has the same hash, so they all share one probe chain
+        // which spans several groups.
+        let n = 4 * Group::WIDTH as u64;
+        let mut table = RawTable::with_capacity(n as usize);
+        for i in 0..n {
+            table.insert(0, i, |_| 0);
+        }
+
+        for i in (0..n).step_by(3) {
+            let bucket = table.find(0, |x| *x == i).unwrap();
+            if i % 2 == 0 {
+                unsafe { table.erase(bucket) };
+            } else {
+                assert_eq!(unsafe { table.remove(bucket) }, i);
+            }
+        }
+        assert_eq!(table.len(), (n - (n + 2) / 3) as usize);
+        for i in 0..n {
+            assert_eq!(table.find(0, |x| *x == i).is_some(), i % 3 != 0);
+        }
+
+        // Slots freed in the middle of the chain can be reused.
+        for i in (0..n).step_by(3) {
+            table.insert(0, i, |_| 0);
+        }
+        assert_eq!(table.len(), n as usize);
+        assert!((0..n).all(|i| table.find(0, |x| *x == i).is_some()));
+    }
+
+    #[test]
+    fn erase_restores_capacity_in_small_table() {
+        // No probe window of a table smaller than a group can be full, so
+        // erased slots are marked EMPTY rather than DELETED and count
+        // towards the capacity again.
+        let mut table = RawTable::with_capacity(3);
+        assert!(table.buckets() <= Group::WIDTH);
+        let capacity = table.capacity();
+        for round in 0..10 {
+            for i in 0..capacity as u64 {
+                table.insert(i, i + round, |x| *x);
+            }
+            for i in 0..capacity as u64 {
+                let bucket = table.find(i, |x| *x == i + round).unwrap();
+                unsafe { table.erase(bucket) };
+                assert_eq!(table.capacity(), capacity);
+            }
+        }
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn bucket_next_n() {
+        fn check<T>() {
+            // `next_n` doesn't look at control bytes, so the buckets don't
+            // need to be full for their positions to be compared.
+            let table = RawTable::<T>::with_capacity(28);
+            unsafe {
+                let first = table.bucket(0);
+                for i in 0..table.buckets() {
+                    let bucket = first.next_n(i);
+                    assert_eq!(table.bucket_index(&bucket), i);
+                    assert_eq!(bucket.as_ptr(), table.bucket(i).as_ptr());
+                }
+                // Walking a contiguous run from the middle of the table.
+                let mid = table.bucket(8);
+                for i in 0..8 {
+                    assert_eq!(table.bucket_index(&mid.next_n(i)), 8 + i);
+                }
+            }
+        }
+        check::<u8>();
+        check::<(u64, [u8; 3])>();
+        check::<()>();
+    }
 }
@@ -0,0 +1,148 @@
+use super::bitmask::BitMask;
+use super::EMPTY;
+use core::arch::aarch64 as neon;
+use core::mem;
+
+pub type BitMaskWord = u64;
+pub const BITMASK_STRIDE: usize = 4;
+// We only care about the highest bit of each nibble for the mask.
+pub const BITMASK_MASK: BitMaskWord = 0x8888_8888_8888_8888;
+
+/// Abstraction over a group of control bytes which can be scanned in
+/// parallel.
+///
+/// This implementation uses a 128-bit NEON value.
+#[derive(Copy, Clone)]
+pub struct Group(neon::uint8x16_t);
+
+/// Converts a vector where every byte is either `0x00` or `0xFF` into a
+/// `BitMask`.
+///
+/// NEON has no equivalent of SSE2's `movemask`. Instead we shift each 16-bit
+/// lane right by 4 and narrow it to 8 bits, which packs one nibble per input
+/// byte into a 64-bit word, and then keep only the top bit of each nibble.
+#[inline]
+fn to_bitmask(cmp: neon::uint8x16_t) -> BitMask {
+    unsafe {
+        let nibbles = neon::vshrn_n_u16(neon::vreinterpretq_u16_u8(cmp), 4);
+        BitMask(neon::vget_lane_u64(neon::vreinterpret_u64_u8(nibbles), 0) & BITMASK_MASK)
+    }
+}
+
+// FIXME: https://github.com/rust-lang/rust-clippy/issues/3859
+#[allow(clippy::use_self)]
+impl Group {
+    /// Number of bytes in the group.
+    pub const WIDTH: usize = mem::size_of::<Self>();
+
+    /// Whether matching a whole group is a single SIMD operation, which
+    /// makes scanning a group at a time cheaper than checking each byte.
+    pub const IS_SIMD: bool = true;
+
+    /// Returns a full group of empty bytes, suitable for use as the initial
+    /// value for an empty hash table.
+    ///
+    /// This is guaranteed to be aligned to the group size.
+    #[inline]
+    #[allow(clippy::items_after_statements)]
+    pub const fn static_empty() -> &'static [u8; Group::WIDTH] {
+        #[repr(C)]
+        struct AlignedBytes {
+            _align: [Group; 0],
+            bytes: [u8; Group::WIDTH],
+        }
+        const ALIGNED_BYTES: AlignedBytes = AlignedBytes {
+            _align: [],
+            bytes: [EMPTY; Group::WIDTH],
+        };
+        &ALIGNED_BYTES.bytes
+    }
+
+    /// Loads a group of bytes starting at the given address.
+    #[inline]
+    #[allow(clippy::cast_ptr_alignment)] // unaligned load
+    pub unsafe fn load(ptr: *const u8) -> Self {
+        Group(neon::vld1q_u8(ptr))
+    }
+
+    /// Loads a group of bytes starting at the given address, which must be
+    /// aligned to `mem::align_of::<Group>()`.
+    #[inline]
+    #[allow(clippy::cast_ptr_alignment)]
+    pub unsafe fn load_aligned(ptr: *const u8) -> Self {
+        // FIXME: use align_offset once it stabilizes
+        debug_assert_eq!(ptr as usize & (mem::align_of::<Self>() - 1), 0);
+        Group(neon::vld1q_u8(ptr))
+    }
+
+    /// Stores the group of bytes to the given address, which must be
+    /// aligned to `mem::align_of::<Group>()`.
+    #[inline]
+    #[allow(clippy::cast_ptr_alignment)]
+    pub unsafe fn store_aligned(self, ptr: *mut u8) {
+        // FIXME: use align_offset once it stabilizes
+        debug_assert_eq!(ptr as usize & (mem::align_of::<Self>() - 1), 0);
+        neon::vst1q_u8(ptr, self.0);
+    }
+
+    /// Returns a `BitMask` indicating all bytes in the group which have
+    /// the given value.
+    #[inline]
+    pub fn match_byte(self, byte: u8) -> BitMask {
+        unsafe { to_bitmask(neon::vceqq_u8(self.0, neon::vdupq_n_u8(byte))) }
+    }
+
+    /// Returns a `BitMask` indicating all bytes in the group which have any
+    /// of the given values.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn match_any_of<const N: usize>(self, bytes: [u8; N]) -> BitMask {
+        unsafe {
+            let mut cmp = neon::vdupq_n_u8(0);
+            for &byte in &bytes {
+                cmp = neon::vorrq_u8(cmp, neon::vceqq_u8(self.0, neon::vdupq_n_u8(byte)));
+            }
+            to_bitmask(cmp)
+        }
+    }
+
+    /// Returns a `BitMask` indicating all bytes in the group which are
+    /// `EMPTY`.
+    #[inline]
+    pub fn match_empty(self) -> BitMask {
+        self.match_byte(EMPTY)
+    }
+
+    /// Returns a `BitMask` indicating all bytes in the group which are
+    /// `EMPTY` or `DELETED`.
+    #[inline]
+    pub fn match_empty_or_deleted(self) -> BitMask {
+        // A byte is EMPTY or DELETED iff the high bit is set
+        unsafe { to_bitmask(neon::vcltzq_s8(neon::vreinterpretq_s8_u8(self.0))) }
+    }
+
+    /// Returns a `BitMask` indicating all bytes in the group which are full.
+    #[inline]
+    pub fn match_full(&self) -> BitMask {
+        self.match_empty_or_deleted().invert()
+    }
+
+    /// Performs the following transformation on all bytes in the group:
+    /// - `EMPTY => EMPTY`
+    /// - `DELETED => EMPTY`
+    /// - `FULL => DELETED`
+    #[inline]
+    pub fn convert_special_to_empty_and_full_to_deleted(self) -> Self {
+        // Map high_bit = 1 (EMPTY or DELETED) to 1111_1111
+        // and high_bit = 0 (FULL) to 1000_0000
+        //
+        // Here's this logic expanded to concrete values:
+        //   let special = 0 > byte = 1111_1111 (true) or 0000_0000 (false)
+        //   1111_1111 | 1000_0000 = 1111_1111
+        //   0000_0000 | 1000_0000 = 1000_0000
+        unsafe {
+            let special = neon::vcltzq_s8(neon::vreinterpretq_s8_u8(self.0));
+            Group(neon::vorrq_u8(special, neon::vdupq_n_u8(0x80)))
+        }
+    }
+}
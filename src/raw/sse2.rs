@@ -24,6 +24,10 @@ impl Group {
     /// Number of bytes in the group.
     pub const WIDTH: usize = mem::size_of::<Self>();
 
+    /// Whether matching a whole group is a single SIMD operation, which
+    /// makes scanning a group at a time cheaper than checking each byte.
+    pub const IS_SIMD: bool = true;
+
     /// Returns a full group of empty bytes, suitable for use as the initial
     /// value for an empty hash table.
     ///
@@ -88,6 +92,29 @@ impl Group {
         }
     }
 
+    /// Returns a `BitMask` indicating all bytes in the group which have any
+    /// of the given values.
+    #[cfg(feature = "raw")]
+    #[inline]
+    pub fn match_any_of<const N: usize>(self, bytes: [u8; N]) -> BitMask {
+        #[allow(
+            clippy::cast_possible_wrap, // byte: u8 as i8
+            // byte: i32 as u16
+            //   note: _mm_movemask_epi8 returns a 16-bit mask in a i32, the
+            //   upper 16-bits of the i32 are zeroed:
+            clippy::cast_sign_loss,
+            clippy::cast_possible_truncation
+        )]
+        unsafe {
+            let mut cmp = x86::_mm_setzero_si128();
+            for &byte in &bytes {
+                let eq = x86::_mm_cmpeq_epi8(self.0, x86::_mm_set1_epi8(byte as i8));
+                cmp = x86::_mm_or_si128(cmp, eq);
+            }
+            BitMask(x86::_mm_movemask_epi8(cmp) as u16)
+        }
+    }
+
     /// Returns a `BitMask` indicating all bytes in the group which are
     /// `EMPTY`.
     #[inline]
@@ -1,13 +1,12 @@
-use crate::TryReserveError;
+use crate::{Equivalent, TryReserveError};
 use alloc::borrow::ToOwned;
-use core::borrow::Borrow;
 use core::fmt;
 use core::hash::{BuildHasher, Hash};
 use core::iter::{Chain, FromIterator, FusedIterator};
 use core::mem;
 use core::ops::{BitAnd, BitOr, BitXor, Sub};
 
-use super::map::{self, ConsumeAllOnDrop, DefaultHashBuilder, DrainFilterInner, HashMap, Keys};
+use super::map::{self, ConsumeAllOnDrop, DefaultHashBuilder, ExtractIfInner, HashMap, Keys};
 use crate::raw::{Allocator, Global};
 
 // Future Optimization (FIXME!)
@@ -331,16 +330,22 @@ impl<T, S, A: Allocator + Clone> HashSet<T, S, A> {
     /// In other words, move all elements `e` such that `f(&e)` returns `true` out
     /// into another iterator.
     ///
-    /// When the returned DrainedFilter is dropped, any remaining elements that satisfy
+    /// When the returned ExtractIf is dropped, any remaining elements that satisfy
     /// the predicate are dropped from the set.
     ///
+    /// It is unspecified how many more elements will be subjected to the closure
+    /// if a panic occurs in the closure, or a panic occurs while dropping an element,
+    /// or if the `ExtractIf` value is leaked.
+    ///
+    /// Keeps the allocated memory for reuse.
+    ///
     /// # Examples
     ///
     /// ```
     /// use hashbrown::HashSet;
     ///
     /// let mut set: HashSet<i32> = (0..8).collect();
-    /// let drained: HashSet<i32> = set.drain_filter(|v| v % 2 == 0).collect();
+    /// let drained: HashSet<i32> = set.extract_if(|v| v % 2 == 0).collect();
     ///
     /// let mut evens = drained.into_iter().collect::<Vec<_>>();
     /// let mut odds = set.into_iter().collect::<Vec<_>>();
@@ -351,19 +356,32 @@ impl<T, S, A: Allocator + Clone> HashSet<T, S, A> {
     /// assert_eq!(odds, vec![1, 3, 5, 7]);
     /// ```
     #[cfg_attr(feature = "inline-more", inline)]
-    pub fn drain_filter<F>(&mut self, f: F) -> DrainFilter<'_, T, F, A>
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, T, F, A>
     where
         F: FnMut(&T) -> bool,
     {
-        DrainFilter {
+        ExtractIf {
             f,
-            inner: DrainFilterInner {
+            inner: ExtractIfInner {
                 iter: unsafe { self.map.table.iter() },
                 table: &mut self.map.table,
             },
         }
     }
 
+    /// Drains elements which are true under the given predicate,
+    /// and returns an iterator over the removed items.
+    ///
+    /// This method has been renamed to [`extract_if`](HashSet::extract_if).
+    #[cfg_attr(feature = "inline-more", inline)]
+    #[deprecated(since = "0.12.4", note = "renamed to `extract_if`")]
+    pub fn drain_filter<F>(&mut self, f: F) -> ExtractIf<'_, T, F, A>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.extract_if(f)
+    }
+
     /// Clears the set, removing all values.
     ///
     /// # Examples
@@ -562,6 +580,28 @@ where
         self.map.reserve(additional);
     }
 
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the `HashSet`, without deliberately over-allocating.
+    ///
+    /// See [`HashMap::reserve_exact`](crate::HashMap::reserve_exact).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashSet;
+    /// let mut set: HashSet<i32> = HashSet::new();
+    /// set.reserve_exact(10);
+    /// assert!(set.capacity() >= 10);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.map.reserve_exact(additional);
+    }
+
     /// Tries to reserve capacity for at least `additional` more elements to be inserted
     /// in the given `HashSet<K,V>`. The collection may reserve more space to avoid
     /// frequent reallocations.
@@ -583,6 +623,26 @@ where
         self.map.try_reserve(additional)
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the `HashSet`, without deliberately over-allocating.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error
+    /// is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashSet;
+    /// let mut set: HashSet<i32> = HashSet::new();
+    /// set.try_reserve_exact(10).expect("why is the test harness OOMing on 10 bytes?");
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.map.try_reserve_exact(additional)
+    }
+
     /// Shrinks the capacity of the set as much as possible. It will drop
     /// down as much as possible while maintaining the internal rules
     /// and possibly leaving some space in accordance with the resize policy.
@@ -773,8 +833,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn contains<Q: ?Sized>(&self, value: &Q) -> bool
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<T>,
     {
         self.map.contains_key(value)
     }
@@ -800,8 +859,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn get<Q: ?Sized>(&self, value: &Q) -> Option<&T>
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<T>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.map.get_key_value(value) {
@@ -856,8 +914,7 @@ where
     #[inline]
     pub fn get_or_insert_owned<Q: ?Sized>(&mut self, value: &Q) -> &T
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq + ToOwned<Owned = T>,
+        Q: Hash + Equivalent<T> + ToOwned<Owned = T>,
     {
         // Although the raw entry gives us `&mut T`, we only return `&T` to be consistent with
         // `get`. Key mutation is "raw" because you're not supposed to affect `Eq` or `Hash`.
@@ -889,8 +946,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn get_or_insert_with<Q: ?Sized, F>(&mut self, value: &Q, f: F) -> &T
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<T>,
         F: FnOnce(&Q) -> T,
     {
         // Although the raw entry gives us `&mut T`, we only return `&T` to be consistent with
@@ -1106,8 +1162,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn remove<Q: ?Sized>(&mut self, value: &Q) -> bool
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<T>,
     {
         self.map.remove(value).is_some()
     }
@@ -1133,8 +1188,7 @@ where
     #[cfg_attr(feature = "inline-more", inline)]
     pub fn take<Q: ?Sized>(&mut self, value: &Q) -> Option<T>
     where
-        T: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<T>,
     {
         // Avoid `Option::map` because it bloats LLVM IR.
         match self.map.remove_entry(value) {
@@ -1186,6 +1240,46 @@ where
     }
 }
 
+impl<T, S, A> HashSet<T, S, A>
+where
+    T: Eq + Hash,
+    S: BuildHasher + Default,
+    A: Default + Allocator + Clone,
+{
+    /// Creates a set from an iterator like [`FromIterator::from_iter`], and
+    /// also returns the number of elements that were dropped because an
+    /// equal element had already been inserted.
+    ///
+    /// This is useful to validate inputs which are expected to be unique.
+    /// As with [`insert`], the first occurrence of each element is kept.
+    ///
+    /// [`insert`]: #method.insert
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashSet;
+    ///
+    /// let (set, duplicates): (HashSet<i32>, usize) =
+    ///     HashSet::from_iter_counting_duplicates([1, 2, 2, 3, 1, 1]);
+    /// assert_eq!(set.len(), 3);
+    /// assert_eq!(duplicates, 3);
+    /// ```
+    pub fn from_iter_counting_duplicates<I: IntoIterator<Item = T>>(iter: I) -> (Self, usize) {
+        let iter = iter.into_iter();
+        let mut set = Self::with_hasher_in(Default::default(), Default::default());
+        set.reserve(iter.size_hint().0);
+
+        let mut duplicates = 0;
+        for value in iter {
+            if !set.insert(value) {
+                duplicates += 1;
+            }
+        }
+        (set, duplicates)
+    }
+}
+
 impl<T, S, A> FromIterator<T> for HashSet<T, S, A>
 where
     T: Eq + Hash,
@@ -1435,6 +1529,16 @@ pub struct IntoIter<K, A: Allocator + Clone = Global> {
     iter: map::IntoIter<K, (), A>,
 }
 
+impl<K: Clone, A: Allocator + Clone> Clone for IntoIter<K, A> {
+    /// Clones the items that have not been yielded yet into a new iterator.
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        IntoIter {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
 /// A draining iterator over the items of a `HashSet`.
 ///
 /// This `struct` is created by the [`drain`] method on [`HashSet`].
@@ -1446,21 +1550,27 @@ pub struct Drain<'a, K, A: Allocator + Clone = Global> {
     iter: map::Drain<'a, K, (), A>,
 }
 
-/// A draining iterator over entries of a `HashSet` which don't satisfy the predicate `f`.
+/// A draining iterator over entries of a `HashSet` which satisfy the predicate `f`.
 ///
-/// This `struct` is created by the [`drain_filter`] method on [`HashSet`]. See its
+/// This `struct` is created by the [`extract_if`] method on [`HashSet`]. See its
 /// documentation for more.
 ///
-/// [`drain_filter`]: struct.HashSet.html#method.drain_filter
+/// [`extract_if`]: struct.HashSet.html#method.extract_if
 /// [`HashSet`]: struct.HashSet.html
-pub struct DrainFilter<'a, K, F, A: Allocator + Clone = Global>
+pub struct ExtractIf<'a, K, F, A: Allocator + Clone = Global>
 where
     F: FnMut(&K) -> bool,
 {
     f: F,
-    inner: DrainFilterInner<'a, K, (), A>,
+    inner: ExtractIfInner<'a, K, (), A>,
 }
 
+/// A draining iterator over entries of a `HashSet` which satisfy a predicate.
+///
+/// This type has been renamed to [`ExtractIf`].
+#[deprecated(since = "0.12.4", note = "renamed to `ExtractIf`")]
+pub type DrainFilter<'a, K, F, A = Global> = ExtractIf<'a, K, F, A>;
+
 /// A lazy iterator producing elements in the intersection of `HashSet`s.
 ///
 /// This `struct` is created by the [`intersection`] method on [`HashSet`].
@@ -1649,7 +1759,7 @@ impl<K: fmt::Debug, A: Allocator + Clone> fmt::Debug for Drain<'_, K, A> {
     }
 }
 
-impl<'a, K, F, A: Allocator + Clone> Drop for DrainFilter<'a, K, F, A>
+impl<'a, K, F, A: Allocator + Clone> Drop for ExtractIf<'a, K, F, A>
 where
     F: FnMut(&K) -> bool,
 {
@@ -1663,7 +1773,7 @@ where
     }
 }
 
-impl<K, F, A: Allocator + Clone> Iterator for DrainFilter<'_, K, F, A>
+impl<K, F, A: Allocator + Clone> Iterator for ExtractIf<'_, K, F, A>
 where
     F: FnMut(&K) -> bool,
 {
@@ -1682,9 +1792,17 @@ where
     }
 }
 
-impl<K, F, A: Allocator + Clone> FusedIterator for DrainFilter<'_, K, F, A> where
-    F: FnMut(&K) -> bool
+impl<K, F, A: Allocator + Clone> FusedIterator for ExtractIf<'_, K, F, A> where F: FnMut(&K) -> bool {}
+
+impl<K, F, A: Allocator + Clone> fmt::Debug for ExtractIf<'_, K, F, A>
+where
+    F: FnMut(&K) -> bool,
 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("remaining", &self.inner.iter.len())
+            .finish()
+    }
 }
 
 impl<T, S, A: Allocator + Clone> Clone for Intersection<'_, T, S, A> {
@@ -2416,6 +2534,29 @@ mod test_set {
         assert!(b.is_superset(&a));
     }
 
+    #[test]
+    fn test_iterators_are_fused() {
+        use super::{Difference, Drain, ExtractIf, Intersection, IntoIter, Iter};
+        use super::{SymmetricDifference, Union};
+        use crate::raw::Allocator;
+        use core::iter::FusedIterator;
+
+        fn assert_fused<I: FusedIterator>() {}
+
+        fn check<A: Allocator + Clone>() {
+            assert_fused::<Iter<'_, u32>>();
+            assert_fused::<IntoIter<u32, A>>();
+            assert_fused::<Drain<'_, u32, A>>();
+            assert_fused::<ExtractIf<'_, u32, fn(&u32) -> bool, A>>();
+            assert_fused::<Intersection<'_, u32, DefaultHashBuilder, A>>();
+            assert_fused::<Difference<'_, u32, DefaultHashBuilder, A>>();
+            assert_fused::<SymmetricDifference<'_, u32, DefaultHashBuilder, A>>();
+            assert_fused::<Union<'_, u32, DefaultHashBuilder, A>>();
+        }
+
+        check::<crate::raw::Global>();
+    }
+
     #[test]
     fn test_iterate() {
         let mut a = HashSet::new();
@@ -2568,6 +2709,38 @@ mod test_set {
         assert_eq!(set.iter().len(), xs.len() - 1);
     }
 
+    #[test]
+    fn test_from_array() {
+        let set: HashSet<i32> = HashSet::from([3, 1, 3, 2, 1]);
+        assert_eq!(set.len(), 3);
+        assert!(set.capacity() >= 5);
+        assert_eq!(set, [1, 2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn test_from_iter_counting_duplicates() {
+        // Every value in 0..100 appears once, multiples of 3 appear twice
+        // more and multiples of 10 once more.
+        let xs = (0..100)
+            .chain((0..100).step_by(3))
+            .chain((0..100).step_by(3))
+            .chain((0..100).step_by(10));
+        let (set, duplicates): (HashSet<i32>, usize) = HashSet::from_iter_counting_duplicates(xs);
+        assert_eq!(set.len(), 100);
+        assert_eq!(duplicates, 34 + 34 + 10);
+        assert!((0..100).all(|x| set.contains(&x)));
+
+        let (set, duplicates): (HashSet<i32>, usize) =
+            HashSet::from_iter_counting_duplicates(0..10);
+        assert_eq!(set.len(), 10);
+        assert_eq!(duplicates, 0);
+
+        let (set, duplicates): (HashSet<i32>, usize) =
+            HashSet::from_iter_counting_duplicates(core::iter::empty());
+        assert!(set.is_empty());
+        assert_eq!(duplicates, 0);
+    }
+
     #[test]
     fn test_move_iter() {
         let hs = {
@@ -2583,6 +2756,21 @@ mod test_set {
         assert!(v == ['a', 'b'] || v == ['b', 'a']);
     }
 
+    #[test]
+    fn test_move_iter_clone() {
+        let hs: HashSet<i32> = (0..20).collect();
+        let mut iter = hs.into_iter();
+        let first: Vec<i32> = iter.by_ref().take(10).collect();
+
+        let mut rest: Vec<i32> = iter.clone().collect();
+        let mut cloned_rest: Vec<i32> = iter.collect();
+        rest.sort_unstable();
+        cloned_rest.sort_unstable();
+        assert_eq!(rest, cloned_rest);
+        assert!(rest.iter().all(|k| !first.contains(k)));
+        assert_eq!(rest.len() + first.len(), 20);
+    }
+
     #[test]
     fn test_eq() {
         // These constants once happened to expose a bug in insert().
@@ -2658,6 +2846,33 @@ mod test_set {
         }
     }
 
+    #[test]
+    fn test_get_or_insert_with_is_lazy() {
+        use std::borrow::ToOwned;
+        use std::string::String;
+
+        let mut set: HashSet<String> = HashSet::new();
+        let mut calls = 0;
+        for &word in &["apple", "pear", "apple", "apple", "pear"] {
+            let value = set.get_or_insert_with(word, |w| {
+                calls += 1;
+                w.to_owned()
+            });
+            assert_eq!(value, word);
+        }
+        // `f` was only called the first time each word was seen.
+        assert_eq!(calls, 2);
+        assert_eq!(set.len(), 2);
+
+        let stored: *const String = set.get("apple").unwrap();
+        assert_eq!(
+            set.get_or_insert("apple".to_owned()) as *const String,
+            stored
+        );
+        assert_eq!(set.get_or_insert("plum".to_owned()), "plum");
+        assert_eq!(set.len(), 3);
+    }
+
     #[test]
     fn test_replace() {
         use core::hash;
@@ -2682,11 +2897,14 @@ mod test_set {
         let mut s = HashSet::new();
         assert_eq!(s.replace(Foo("a", 1)), None);
         assert_eq!(s.len(), 1);
-        assert_eq!(s.replace(Foo("a", 2)), Some(Foo("a", 1)));
+        // `Foo("a", 1) == Foo("a", 2)`, so check the field that `Eq` ignores
+        // to see which of the two values was returned and which was kept.
+        assert_eq!(s.replace(Foo("a", 2)).map(|foo| foo.1), Some(1));
         assert_eq!(s.len(), 1);
+        assert_eq!(s.get(&Foo("a", 0)).map(|foo| foo.1), Some(2));
 
         let mut it = s.iter();
-        assert_eq!(it.next(), Some(&Foo("a", 2)));
+        assert_eq!(it.next().map(|foo| foo.1), Some(2));
         assert_eq!(it.next(), None);
     }
 
@@ -2730,10 +2948,10 @@ mod test_set {
     }
 
     #[test]
-    fn test_drain_filter() {
+    fn test_extract_if() {
         {
             let mut set: HashSet<i32> = (0..8).collect();
-            let drained = set.drain_filter(|&k| k % 2 == 0);
+            let drained = set.extract_if(|&k| k % 2 == 0);
             let mut out = drained.collect::<Vec<_>>();
             out.sort_unstable();
             assert_eq!(vec![0, 2, 4, 6], out);
@@ -2741,11 +2959,69 @@ mod test_set {
         }
         {
             let mut set: HashSet<i32> = (0..8).collect();
-            drop(set.drain_filter(|&k| k % 2 == 0));
+            drop(set.extract_if(|&k| k % 2 == 0));
             assert_eq!(set.len(), 4, "Removes non-matching items on drop");
         }
     }
 
+    #[test]
+    fn test_extract_if_debug() {
+        let mut set: HashSet<i32> = (0..8).collect();
+        let mut iter = set.extract_if(|&k| k % 2 == 0);
+        assert_eq!(format!("{:?}", iter), "ExtractIf { remaining: 8 }");
+        assert_eq!(format!("{:?}", iter), "ExtractIf { remaining: 8 }");
+        let mut out: Vec<_> = iter.by_ref().collect();
+        out.sort_unstable();
+        assert_eq!(out, [0, 2, 4, 6]);
+        assert_eq!(format!("{:?}", iter), "ExtractIf { remaining: 0 }");
+    }
+
+    #[test]
+    fn test_extract_if_early_drop() {
+        let mut set: HashSet<i32> = (0..8).collect();
+        let buckets = set.map.table.buckets();
+        let mut calls = 0;
+        {
+            let mut iter = set.extract_if(|&k| {
+                calls += 1;
+                k % 2 == 0
+            });
+            assert!(iter.next().is_some());
+        }
+        // Dropping the iterator runs the predicate over the remaining elements.
+        assert_eq!(calls, 8);
+        assert_eq!(set.len(), 4);
+        assert!(set.iter().all(|&k| k % 2 != 0));
+        // Removed entries may leave tombstones, but nothing is reallocated.
+        assert_eq!(set.map.table.buckets(), buckets);
+    }
+
+    #[test]
+    fn test_extract_if_pred_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mut set: HashSet<i32> = (0..8).collect();
+        let buckets = set.map.table.buckets();
+        let mut calls = 0;
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            set.extract_if(|_| {
+                calls += 1;
+                if calls == 4 {
+                    panic!("panic in predicate");
+                }
+                true
+            })
+            .for_each(drop);
+        }));
+        assert!(result.is_err());
+
+        // The set must stay consistent, whatever was extracted before the panic.
+        assert_eq!(set.iter().count(), set.len());
+        assert!(set.len() < 8);
+        assert!(set.iter().all(|&k| (0..8).contains(&k)));
+        assert_eq!(set.map.table.buckets(), buckets);
+    }
+
     #[test]
     fn test_const_with_hasher() {
         use core::hash::BuildHasher;
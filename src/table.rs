@@ -0,0 +1,1125 @@
+use crate::raw::{Allocator, Bucket, Global, InsertSlot, RawDrain, RawIntoIter, RawIter, RawTable};
+use crate::TryReserveError;
+use core::fmt::{self, Debug};
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+/// Low-level hash table with explicit hashing.
+///
+/// The primary use case for this type over [`HashMap`] or [`HashSet`] is to
+/// support types that do not implement the [`Hash`] and [`Eq`] traits, but
+/// instead require additional data not contained in the key itself to compute a
+/// hash and compare two elements for equality. Examples of when this can be
+/// useful include:
+/// - An interner, where the elements are indices into a separate string buffer.
+/// - A multimap, where several elements can share a key.
+/// - A table whose hash function depends on data stored outside the table.
+///
+/// Since there is no stored hasher, every method which may need to look up or
+/// move elements takes the hash of the value and an equality or hashing
+/// function as arguments. The hash passed for a value must always be the same
+/// as the one the hasher function returns for it, otherwise lookups may fail
+/// to find it. This is a logic error, but it does not cause undefined behavior.
+///
+/// [`HashMap`]: crate::HashMap
+/// [`HashSet`]: crate::HashSet
+/// [`Hash`]: https://doc.rust-lang.org/std/hash/trait.Hash.html
+/// [`Eq`]: https://doc.rust-lang.org/std/cmp/trait.Eq.html
+///
+/// # Examples
+///
+/// ```
+/// use core::hash::{BuildHasher, Hash, Hasher};
+/// use hashbrown::hash_map::DefaultHashBuilder;
+/// use hashbrown::HashTable;
+///
+/// fn hash_one<S: BuildHasher>(hash_builder: &S, value: &str) -> u64 {
+///     let mut state = hash_builder.build_hasher();
+///     value.hash(&mut state);
+///     state.finish()
+/// }
+///
+/// let hasher = DefaultHashBuilder::default();
+/// let hasher = |val: &&str| hash_one(&hasher, val);
+///
+/// let mut table = HashTable::new();
+/// for word in ["a", "b", "c", "a"] {
+///     table.entry(hasher(&word), |&x| x == word, hasher).or_insert(word);
+/// }
+/// assert_eq!(table.len(), 3);
+/// assert_eq!(table.find(hasher(&"b"), |&x| x == "b"), Some(&"b"));
+/// assert_eq!(table.find(hasher(&"d"), |&x| x == "d"), None);
+/// ```
+pub struct HashTable<T, A: Allocator + Clone = Global> {
+    pub(crate) table: RawTable<T, A>,
+}
+
+impl<T> HashTable<T, Global> {
+    /// Creates an empty `HashTable`.
+    ///
+    /// The hash table is initially created with a capacity of 0, so it will not allocate until it
+    /// is first inserted into.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashTable;
+    /// let mut table: HashTable<&str> = HashTable::new();
+    /// assert_eq!(table.len(), 0);
+    /// assert_eq!(table.capacity(), 0);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub const fn new() -> Self {
+        Self {
+            table: RawTable::new(),
+        }
+    }
+
+    /// Creates an empty `HashTable` with the specified capacity.
+    ///
+    /// The hash table will be able to hold at least `capacity` elements without
+    /// reallocating. If `capacity` is 0, the hash table will not allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hashbrown::HashTable;
+    /// let mut table: HashTable<&str> = HashTable::with_capacity(10);
+    /// assert_eq!(table.len(), 0);
+    /// assert!(table.capacity() >= 10);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            table: RawTable::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, A: Allocator + Clone> HashTable<T, A> {
+    /// Creates an empty `HashTable` using the given allocator.
+    ///
+    /// The hash table is initially created with a capacity of 0, so it will not allocate until it
+    /// is first inserted into.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            table: RawTable::new_in(alloc),
+        }
+    }
+
+    /// Creates an empty `HashTable` with the specified capacity using the given allocator.
+    ///
+    /// The hash table will be able to hold at least `capacity` elements without
+    /// reallocating. If `capacity` is 0, the hash table will not allocate.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self {
+            table: RawTable::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.table.allocator()
+    }
+
+    /// Returns a reference to an entry in the table with the given hash and
+    /// which satisfies the equality function passed.
+    ///
+    /// This method will call `eq` for all entries with the given hash, but may
+    /// also call it for entries with a different hash. `eq` should only return
+    /// true for the desired entry, at which point the search is stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::hash_map::DefaultHashBuilder;
+    /// use hashbrown::HashTable;
+    ///
+    /// fn hash_one<S: BuildHasher>(hash_builder: &S, value: &u32) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     value.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let hasher = DefaultHashBuilder::default();
+    /// let hasher = |val: &u32| hash_one(&hasher, val);
+    ///
+    /// let mut table = HashTable::new();
+    /// table.insert_unique(hasher(&1), 1, hasher);
+    /// table.insert_unique(hasher(&2), 2, hasher);
+    /// assert_eq!(table.find(hasher(&2), |&x| x == 2), Some(&2));
+    /// assert_eq!(table.find(hasher(&3), |&x| x == 3), None);
+    /// ```
+    #[inline]
+    pub fn find(&self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<&T> {
+        self.table.get(hash, eq)
+    }
+
+    /// Returns a mutable reference to an entry in the table with the given hash
+    /// and which satisfies the equality function passed.
+    ///
+    /// This method will call `eq` for all entries with the given hash, but may
+    /// also call it for entries with a different hash. `eq` should only return
+    /// true for the desired entry, at which point the search is stopped.
+    ///
+    /// When mutating an entry, you should ensure that it still retains the same
+    /// hash value as when it was inserted, otherwise lookups of that entry may
+    /// fail to find it.
+    #[inline]
+    pub fn find_mut(&mut self, hash: u64, eq: impl FnMut(&T) -> bool) -> Option<&mut T> {
+        self.table.get_mut(hash, eq)
+    }
+
+    /// Returns an `OccupiedEntry` for an entry in the table with the given hash
+    /// and which satisfies the equality function passed, or `None` if there is
+    /// no such entry.
+    ///
+    /// This can be used to remove the entry from the table. Unlike [`entry`],
+    /// this never reserves space for an insertion and so does not need a
+    /// hasher.
+    ///
+    /// [`entry`]: HashTable::entry
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn find_entry(
+        &mut self,
+        hash: u64,
+        eq: impl FnMut(&T) -> bool,
+    ) -> Option<OccupiedEntry<'_, T, A>> {
+        match self.table.find(hash, eq) {
+            Some(bucket) => Some(OccupiedEntry {
+                hash,
+                bucket,
+                table: self,
+            }),
+            None => None,
+        }
+    }
+
+    /// Returns an `Entry` for an entry in the table with the given hash
+    /// and which satisfies the equality function passed.
+    ///
+    /// This can be used to remove the entry from the table, or insert a new
+    /// entry with the given hash if one doesn't already exist.
+    ///
+    /// This method will call `eq` for all entries with the given hash, but may
+    /// also call it for entries with a different hash. `eq` should only return
+    /// true for the desired entry, at which point the search is stopped.
+    ///
+    /// This method may grow the table in preparation for an insertion. Call
+    /// [`HashTable::find_entry`] if this is undesirable.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::hash_map::DefaultHashBuilder;
+    /// use hashbrown::hash_table::{Entry, HashTable};
+    ///
+    /// fn hash_one<S: BuildHasher>(hash_builder: &S, value: &u32) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     value.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let hasher = DefaultHashBuilder::default();
+    /// let hasher = |val: &u32| hash_one(&hasher, val);
+    ///
+    /// let mut table = HashTable::new();
+    /// table.insert_unique(hasher(&1), 1, hasher);
+    ///
+    /// match table.entry(hasher(&1), |&x| x == 1, hasher) {
+    ///     Entry::Occupied(entry) => assert_eq!(entry.remove(), 1),
+    ///     Entry::Vacant(_) => unreachable!(),
+    /// }
+    /// match table.entry(hasher(&2), |&x| x == 2, hasher) {
+    ///     Entry::Occupied(_) => unreachable!(),
+    ///     Entry::Vacant(entry) => assert_eq!(*entry.insert(2), 2),
+    /// }
+    /// assert_eq!(table.len(), 1);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn entry(
+        &mut self,
+        hash: u64,
+        eq: impl FnMut(&T) -> bool,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Entry<'_, T, A> {
+        match self.table.find_or_find_insert_slot(hash, eq, hasher) {
+            Ok(bucket) => Entry::Occupied(OccupiedEntry {
+                hash,
+                bucket,
+                table: self,
+            }),
+            Err(insert_slot) => Entry::Vacant(VacantEntry {
+                hash,
+                insert_slot,
+                table: self,
+            }),
+        }
+    }
+
+    /// Inserts an element into the `HashTable` with the given hash value, but
+    /// without checking whether an equivalent element already exists within
+    /// the table.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::hash::{BuildHasher, Hash, Hasher};
+    /// use hashbrown::hash_map::DefaultHashBuilder;
+    /// use hashbrown::HashTable;
+    ///
+    /// fn hash_one<S: BuildHasher>(hash_builder: &S, value: &u32) -> u64 {
+    ///     let mut state = hash_builder.build_hasher();
+    ///     value.hash(&mut state);
+    ///     state.finish()
+    /// }
+    ///
+    /// let hasher = DefaultHashBuilder::default();
+    /// let hasher = |val: &u32| hash_one(&hasher, val);
+    ///
+    /// let mut v = HashTable::new();
+    /// v.insert_unique(hasher(&1), 1, hasher);
+    /// // Nothing stops a duplicate from being inserted.
+    /// v.insert_unique(hasher(&1), 1, hasher);
+    /// assert_eq!(v.len(), 2);
+    /// ```
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_unique(
+        &mut self,
+        hash: u64,
+        value: T,
+        hasher: impl Fn(&T) -> u64,
+    ) -> OccupiedEntry<'_, T, A> {
+        let bucket = self.table.insert(hash, value, hasher);
+        OccupiedEntry {
+            hash,
+            bucket,
+            table: self,
+        }
+    }
+
+    /// Clears the table, removing all values.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Shrinks the capacity of the table as much as possible. It will drop
+    /// down as much as possible while maintaining the internal rules
+    /// and possibly leaving some space in accordance with the resize policy.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn shrink_to_fit(&mut self, hasher: impl Fn(&T) -> u64) {
+        self.table.shrink_to(0, hasher);
+    }
+
+    /// Shrinks the capacity of the table with a lower limit. It will drop
+    /// down no lower than the supplied limit while maintaining the internal rules
+    /// and possibly leaving some space in accordance with the resize policy.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    ///
+    /// If the current capacity is less than the lower limit, this is a no-op.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn shrink_to(&mut self, min_capacity: usize, hasher: impl Fn(&T) -> u64) {
+        self.table.shrink_to(min_capacity, hasher);
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the `HashTable`. The collection may reserve more space to avoid
+    /// frequent reallocations.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new allocation size overflows [`usize`].
+    ///
+    /// [`usize`]: https://doc.rust-lang.org/std/primitive.usize.html
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn reserve(&mut self, additional: usize, hasher: impl Fn(&T) -> u64) {
+        self.table.reserve(additional, hasher);
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be inserted
+    /// in the given `HashTable`. The collection may reserve more space to avoid
+    /// frequent reallocations.
+    ///
+    /// `hasher` is called if entries need to be moved or copied to a new table.
+    /// This must return the same hash value that each entry was inserted with.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows, or the allocator reports a failure, then an error
+    /// is returned.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn try_reserve(
+        &mut self,
+        additional: usize,
+        hasher: impl Fn(&T) -> u64,
+    ) -> Result<(), TryReserveError> {
+        self.table.try_reserve(additional, hasher)
+    }
+
+    /// Returns the number of elements the table can hold without reallocating.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    /// Returns the number of elements in the table.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Returns `true` if the table contains no elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// An iterator visiting all elements in arbitrary order.
+    /// The iterator element type is `&'a T`.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn iter(&self) -> Iter<'_, T> {
+        // Here we tie the lifetime of self to the iter.
+        unsafe {
+            Iter {
+                inner: self.table.iter(),
+                marker: PhantomData,
+            }
+        }
+    }
+
+    /// An iterator visiting all elements in arbitrary order,
+    /// with mutable references to the elements.
+    /// The iterator element type is `&'a mut T`.
+    ///
+    /// The elements must keep the hash they were inserted with.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        // Here we tie the lifetime of self to the iter.
+        unsafe {
+            IterMut {
+                inner: self.table.iter(),
+                marker: PhantomData,
+            }
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all elements `e` such that `f(&mut e)` returns `false`.
+    /// The elements are visited in unsorted (and unspecified) order.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn retain(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        // Here we only use `iter` as a temporary, preventing use-after-free
+        unsafe {
+            for item in self.table.iter() {
+                if !f(item.as_mut()) {
+                    self.table.erase(item);
+                }
+            }
+        }
+    }
+
+    /// Clears the table, returning all elements in an iterator. Keeps the
+    /// allocated memory for reuse.
+    ///
+    /// If the returned iterator is dropped before being fully consumed, it
+    /// drops the remaining elements.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn drain(&mut self) -> Drain<'_, T, A> {
+        Drain {
+            inner: self.table.drain(),
+        }
+    }
+
+    /// Attempts to get mutable references to `N` values in the table at once.
+    ///
+    /// The `eq` argument should be a closure such that `eq(i, k)` returns true
+    /// if `k` is equal to the `i`th element to be looked up.
+    ///
+    /// Returns `None` if any of the elements are missing, or if two hashes
+    /// resolve to the same element.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        hashes: [u64; N],
+        eq: impl FnMut(usize, &T) -> bool,
+    ) -> Option<[&'_ mut T; N]> {
+        self.table.get_many_mut(hashes, eq)
+    }
+}
+
+impl<T, A> Default for HashTable<T, A>
+where
+    A: Allocator + Clone + Default,
+{
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn default() -> Self {
+        Self::new_in(Default::default())
+    }
+}
+
+impl<T, A> Clone for HashTable<T, A>
+where
+    T: Clone,
+    A: Allocator + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.table.clone_from(&source.table);
+    }
+}
+
+impl<T, A> Debug for HashTable<T, A>
+where
+    T: Debug,
+    A: Allocator + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A: Allocator + Clone> IntoIterator for HashTable<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter {
+            inner: self.table.into_iter(),
+        }
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> IntoIterator for &'a HashTable<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> IntoIterator for &'a mut HashTable<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// A view into a single entry in a table, which may either be vacant or occupied.
+///
+/// This `enum` is constructed from the [`entry`] method on [`HashTable`].
+///
+/// [`HashTable`]: struct.HashTable.html
+/// [`entry`]: struct.HashTable.html#method.entry
+pub enum Entry<'a, T, A = Global>
+where
+    A: Allocator + Clone,
+{
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, T, A>),
+
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, T, A>),
+}
+
+impl<T: Debug, A: Allocator + Clone> Debug for Entry<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Entry::Vacant(ref v) => f.debug_tuple("Entry").field(v).finish(),
+            Entry::Occupied(ref o) => f.debug_tuple("Entry").field(o).finish(),
+        }
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> Entry<'a, T, A> {
+    /// Sets the value of the entry, replacing any existing value if there is
+    /// one, and returns an [`OccupiedEntry`].
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert(self, value: T) -> OccupiedEntry<'a, T, A> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                *entry.get_mut() = value;
+                entry
+            }
+            Entry::Vacant(entry) => entry.insert_entry(value),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default if empty, and
+    /// returns a mutable reference to the value in the entry.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default
+    /// function if empty, and returns a mutable reference to the value in the
+    /// entry.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the table.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut T),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, T: Default, A: Allocator + Clone> Entry<'a, T, A> {
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn or_default(self) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Default::default()),
+        }
+    }
+}
+
+/// A view into an occupied entry in a `HashTable`.
+/// It is part of the [`Entry`] enum.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct OccupiedEntry<'a, T, A: Allocator + Clone = Global> {
+    hash: u64,
+    bucket: Bucket<T>,
+    table: &'a mut HashTable<T, A>,
+}
+
+unsafe impl<T, A> Send for OccupiedEntry<'_, T, A>
+where
+    T: Send,
+    A: Send + Allocator + Clone,
+{
+}
+unsafe impl<T, A> Sync for OccupiedEntry<'_, T, A>
+where
+    T: Sync,
+    A: Sync + Allocator + Clone,
+{
+}
+
+impl<T: Debug, A: Allocator + Clone> Debug for OccupiedEntry<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> OccupiedEntry<'a, T, A> {
+    /// Gets a reference to the value in the entry.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get(&self) -> &T {
+        unsafe { self.bucket.as_ref() }
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    ///
+    /// If you need a reference to the `OccupiedEntry` which may outlive the
+    /// destruction of the `Entry` value, see [`into_mut`].
+    ///
+    /// [`into_mut`]: #method.into_mut
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { self.bucket.as_mut() }
+    }
+
+    /// Converts the `OccupiedEntry` into a mutable reference to the value in
+    /// the entry with a lifetime bound to the table itself.
+    ///
+    /// If you need multiple references to the `OccupiedEntry`, see [`get_mut`].
+    ///
+    /// [`get_mut`]: #method.get_mut
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn into_mut(self) -> &'a mut T {
+        unsafe { self.bucket.as_mut() }
+    }
+
+    /// Returns the hash which the entry was looked up or inserted with.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    /// Keeps the allocated memory for reuse.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn remove(self) -> T {
+        unsafe { self.table.table.remove(self.bucket) }
+    }
+
+    /// Converts the `OccupiedEntry` into a mutable reference to the underlying
+    /// table.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn into_table(self) -> &'a mut HashTable<T, A> {
+        self.table
+    }
+}
+
+/// A view into a vacant entry in a `HashTable`.
+/// It is part of the [`Entry`] enum.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct VacantEntry<'a, T, A: Allocator + Clone = Global> {
+    hash: u64,
+    insert_slot: InsertSlot,
+    table: &'a mut HashTable<T, A>,
+}
+
+impl<T: Debug, A: Allocator + Clone> Debug for VacantEntry<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VacantEntry")
+    }
+}
+
+impl<'a, T, A: Allocator + Clone> VacantEntry<'a, T, A> {
+    /// Sets the value of the entry with the hash it was looked up with, and
+    /// returns a mutable reference to it.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert(self, value: T) -> &'a mut T {
+        self.insert_entry(value).into_mut()
+    }
+
+    /// Sets the value of the entry with the hash it was looked up with, and
+    /// returns an [`OccupiedEntry`].
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn insert_entry(self, value: T) -> OccupiedEntry<'a, T, A> {
+        // `entry` reserved space for this insertion and nothing can have been
+        // inserted since, so the slot is still free for this hash.
+        let bucket = unsafe {
+            self.table
+                .table
+                .insert_in_slot(self.hash, self.insert_slot, value)
+        };
+        OccupiedEntry {
+            hash: self.hash,
+            bucket,
+            table: self.table,
+        }
+    }
+
+    /// Returns the hash which the entry was looked up with.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Converts the `VacantEntry` into a mutable reference to the underlying
+    /// table.
+    #[cfg_attr(feature = "inline-more", inline)]
+    pub fn into_table(self) -> &'a mut HashTable<T, A> {
+        self.table
+    }
+}
+
+/// An iterator over the entries of a `HashTable` in arbitrary order.
+/// The iterator element type is `&'a T`.
+///
+/// This `struct` is created by the [`iter`] method on [`HashTable`]. See its
+/// documentation for more.
+///
+/// [`iter`]: struct.HashTable.html#method.iter
+/// [`HashTable`]: struct.HashTable.html
+pub struct Iter<'a, T> {
+    inner: RawIter<T>,
+    marker: PhantomData<&'a T>,
+}
+
+// FIXME(#26925) Remove in favor of `#[derive(Clone)]`
+impl<T> Clone for Iter<'_, T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Debug> Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next(&mut self) -> Option<&'a T> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next() {
+            Some(bucket) => Some(unsafe { bucket.as_ref() }),
+            None => None,
+        }
+    }
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T> ExactSizeIterator for Iter<'_, T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// A mutable iterator over the entries of a `HashTable` in arbitrary order.
+/// The iterator element type is `&'a mut T`.
+///
+/// This `struct` is created by the [`iter_mut`] method on [`HashTable`]. See its
+/// documentation for more.
+///
+/// [`iter_mut`]: struct.HashTable.html#method.iter_mut
+/// [`HashTable`]: struct.HashTable.html
+pub struct IterMut<'a, T> {
+    inner: RawIter<T>,
+    // To ensure invariance with respect to T
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<T: Debug> Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter {
+            inner: self.inner.clone(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next(&mut self) -> Option<&'a mut T> {
+        // Avoid `Option::map` because it bloats LLVM IR.
+        match self.inner.next() {
+            Some(bucket) => Some(unsafe { bucket.as_mut() }),
+            None => None,
+        }
+    }
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T> ExactSizeIterator for IterMut<'_, T> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// An owning iterator over the entries of a `HashTable` in arbitrary order.
+/// The iterator element type is `T`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`HashTable`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+/// The table cannot be used after calling that method.
+///
+/// [`into_iter`]: struct.HashTable.html#method.into_iter
+/// [`HashTable`]: struct.HashTable.html
+/// [`IntoIterator`]: https://doc.rust-lang.org/core/iter/trait.IntoIterator.html
+pub struct IntoIter<T, A: Allocator + Clone = Global> {
+    inner: RawIntoIter<T, A>,
+}
+
+impl<T: Debug, A: Allocator + Clone> Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter {
+            inner: self.inner.iter(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+impl<T, A: Allocator + Clone> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T, A: Allocator + Clone> ExactSizeIterator for IntoIter<T, A> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+impl<T, A: Allocator + Clone> FusedIterator for IntoIter<T, A> {}
+
+/// A draining iterator over the items of a `HashTable`.
+///
+/// This `struct` is created by the [`drain`] method on [`HashTable`].
+/// See its documentation for more.
+///
+/// [`HashTable`]: struct.HashTable.html
+/// [`drain`]: struct.HashTable.html#method.drain
+pub struct Drain<'a, T, A: Allocator + Clone = Global> {
+    inner: RawDrain<'a, T, A>,
+}
+
+impl<T: Debug, A: Allocator + Clone> Debug for Drain<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let iter = Iter {
+            inner: self.inner.iter(),
+            marker: PhantomData,
+        };
+        f.debug_list().entries(iter).finish()
+    }
+}
+
+impl<T, A: Allocator + Clone> Iterator for Drain<'_, T, A> {
+    type Item = T;
+
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
+    }
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<T, A: Allocator + Clone> ExactSizeIterator for Drain<'_, T, A> {
+    #[cfg_attr(feature = "inline-more", inline)]
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+impl<T, A: Allocator + Clone> FusedIterator for Drain<'_, T, A> {}
+
+#[cfg(test)]
+mod test_table {
+    use super::{Entry, HashTable};
+    use crate::hash_map::DefaultHashBuilder;
+    use core::hash::{BuildHasher, Hash, Hasher};
+    use std::string::String;
+    use std::vec::Vec;
+
+    fn hash_one<H: Hash + ?Sized>(hash_builder: &DefaultHashBuilder, value: &H) -> u64 {
+        let mut state = hash_builder.build_hasher();
+        value.hash(&mut state);
+        state.finish()
+    }
+
+    #[test]
+    fn test_iterators_are_fused() {
+        use super::{Drain, IntoIter, Iter, IterMut};
+        use crate::raw::Allocator;
+        use core::iter::FusedIterator;
+
+        fn assert_fused<I: FusedIterator>() {}
+
+        fn check<A: Allocator + Clone>() {
+            assert_fused::<Iter<'_, u32>>();
+            assert_fused::<IterMut<'_, u32>>();
+            assert_fused::<IntoIter<u32, A>>();
+            assert_fused::<Drain<'_, u32, A>>();
+        }
+
+        check::<crate::raw::Global>();
+    }
+
+    #[test]
+    fn test_interner() {
+        // The table stores indices into `strings`, hashed by the string they
+        // refer to, so it needs the buffer to hash and compare its elements.
+        let hash_builder = DefaultHashBuilder::default();
+        let mut strings: Vec<String> = Vec::new();
+        let mut table: HashTable<usize> = HashTable::new();
+
+        let mut intern = |s: &str| -> usize {
+            let hash = hash_one(&hash_builder, s);
+            let hasher = |&i: &usize| hash_one(&hash_builder, strings[i].as_str());
+            match table.entry(hash, |&i| strings[i] == s, hasher) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    strings.push(s.into());
+                    *entry.insert(strings.len() - 1)
+                }
+            }
+        };
+
+        let words = ["apple", "pear", "apple", "plum", "pear", "apple"];
+        let ids: Vec<usize> = words.iter().map(|w| intern(w)).collect();
+        assert_eq!(ids, [0, 1, 0, 2, 1, 0]);
+        assert_eq!(table.len(), 3);
+        assert_eq!(strings, ["apple", "pear", "plum"]);
+    }
+
+    #[test]
+    fn test_multimap() {
+        let hash_builder = DefaultHashBuilder::default();
+        let hasher = |&(k, _): &(u32, u32)| hash_one(&hash_builder, &k);
+
+        let mut table = HashTable::new();
+        for k in 0..100 {
+            for v in 0..3 {
+                table.insert_unique(hasher(&(k, v)), (k, v), hasher);
+            }
+        }
+        assert_eq!(table.len(), 300);
+
+        // Remove one value of every key through `find_entry`.
+        for k in 0..100 {
+            let entry = table.find_entry(hasher(&(k, 0)), |&(x, v)| x == k && v == 1);
+            assert_eq!(entry.unwrap().remove(), (k, 1));
+            assert!(table
+                .find_entry(hasher(&(k, 0)), |&(x, v)| x == k && v == 1)
+                .is_none());
+        }
+        assert_eq!(table.len(), 200);
+
+        // Both remaining values of each key are still found.
+        for k in 0..100 {
+            for v in [0, 2] {
+                let found = table.find(hasher(&(k, 0)), |&e| e == (k, v));
+                assert_eq!(found, Some(&(k, v)));
+            }
+        }
+
+        for e in table.iter_mut() {
+            e.1 += 10;
+        }
+        table.retain(|&mut (k, _)| k % 2 == 0);
+        assert_eq!(table.len(), 100);
+        assert!(table.iter().all(|&(k, v)| k % 2 == 0 && v >= 10));
+
+        let mut drained: Vec<(u32, u32)> = table.drain().collect();
+        drained.sort_unstable();
+        assert_eq!(drained.len(), 100);
+        assert_eq!(drained[..2], [(0, 10), (0, 12)]);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_entry() {
+        // Elements are `(key, count)` pairs hashed by key only.
+        let hash_builder = DefaultHashBuilder::default();
+        let hash = |k: u32| hash_one(&hash_builder, &k);
+        let hasher = |&(k, _): &(u32, u32)| hash(k);
+
+        let mut table = HashTable::new();
+        for i in 0..10 {
+            table
+                .entry(hash(i), |&(k, _)| k == i, hasher)
+                .or_insert((i, 0))
+                .1 += 100;
+        }
+        for i in 0..20 {
+            table
+                .entry(hash(i), |&(k, _)| k == i, hasher)
+                .and_modify(|e| e.1 += 1000)
+                .or_insert_with(|| (i, i));
+        }
+        assert_eq!(table.len(), 20);
+        for i in 0..20 {
+            let expected = if i < 10 { 1100 } else { i };
+            assert_eq!(table.find(hash(i), |&(k, _)| k == i), Some(&(i, expected)));
+        }
+
+        // Replacing through `Entry::insert` keeps a single element.
+        let entry = table
+            .entry(hash(3), |&(k, _)| k == 3, hasher)
+            .insert((3, 3));
+        assert_eq!(*entry.get(), (3, 3));
+        assert_eq!(entry.into_table().len(), 20);
+
+        match table.entry(hash(5), |&(k, _)| k == 5, hasher) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), (5, 1100)),
+            Entry::Vacant(_) => panic!("5 should be in the table"),
+        }
+        assert_eq!(table.find(hash(5), |&(k, _)| k == 5), None);
+        assert_eq!(table.len(), 19);
+    }
+
+    #[test]
+    fn test_into_iter_and_clone() {
+        let hash_builder = DefaultHashBuilder::default();
+        let hasher = |x: &u32| hash_one(&hash_builder, x);
+
+        let mut table = HashTable::with_capacity(10);
+        let capacity = table.capacity();
+        for i in 0..10 {
+            table.insert_unique(hasher(&i), i, hasher);
+        }
+        assert_eq!(table.capacity(), capacity);
+
+        let clone = table.clone();
+        let mut values: Vec<u32> = table.into_iter().collect();
+        values.sort_unstable();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+        assert!((0..10).all(|i| clone.find(hasher(&i), |&x| x == i).is_some()));
+
+        let mut clone = clone;
+        clone.retain(|&mut x| x < 2);
+        clone.shrink_to_fit(hasher);
+        assert!(clone.capacity() < capacity);
+        assert_eq!(
+            clone.get_many_mut([hasher(&0), hasher(&1)], |i, &x| x as usize == i),
+            Some([&mut 0, &mut 1])
+        );
+    }
+}
@@ -251,6 +251,47 @@ fn map_seq_par_equivalence_collect() {
     assert_eq!(map_par, map_expected);
 }
 
+#[test]
+fn map_seq_par_equivalence_collect_duplicates() {
+    let vec = (0..1000u32).map(|i| (i % 100, i)).collect::<Vec<_>>();
+    let map_seq = vec.iter().copied().collect::<HashMap<_, _>>();
+    let map_par = vec.par_iter().copied().collect::<HashMap<_, _>>();
+
+    assert_eq!(map_seq, map_par);
+    assert_eq!(map_par.len(), 100);
+
+    // The last value produced for each key wins.
+    for (k, v) in &map_par {
+        assert_eq!(*v, 900 + *k);
+    }
+}
+
+#[test]
+fn map_seq_par_equivalence_from_unique_iter() {
+    let map_expected = MAP.clone();
+    let map_par: HashMap<_, _> = HashMap::par_from_unique_iter(MAP_VEC.clone());
+    assert_eq!(map_par, map_expected);
+
+    let map_par = HashMap::<char, u32>::par_from_unique_iter(MAP_VEC_EMPTY.clone());
+    assert_eq!(map_par, MAP_EMPTY.clone());
+
+    // Large enough to be split across many rayon jobs.
+    let vec = (0..100_000u64).map(|i| (i, i * 3)).collect::<Vec<_>>();
+    let map_seq = vec.iter().copied().collect::<HashMap<_, _>>();
+    let map_par: HashMap<_, _> = HashMap::par_from_unique_iter(vec.par_iter().copied());
+    assert_eq!(map_par, map_seq);
+    assert_eq!(map_par.len(), 100_000);
+    assert!(map_par.iter().all(|(&k, &v)| v == k * 3));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic = "duplicate key"]
+fn map_from_unique_iter_duplicates() {
+    let vec = (0..1000u32).map(|i| (i % 100, i)).collect::<Vec<_>>();
+    let _: HashMap<u32, u32> = HashMap::par_from_unique_iter(vec);
+}
+
 lazy_static! {
     static ref MAP_EXISTING_EMPTY: HashMap<char, u32> = HashMap::new();
     static ref MAP_EXISTING: HashMap<char, u32> = {
@@ -311,6 +352,59 @@ fn map_seq_par_equivalence_existing_extend() {
     assert_eq3!(map_seq, map_par, expected);
 }
 
+#[test]
+fn map_seq_par_equivalence_retain() {
+    let mut map_seq = (0..10_000).map(|i| (i, i)).collect::<HashMap<u32, u32>>();
+    let mut map_par = map_seq.clone();
+
+    let keep = |k: &u32, v: &mut u32| {
+        *v *= 2;
+        k % 3 != 0
+    };
+    map_seq.retain(keep);
+    map_par.par_retain(keep);
+
+    assert_eq!(map_seq.len(), 6_666);
+    assert_eq!(map_seq, map_par);
+
+    let mut map_empty = MAP_EMPTY.clone();
+    map_empty.par_retain(|_, _| false);
+    assert_eq!(map_empty, *MAP_EMPTY);
+}
+
+#[test]
+fn map_seq_par_equivalence_eq() {
+    let map_a = (0..100_000)
+        .map(|i| (i, i * 2))
+        .collect::<HashMap<u32, u32>>();
+    let map_b = (0..100_000)
+        .rev()
+        .map(|i| (i, i * 2))
+        .collect::<HashMap<u32, u32>>();
+    assert!(map_a.par_eq(&map_b));
+    assert_eq!(map_a.par_eq(&map_b), map_a == map_b);
+
+    // Same keys, one differing value.
+    let mut map_value = map_b.clone();
+    *map_value.get_mut(&54_321).unwrap() += 1;
+    assert!(!map_a.par_eq(&map_value));
+    assert_eq!(map_a.par_eq(&map_value), map_a == map_value);
+
+    // Same length, one differing key.
+    let mut map_key = map_b.clone();
+    let value = map_key.remove(&54_321).unwrap();
+    map_key.insert(100_000, value);
+    assert_eq!(map_key.len(), map_a.len());
+    assert!(!map_a.par_eq(&map_key));
+    assert_eq!(map_a.par_eq(&map_key), map_a == map_key);
+
+    // Different hashers.
+    let mut map_std = HashMap::with_hasher(std::collections::hash_map::RandomState::new());
+    map_std.extend(map_a.iter().map(|(&k, &v)| (k, v)));
+    assert!(map_a.par_eq(&map_std));
+    assert!(!map_key.par_eq(&map_std));
+}
+
 lazy_static! {
     static ref SET_EMPTY: HashSet<char> = HashSet::new();
     static ref SET: HashSet<char> = {
@@ -405,6 +499,43 @@ fn set_seq_par_equivalence_collect() {
     assert_eq!(set_par, set_expected);
 }
 
+#[test]
+fn set_seq_par_equivalence_collect_duplicates() {
+    // Only the first field takes part in equality so that we can tell which
+    // of several equal values ended up in the set.
+    #[derive(Clone, Copy, Debug)]
+    struct Tagged(u32, u32);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl std::hash::Hash for Tagged {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    let vec = (0..1000u32).map(|i| Tagged(i % 100, i)).collect::<Vec<_>>();
+    let set_seq = vec.iter().copied().collect::<HashSet<_>>();
+    let set_par = vec.par_iter().copied().collect::<HashSet<_>>();
+
+    assert_eq!(set_seq, set_par);
+    assert_eq!(set_par.len(), 100);
+
+    // The first value produced for each key wins.
+    for x in &set_par {
+        assert_eq!(x.0, x.1);
+    }
+    for x in &set_seq {
+        assert_eq!(x.0, x.1);
+    }
+}
+
 lazy_static! {
     static ref SET_EXISTING_EMPTY: HashSet<char> = HashSet::new();
     static ref SET_EXISTING: HashSet<char> = {
@@ -465,6 +596,49 @@ fn set_seq_par_equivalence_existing_extend() {
     assert_eq3!(set_seq, set_par, expected);
 }
 
+#[test]
+fn set_seq_par_equivalence_existing_extend_duplicates() {
+    // Only the first field takes part in equality so that we can tell which
+    // of several equal values ended up in the set.
+    #[derive(Clone, Copy, Debug)]
+    struct Tagged(u32, u32);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl std::hash::Hash for Tagged {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    let existing = (0..50u32).map(|i| Tagged(i, 0)).collect::<HashSet<_>>();
+    let vec = (0..1000u32).map(|i| Tagged(i % 100, i)).collect::<Vec<_>>();
+
+    let mut set_seq = existing.clone();
+    let mut set_par = existing;
+    set_seq.extend(vec.iter().copied());
+    set_par.par_extend(vec.par_iter().copied());
+
+    assert_eq!(set_seq, set_par);
+    assert_eq!(set_par.len(), 100);
+
+    // Values already in the set are kept, and otherwise the first value
+    // produced for each key is inserted.
+    for x in &set_par {
+        if x.0 < 50 {
+            assert_eq!(x.1, 0);
+        } else {
+            assert_eq!(x.0, x.1);
+        }
+    }
+}
+
 lazy_static! {
     static ref SET_A: HashSet<char> = ['a', 'b', 'c', 'd'].iter().copied().collect();
     static ref SET_B: HashSet<char> = ['a', 'b', 'e', 'f'].iter().copied().collect();
@@ -524,6 +698,56 @@ fn set_seq_par_equivalence_intersection() {
     );
 }
 
+#[test]
+fn set_seq_par_equivalence_intersection_unbalanced() {
+    let small = (0..100).step_by(7).collect::<HashSet<u32>>();
+    let large = (0..10_000).step_by(3).collect::<HashSet<u32>>();
+
+    for (a, b) in [(&small, &large), (&large, &small)] {
+        let mut vec_seq = a.intersection(b).copied().collect::<Vec<_>>();
+        let mut vec_par = a.par_intersection(b).copied().collect::<Vec<_>>();
+        vec_seq.sort_unstable();
+        vec_par.sort_unstable();
+
+        let expected = (0..100).step_by(21).collect::<Vec<_>>();
+        assert_eq3!(vec_seq, vec_par, expected);
+    }
+}
+
+#[test]
+fn set_seq_par_equivalence_subset_superset_disjoint() {
+    let small = (0..100).collect::<HashSet<u32>>();
+    let large = (0..10_000).collect::<HashSet<u32>>();
+    let odd = (0..10_000).filter(|i| i % 2 == 1).collect::<HashSet<u32>>();
+    let even_small = (0..100).filter(|i| i % 2 == 0).collect::<HashSet<u32>>();
+    let high = (20_000..30_000).collect::<HashSet<u32>>();
+    let empty = HashSet::new();
+
+    let sets = [&small, &large, &odd, &even_small, &high, &empty];
+    for a in sets {
+        for b in sets {
+            assert_eq!(a.par_is_subset(b), a.is_subset(b));
+            assert_eq!(a.par_is_superset(b), a.is_superset(b));
+            assert_eq!(a.par_is_disjoint(b), a.is_disjoint(b));
+            assert_eq!(a.par_is_disjoint(b), b.par_is_disjoint(a));
+        }
+    }
+
+    // Subset.
+    assert!(small.par_is_subset(&large));
+    assert!(!large.par_is_subset(&small));
+    // Superset.
+    assert!(large.par_is_superset(&small));
+    assert!(!small.par_is_superset(&large));
+    // Disjoint.
+    assert!(odd.par_is_disjoint(&even_small));
+    assert!(high.par_is_disjoint(&large));
+    // Overlapping, but neither contains the other.
+    assert!(!odd.par_is_disjoint(&small));
+    assert!(!odd.par_is_subset(&small));
+    assert!(!odd.par_is_superset(&small));
+}
+
 #[test]
 fn set_seq_par_equivalence_union() {
     let union_ab_seq = SET_A.union(&*SET_B).copied().collect::<HashSet<_>>();
@@ -531,3 +755,53 @@ fn set_seq_par_equivalence_union() {
 
     assert_eq3!(union_ab_seq, union_ab_par, *SET_UNION_AB);
 }
+
+#[test]
+fn set_seq_par_equivalence_extract_if() {
+    let mut set_seq = (0..10_000).collect::<HashSet<u32>>();
+    let mut set_par = set_seq.clone();
+
+    let mut extracted_seq = set_seq.extract_if(|x| x % 3 == 0).collect::<Vec<_>>();
+    let par_iter = set_par.par_extract_if(|x| x % 3 == 0);
+    assert_eq!(par_iter.opt_len(), Some(3_334));
+    let mut extracted_par = par_iter.collect::<Vec<_>>();
+    extracted_seq.sort_unstable();
+    extracted_par.sort_unstable();
+
+    assert_eq!(extracted_seq, extracted_par);
+    assert_eq!(extracted_par.len(), 3_334);
+    assert_eq!(set_seq, set_par);
+    assert_eq!(set_par.len(), 6_666);
+
+    // Elements are removed even if the iterator is never consumed.
+    drop(set_par.par_extract_if(|x| x % 2 == 0));
+    set_seq.retain(|x| x % 2 != 0);
+    assert_eq!(set_seq, set_par);
+
+    let mut set_empty = SET_EMPTY.clone();
+    assert_eq!(set_empty.par_extract_if(|_| true).count(), 0);
+}
+
+#[test]
+fn set_seq_par_equivalence_retain() {
+    let mut set_seq = SET_A.clone();
+    let mut set_par = SET_A.clone();
+
+    set_seq.retain(|c| !SET_B.contains(c));
+    set_par.par_retain(|c| !SET_B.contains(c));
+
+    assert_eq3!(set_seq, set_par, *SET_DIFF_AB);
+}
+
+#[test]
+fn set_par_iter_debug() {
+    let set: HashSet<u32> = [7].iter().copied().collect();
+    assert_eq!(format!("{:?}", set.par_iter()), "[7]");
+    assert_eq!(format!("{:?}", set.clone().into_par_iter()), "[7]");
+
+    let mut set = set;
+    let drain = set.par_drain();
+    assert_eq!(format!("{:?}", drain), "[7]");
+    assert_eq!(drain.collect::<Vec<_>>(), [7]);
+    assert!(set.is_empty());
+}
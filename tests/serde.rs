@@ -63,3 +63,427 @@ fn set_serde_tokens() {
         ],
     );
 }
+
+#[cfg(feature = "raw")]
+#[test]
+fn raw_table_serde_tokens() {
+    use hashbrown::raw::RawTable;
+    use serde::{Deserialize, Deserializer};
+    use serde_test::{assert_de_tokens, assert_ser_tokens};
+
+    fn hasher(x: &u32) -> u64 {
+        u64::from(*x)
+    }
+
+    // The element order of a `RawTable` is unspecified, so compare the
+    // deserialized elements once sorted.
+    #[derive(Debug, PartialEq)]
+    struct Sorted(Vec<u32>);
+
+    impl<'de> Deserialize<'de> for Sorted {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let table = RawTable::deserialize_seq_with_hasher(deserializer, hasher)?;
+            let mut values: Vec<u32> = unsafe { table.iter().map(|b| *b.as_ref()).collect() };
+            values.sort_unstable();
+            Ok(Sorted(values))
+        }
+    }
+
+    let mut table = RawTable::new();
+    assert_ser_tokens(&table, &[Token::Seq { len: Some(0) }, Token::SeqEnd]);
+
+    table.insert(hasher(&10), 10, hasher);
+    assert_ser_tokens(
+        &table,
+        &[Token::Seq { len: Some(1) }, Token::U32(10), Token::SeqEnd],
+    );
+
+    assert_de_tokens(
+        &Sorted(vec![10, 20, 30]),
+        &[
+            Token::Seq { len: Some(3) },
+            Token::U32(30),
+            Token::U32(10),
+            Token::U32(20),
+            Token::SeqEnd,
+        ],
+    );
+}
+
+#[test]
+fn map_serde_as_seq_tokens() {
+    use hashbrown::hash_map::{AsSeq, AsSeqSeed};
+    use serde::de::DeserializeSeed;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Round-trips a map through `AsSeq` and `AsSeqSeed`.
+    #[derive(Debug, PartialEq)]
+    struct SeqMap(FnvHashMap<char, u32>);
+
+    impl Serialize for SeqMap {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            AsSeq(&self.0).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SeqMap {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            AsSeqSeed::new().deserialize(deserializer).map(SeqMap)
+        }
+    }
+
+    assert_tokens(
+        &SeqMap(FnvHashMap::default()),
+        &[Token::Seq { len: Some(0) }, Token::SeqEnd],
+    );
+
+    let mut map = FnvHashMap::default();
+    map.insert('b', 20);
+    map.insert('a', 10);
+    map.insert('c', 30);
+
+    assert_tokens(
+        &SeqMap(map.clone()),
+        &[
+            Token::Seq { len: Some(3) },
+            Token::Tuple { len: 2 },
+            Token::Char('a'),
+            Token::U32(10),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Char('c'),
+            Token::U32(30),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Char('b'),
+            Token::U32(20),
+            Token::TupleEnd,
+            Token::SeqEnd,
+        ],
+    );
+
+    // Later duplicates overwrite earlier ones.
+    map.insert('a', 40);
+    serde_test::assert_de_tokens(
+        &SeqMap(map),
+        &[
+            Token::Seq { len: Some(4) },
+            Token::Tuple { len: 2 },
+            Token::Char('a'),
+            Token::U32(10),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Char('b'),
+            Token::U32(20),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Char('c'),
+            Token::U32(30),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Char('a'),
+            Token::U32(40),
+            Token::TupleEnd,
+            Token::SeqEnd,
+        ],
+    );
+}
+
+/// A minimal length-prefixed binary format in the style of bincode. Like
+/// bincode it is not self-describing, so it only supports the types these
+/// tests need and `deserialize_any` is an error.
+mod binary {
+    use serde::de::value::Error;
+    use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+    use serde::ser::{self, Impossible, Serialize};
+
+    pub fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut serializer = Serializer(Vec::new());
+        value.serialize(&mut serializer).unwrap();
+        serializer.0
+    }
+
+    pub fn from_bytes_seed<'de, T: DeserializeSeed<'de>>(
+        seed: T,
+        bytes: &'de [u8],
+    ) -> Result<T::Value, Error> {
+        let mut deserializer = Deserializer(bytes);
+        let value = seed.deserialize(&mut deserializer)?;
+        if deserializer.0.is_empty() {
+            Ok(value)
+        } else {
+            Err(de::Error::custom("trailing bytes"))
+        }
+    }
+
+    struct Serializer(Vec<u8>);
+
+    macro_rules! unsupported {
+        ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {$(
+            fn $method(self, $(_: $arg),*) -> Result<$ret, Error> {
+                Err(ser::Error::custom(stringify!($method)))
+            }
+        )*};
+    }
+
+    impl ser::Serializer for &mut Serializer {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Self;
+        type SerializeTuple = Self;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Impossible<(), Error>;
+        type SerializeStruct = Impossible<(), Error>;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            Ok(())
+        }
+
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            self.serialize_u32(v as u32)
+        }
+
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+            let len = len.ok_or_else(|| ser::Error::custom("unknown length"))?;
+            self.0.extend_from_slice(&(len as u64).to_le_bytes());
+            Ok(self)
+        }
+
+        fn serialize_tuple(self, _: usize) -> Result<Self, Error> {
+            Ok(self)
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<(), Error> {
+            Err(ser::Error::custom("serialize_some"))
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(ser::Error::custom("serialize_newtype_struct"))
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            Err(ser::Error::custom("serialize_newtype_variant"))
+        }
+
+        unsupported! {
+            serialize_bool(bool) -> ();
+            serialize_i8(i8) -> ();
+            serialize_i16(i16) -> ();
+            serialize_i32(i32) -> ();
+            serialize_i64(i64) -> ();
+            serialize_u8(u8) -> ();
+            serialize_u16(u16) -> ();
+            serialize_u64(u64) -> ();
+            serialize_f32(f32) -> ();
+            serialize_f64(f64) -> ();
+            serialize_str(&str) -> ();
+            serialize_bytes(&[u8]) -> ();
+            serialize_none() -> ();
+            serialize_unit() -> ();
+            serialize_unit_struct(&'static str) -> ();
+            serialize_unit_variant(&'static str, u32, &'static str) -> ();
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+            serialize_struct_variant(&'static str, u32, &'static str, usize)
+                -> Self::SerializeStructVariant;
+        }
+    }
+
+    impl ser::SerializeSeq for &mut Serializer {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut **self)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    impl ser::SerializeTuple for &mut Serializer {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut **self)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    struct Deserializer<'de>(&'de [u8]);
+
+    impl<'de> Deserializer<'de> {
+        fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+            if self.0.len() < N {
+                return Err(de::Error::custom("unexpected end of input"));
+            }
+            let (bytes, rest) = self.0.split_at(N);
+            self.0 = rest;
+            let mut array = [0; N];
+            array.copy_from_slice(bytes);
+            Ok(array)
+        }
+
+        fn read_u32(&mut self) -> Result<u32, Error> {
+            self.take().map(u32::from_le_bytes)
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+        type Error = Error;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+            Err(de::Error::custom("the format is not self-describing"))
+        }
+
+        fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_u32(self.read_u32()?)
+        }
+
+        fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            let c = char::from_u32(self.read_u32()?)
+                .ok_or_else(|| de::Error::custom("invalid char"))?;
+            visitor.visit_char(c)
+        }
+
+        fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            let len = u64::from_le_bytes(self.take()?) as usize;
+            visitor.visit_seq(Access { de: self, len })
+        }
+
+        fn deserialize_tuple<V: Visitor<'de>>(
+            self,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(Access { de: self, len })
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u64 u128 f32 f64 str string bytes
+            byte_buf option unit unit_struct newtype_struct tuple_struct map
+            struct enum identifier ignored_any
+        }
+    }
+
+    struct Access<'a, 'de> {
+        de: &'a mut Deserializer<'de>,
+        len: usize,
+    }
+
+    impl<'de, 'a> SeqAccess<'de> for Access<'a, 'de> {
+        type Error = Error;
+
+        fn next_element_seed<T: DeserializeSeed<'de>>(
+            &mut self,
+            seed: T,
+        ) -> Result<Option<T::Value>, Error> {
+            if self.len == 0 {
+                return Ok(None);
+            }
+            self.len -= 1;
+            seed.deserialize(&mut *self.de).map(Some)
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(self.len)
+        }
+    }
+}
+
+#[test]
+fn map_serde_as_seq_binary_round_trip() {
+    use hashbrown::hash_map::{AsSeq, AsSeqSeed};
+
+    let map: FnvHashMap<char, u32> = ('a'..='z').zip(0..).collect();
+    let bytes = binary::to_bytes(&AsSeq(&map));
+    // A length prefix followed by one 4-byte key and one 4-byte value per
+    // entry, with no map framing.
+    assert_eq!(bytes.len(), 8 + map.len() * 8);
+
+    let round_trip: FnvHashMap<char, u32> =
+        binary::from_bytes_seed(AsSeqSeed::new(), &bytes).unwrap();
+    assert_eq!(round_trip, map);
+
+    let empty = FnvHashMap::<char, u32>::default();
+    let bytes = binary::to_bytes(&AsSeq(&empty));
+    let round_trip: FnvHashMap<char, u32> =
+        binary::from_bytes_seed(AsSeqSeed::new(), &bytes).unwrap();
+    assert!(round_trip.is_empty());
+
+    // Truncated input is an error rather than a partial map.
+    let bytes = binary::to_bytes(&AsSeq(&map));
+    let truncated: Result<FnvHashMap<char, u32>, _> =
+        binary::from_bytes_seed(AsSeqSeed::new(), &bytes[..bytes.len() - 1]);
+    assert!(truncated.is_err());
+}
+
+#[test]
+fn map_serde_deserialize_in_place_reuses_allocation() {
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::Deserialize;
+
+    let mut map = FnvHashMap::<u32, u32>::with_capacity_and_hasher(64, Default::default());
+    let capacity = map.capacity();
+
+    for round in 0..2 {
+        let entries = (0..48).map(|i| (i, i * 10 + round));
+        let deserializer = MapDeserializer::<_, Error>::new(entries);
+        FnvHashMap::deserialize_in_place(deserializer, &mut map).unwrap();
+
+        assert_eq!(map.len(), 48);
+        assert!((0..48).all(|i| map[&i] == i * 10 + round));
+        assert_eq!(map.capacity(), capacity);
+    }
+}
+
+#[test]
+fn set_serde_deserialize_in_place_reuses_allocation() {
+    use serde::de::value::{Error, SeqDeserializer};
+    use serde::Deserialize;
+
+    let mut set = FnvHashSet::<u32>::with_capacity_and_hasher(64, Default::default());
+    let capacity = set.capacity();
+
+    for round in 0..2 {
+        let values = (0..48).map(|i| i + round);
+        let deserializer = SeqDeserializer::<_, Error>::new(values);
+        FnvHashSet::deserialize_in_place(deserializer, &mut set).unwrap();
+
+        assert_eq!(set.len(), 48);
+        assert!((0..48).all(|i| set.contains(&(i + round))));
+        assert_eq!(set.capacity(), capacity);
+    }
+}